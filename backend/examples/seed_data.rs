@@ -3,8 +3,8 @@
 //! Run with: cargo run --example seed_data
 
 use document_path_db::models::{
-    BusinessTask, CounterScope, Department, DeptCode, DocumentType, PathGenerationRule,
-    RuleComponent, Section, SectionCode, User,
+    BusinessTask, CounterScope, Department, DeptCode, DocumentType, PathGenerationRule, Section,
+    SectionCode, User,
 };
 use document_path_db::storage::{business_task, department, document_type, init_db_pool, section, user};
 
@@ -85,19 +85,11 @@ async fn main() -> anyhow::Result<()> {
     // Step 4: Create Document Types
     println!("\n4. Creating Document Types...");
     
-    // Document type A: Contract documents
-    let rule_a = PathGenerationRule::new(
-        vec![
-            RuleComponent::TypeName,
-            RuleComponent::DeptCode,
-            RuleComponent::SectionCode,
-            RuleComponent::Year { digits: 2 },
-            RuleComponent::Month,
-            RuleComponent::AutoIncrement,
-        ],
+    // Document type A: Contract documents (e.g., AGI2509001)
+    let rule_a = PathGenerationRule::from_pattern(
+        "[TYPE][DEPT][SECT][YYMM][NNN]",
         CounterScope::TypeDeptSectionYearMonth,
-        3,
-    );
+    )?;
     
     let type_a = DocumentType::new(
         "A",
@@ -107,19 +99,13 @@ async fn main() -> anyhow::Result<()> {
     );
     
     document_type::create_document_type(&pool, &type_a).await?;
-    println!("   ✓ Document type A (契約文書) created");
-    
-    // Document type りん議: Approval documents
-    let rule_ringi = PathGenerationRule::new(
-        vec![
-            RuleComponent::TypeName,
-            RuleComponent::SectionCode,
-            RuleComponent::Year { digits: 2 },
-            RuleComponent::AutoIncrement,
-        ],
+    println!("   ✓ Document type A (契約文書) created: {}", type_a.generation_rule.to_pattern());
+    
+    // Document type りん議: Approval documents (e.g., りん議I-25009)
+    let rule_ringi = PathGenerationRule::from_pattern(
+        "[TYPE][SECT]-[YY][NNN]",
         CounterScope::TypeSectionYear,
-        3,
-    ).with_separators(vec!["-".to_string()]);
+    )?;
     
     let type_ringi = DocumentType::new(
         "りん議",
@@ -129,18 +115,13 @@ async fn main() -> anyhow::Result<()> {
     );
     
     document_type::create_document_type(&pool, &type_ringi).await?;
-    println!("   ✓ Document type りん議 created");
-    
-    // Document type 教育: Training documents
-    let rule_edu = PathGenerationRule::new(
-        vec![
-            RuleComponent::TypeName,
-            RuleComponent::Year { digits: 2 },
-            RuleComponent::AutoIncrement,
-        ],
+    println!("   ✓ Document type りん議 created: {}", type_ringi.generation_rule.to_pattern());
+    
+    // Document type 教育: Training documents (e.g., 教育-25-001)
+    let rule_edu = PathGenerationRule::from_pattern(
+        "[TYPE]-[YY]-[NNN]",
         CounterScope::TypeAndYear,
-        3,
-    ).with_separators(vec!["-".to_string()]);
+    )?;
     
     let type_edu = DocumentType::new(
         "教育",
@@ -150,7 +131,7 @@ async fn main() -> anyhow::Result<()> {
     );
     
     document_type::create_document_type(&pool, &type_edu).await?;
-    println!("   ✓ Document type 教育 created");
+    println!("   ✓ Document type 教育 created: {}", type_edu.generation_rule.to_pattern());
 
     // Step 5: Create Business Tasks
     println!("\n5. Creating Business Tasks...");
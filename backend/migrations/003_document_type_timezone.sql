@@ -0,0 +1,6 @@
+-- Business timezone override per document type
+-- Migration: 003_document_type_timezone
+-- Date: 2025-10-01
+
+-- IANA timezone name (e.g., "Asia/Tokyo"); NULL = deployment default
+ALTER TABLE document_types ADD COLUMN timezone TEXT;
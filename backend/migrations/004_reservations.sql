@@ -0,0 +1,31 @@
+-- Number reservations (reserve now, attach the file later)
+-- Migration: 004_reservations
+-- Date: 2025-10-01
+
+-- Reservations table
+-- A reservation consumes a counter value immediately; the number is either
+-- confirmed into a document, released by the user, or expired automatically.
+CREATE TABLE IF NOT EXISTS reservations (
+    id TEXT PRIMARY KEY NOT NULL,
+    document_number TEXT UNIQUE NOT NULL,
+    document_type_code TEXT NOT NULL,
+    department_code CHAR(1) NOT NULL,
+    section_code CHAR(1) NOT NULL,
+    user_id TEXT NOT NULL,
+    scope_key TEXT NOT NULL,
+    counter_value INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'reserved',  -- reserved, confirmed, released, expired
+    document_id TEXT,                         -- set when confirmed
+    reserved_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    resolved_at TEXT,                         -- when confirmed/released/expired
+    FOREIGN KEY (document_type_code) REFERENCES document_types(code),
+    FOREIGN KEY (department_code) REFERENCES departments(code),
+    FOREIGN KEY (section_code) REFERENCES sections(code),
+    FOREIGN KEY (user_id) REFERENCES users(id),
+    FOREIGN KEY (document_id) REFERENCES documents(id)
+);
+
+-- Index for the expiry sweep
+CREATE INDEX IF NOT EXISTS idx_reservations_status_expires
+ON reservations(status, expires_at);
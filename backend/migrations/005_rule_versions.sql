@@ -0,0 +1,43 @@
+-- Versioned generation rules with effective dates
+-- Migration: 005_rule_versions
+-- Date: 2025-10-01
+
+-- Each generation rule row is one immutable version of a document type's rule.
+-- effective_from is the first business date the version applies to
+-- (NULL = since the document type was created).
+ALTER TABLE generation_rules ADD COLUMN document_type_code TEXT;
+ALTER TABLE generation_rules ADD COLUMN effective_from TEXT;
+
+UPDATE generation_rules
+SET document_type_code = (
+    SELECT code FROM document_types
+    WHERE document_types.generation_rule_id = generation_rules.id
+);
+
+CREATE INDEX IF NOT EXISTS idx_generation_rules_type_effective
+ON generation_rules(document_type_code, effective_from);
+
+-- Versions are never edited; schedule a new version instead
+CREATE TRIGGER IF NOT EXISTS trg_generation_rules_immutable
+BEFORE UPDATE ON generation_rules
+BEGIN
+    SELECT RAISE(ABORT, 'generation rule versions are immutable');
+END;
+
+-- Rule version that produced each auto-generated number (NULL = manual number)
+ALTER TABLE documents ADD COLUMN generation_rule_id INTEGER REFERENCES generation_rules(id);
+
+UPDATE documents
+SET generation_rule_id = (
+    SELECT generation_rule_id FROM document_types
+    WHERE document_types.code = documents.document_type_code
+)
+WHERE generated = 1;
+
+ALTER TABLE reservations ADD COLUMN generation_rule_id INTEGER REFERENCES generation_rules(id);
+
+UPDATE reservations
+SET generation_rule_id = (
+    SELECT generation_rule_id FROM document_types
+    WHERE document_types.code = reservations.document_type_code
+);
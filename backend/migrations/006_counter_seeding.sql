@@ -0,0 +1,7 @@
+-- Counter seeding (continue numbering from an external ledger)
+-- Migration: 006_counter_seeding
+-- Date: 2025-10-01
+
+-- Counter values up to seeded_value were issued outside this system
+-- (ledger import or a rule's starting value) and are not reported as missing
+ALTER TABLE counters ADD COLUMN seeded_value INTEGER NOT NULL DEFAULT 0;
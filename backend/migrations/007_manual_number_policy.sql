@@ -0,0 +1,10 @@
+-- Manual number policy per document type
+-- Migration: 007_manual_number_policy
+-- Date: 2025-10-01
+
+-- How manual numbers are checked against the type's rule: off, warn or reject
+ALTER TABLE document_types ADD COLUMN manual_number_mode TEXT NOT NULL DEFAULT 'off'
+    CHECK (manual_number_mode IN ('off', 'warn', 'reject'));
+
+-- Whether an accepted manual number on the auto sequence advances the counter past it
+ALTER TABLE document_types ADD COLUMN manual_number_bump INTEGER NOT NULL DEFAULT 0;
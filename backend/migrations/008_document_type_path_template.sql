@@ -0,0 +1,7 @@
+-- File path template for auto-generated documents
+-- Migration: 008_document_type_path_template
+-- Date: 2025-10-01
+
+-- Template such as '{root}/{yyyy}/{dept}{section}/{number}.{ext}';
+-- NULL keeps the root directory as the file path
+ALTER TABLE document_types ADD COLUMN path_template TEXT;
@@ -0,0 +1,23 @@
+-- Canonical path keys and per-type path uniqueness policy
+-- Migration: 009_path_uniqueness
+-- Date: 2025-10-01
+
+-- Comparison key of the canonical file path (lower-cased for Windows paths).
+-- Rows written before this migration get an approximate key; the duplicate
+-- path report re-canonicalises every path and does not rely on it.
+ALTER TABLE documents ADD COLUMN path_key TEXT;
+
+UPDATE documents
+SET path_key = CASE
+    WHEN file_path LIKE '\\%' OR file_path LIKE '//%' OR substr(file_path, 2, 1) = ':'
+        THEN lower(replace(file_path, '/', '\'))
+    ELSE file_path
+END;
+
+CREATE INDEX IF NOT EXISTS idx_documents_path_key
+ON documents(path_key)
+WHERE deleted = 0;
+
+-- What happens when two live documents share a path: allow, warn or reject
+ALTER TABLE document_types ADD COLUMN path_uniqueness TEXT NOT NULL DEFAULT 'allow'
+    CHECK (path_uniqueness IN ('allow', 'warn', 'reject'));
@@ -0,0 +1,7 @@
+-- Per-type policy for document paths outside the type's root directory
+-- Migration: 010_path_containment
+-- Date: 2025-10-01
+
+-- Whether file paths must lie under root_directory: off, warn or strict
+ALTER TABLE document_types ADD COLUMN path_containment TEXT NOT NULL DEFAULT 'off'
+    CHECK (path_containment IN ('off', 'warn', 'strict'));
@@ -0,0 +1,24 @@
+-- On-disk existence checks of document files (optional verifier)
+-- Migration: 011_file_checks
+-- Date: 2025-10-01
+
+-- Last verification result per document
+-- file_path is the path that was checked, so a result goes stale once the
+-- document moves. size and modified_at keep the values of the last time the
+-- file was present.
+CREATE TABLE IF NOT EXISTS file_checks (
+    document_id TEXT PRIMARY KEY NOT NULL,
+    file_path TEXT NOT NULL,
+    status TEXT NOT NULL
+        CHECK (status IN ('present', 'missing', 'inaccessible', 'unmapped')),
+    size INTEGER,
+    modified_at TEXT,
+    last_seen_at TEXT,                        -- last check that found the file
+    checked_at TEXT NOT NULL,
+    error TEXT,                               -- I/O error when inaccessible
+    FOREIGN KEY (document_id) REFERENCES documents(id)
+);
+
+-- Index for the missing files query
+CREATE INDEX IF NOT EXISTS idx_file_checks_status
+ON file_checks(status);
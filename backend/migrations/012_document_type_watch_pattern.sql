@@ -0,0 +1,8 @@
+-- File name pattern for the filesystem watcher
+-- Migration: 012_document_type_watch_pattern
+-- Date: 2025-10-01
+
+-- Pattern such as '*.pdf' or '{number}.pdf'; new files under the root
+-- directory whose name matches are registered automatically.
+-- NULL leaves the type unwatched
+ALTER TABLE document_types ADD COLUMN watch_pattern TEXT;
@@ -0,0 +1,7 @@
+-- Business task of a reservation
+-- Migration: 013_reservation_business_task
+-- Date: 2025-10-01
+
+-- Task the number was drawn for (counter scopes keyed on the task);
+-- the confirmed document is filed under the same task
+ALTER TABLE reservations ADD COLUMN business_task_id TEXT REFERENCES business_tasks(id);
@@ -0,0 +1,72 @@
+//! GET /api/counters, PUT /api/counters/{scope_key}, GET/POST /api/counters/reconcile
+
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::error::Result;
+use crate::models::Counter;
+use crate::services::clock::GenerationClock;
+use crate::services::counter_service::{self, CounterSeed, ReconcileReport};
+
+#[derive(Debug, Deserialize)]
+pub struct SeedCounterRequest {
+    /// Next counter value to hand out in the scope
+    pub next_value: i64,
+}
+
+/// GET /api/counters - List all counters
+pub async fn list_counters(State(pool): State<SqlitePool>) -> Result<Json<Vec<Counter>>> {
+    let counters = counter_service::list_counters(&pool).await?;
+    Ok(Json(counters))
+}
+
+/// PUT /api/counters/{scope_key} - Seed a counter to continue at a given value
+pub async fn seed_counter(
+    State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
+    Path(scope_key): Path<String>,
+    Json(req): Json<SeedCounterRequest>,
+) -> Result<Json<CounterSeed>> {
+    let seed = counter_service::seed_counter(&pool, &clock, &scope_key, req.next_value).await?;
+    Ok(Json(seed))
+}
+
+/// GET /api/counters/reconcile - Report counters behind their documents (no changes)
+pub async fn check_counters(
+    State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
+) -> Result<Json<ReconcileReport>> {
+    let report = counter_service::reconcile_counters(&pool, &clock, false).await?;
+    Ok(Json(report))
+}
+
+/// POST /api/counters/reconcile - Raise counters behind their documents
+pub async fn reconcile_counters(
+    State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
+) -> Result<Json<ReconcileReport>> {
+    let report = counter_service::reconcile_counters(&pool, &clock, true).await?;
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_counters_signature() {
+        // Compile-time type check
+        let _: fn(State<SqlitePool>) -> _ = list_counters;
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_counters_signature() {
+        // Compile-time type check
+        let _: fn(State<SqlitePool>, State<GenerationClock>) -> _ = check_counters;
+        let _: fn(State<SqlitePool>, State<GenerationClock>) -> _ = reconcile_counters;
+    }
+}
@@ -0,0 +1,66 @@
+//! GET /api/documents/capacity
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::error::{Error, Result};
+use crate::models::{TaskId, TypeCode, UserId};
+use crate::services::clock::GenerationClock;
+use crate::services::generation_service::{self, CounterCapacity, ScopeOwner};
+use crate::services::rule_service;
+use crate::storage::{document_type, user};
+
+#[derive(Debug, Deserialize)]
+pub struct CounterCapacityQuery {
+    pub type_code: String,
+    pub user_id: String,
+    pub task_id: Option<String>,
+}
+
+/// GET /api/documents/capacity - Remaining counter capacity for the user's current scope
+pub async fn get_counter_capacity(
+    State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
+    Query(query_params): Query<CounterCapacityQuery>,
+) -> Result<Json<CounterCapacity>> {
+    let user = user::get_user(&pool, &UserId::new(&query_params.user_id))
+        .await?
+        .ok_or_else(|| Error::UserNotFound(query_params.user_id.clone()))?;
+
+    let type_code = TypeCode::new(&query_params.type_code);
+    let doc_type = document_type::get_document_type(&pool, &type_code)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Document type: {}", query_params.type_code)))?;
+
+    let task_id = query_params.task_id.as_deref().map(TaskId::new);
+    let date = clock.today(doc_type.timezone);
+    let rule = rule_service::effective_rule(&pool, &doc_type, date).await?;
+    let capacity = generation_service::counter_capacity(
+        &pool,
+        &rule,
+        &type_code,
+        &user.department,
+        &user.section,
+        ScopeOwner::new(&user.id, task_id.as_ref()),
+        date,
+    )
+    .await?;
+
+    Ok(Json(capacity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_counter_capacity_signature() {
+        // Compile-time type check
+        let _: fn(State<SqlitePool>, State<GenerationClock>, Query<CounterCapacityQuery>) -> _ =
+            get_counter_capacity;
+    }
+}
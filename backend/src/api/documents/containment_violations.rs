@@ -0,0 +1,40 @@
+//! GET /api/documents/containment-violations
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::error::Result;
+use crate::models::TypeCode;
+use crate::services::path_service::{self, ContainmentViolation};
+
+#[derive(Debug, Deserialize)]
+pub struct ContainmentViolationsQuery {
+    /// Only report documents of this type
+    pub type_code: Option<String>,
+}
+
+/// GET /api/documents/containment-violations - Live documents outside their type's root directory
+pub async fn list_containment_violations(
+    State(pool): State<SqlitePool>,
+    Query(query_params): Query<ContainmentViolationsQuery>,
+) -> Result<Json<Vec<ContainmentViolation>>> {
+    let type_code = query_params.type_code.map(TypeCode::new);
+    let violations = path_service::find_containment_violations(&pool, type_code.as_ref()).await?;
+    Ok(Json(violations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_containment_violations_signature() {
+        // Compile-time type check
+        let _: fn(State<SqlitePool>, Query<ContainmentViolationsQuery>) -> _ =
+            list_containment_violations;
+    }
+}
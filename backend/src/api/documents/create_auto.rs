@@ -3,17 +3,19 @@
 use axum::{Json, extract::State, http::StatusCode};
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
-use std::path::PathBuf;
 
 use crate::error::Result;
 use crate::models::{DocumentPath, TaskId, TypeCode, UserId};
-use crate::services::document_service;
+use crate::services::clock::GenerationClock;
+use crate::services::document_service::{self, AutoDocumentRequest};
 
 #[derive(Debug, Deserialize)]
 pub struct CreateDocumentAutoRequest {
     pub type_code: String,
     pub user_id: String,
     pub task_id: Option<String>,
+    /// File extension for the type's path template (e.g. "pdf")
+    pub extension: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -30,6 +32,10 @@ pub struct CreateDocumentResponse {
     pub updated_at: String,
     pub generated: bool,
     pub deleted: bool,
+    pub generation_rule_id: Option<i64>,
+    /// Warnings raised by the type's manual number and path policies
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 impl From<DocumentPath> for CreateDocumentResponse {
@@ -42,11 +48,13 @@ impl From<DocumentPath> for CreateDocumentResponse {
             section: doc.section.0,
             business_task: doc.business_task.map(|t| t.0),
             user_id: doc.user.0,
-            file_path: doc.file_path.to_string_lossy().to_string(),
+            file_path: doc.file_path.to_string(),
             created_at: doc.created_at.to_rfc3339(),
             updated_at: doc.updated_at.to_rfc3339(),
             generated: doc.generated,
             deleted: doc.deleted,
+            generation_rule_id: doc.generation_rule_id,
+            warnings: Vec::new(),
         }
     }
 }
@@ -54,30 +62,45 @@ impl From<DocumentPath> for CreateDocumentResponse {
 /// POST /api/documents - Create document with auto-generated number
 pub async fn create_document_auto(
     State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
     Json(req): Json<CreateDocumentAutoRequest>,
 ) -> Result<(StatusCode, Json<CreateDocumentResponse>)> {
     // Get user to determine department and section
     let user = crate::storage::user::get_user(&pool, &UserId::new(&req.user_id))
         .await?
         .ok_or_else(|| crate::error::Error::UserNotFound(req.user_id.clone()))?;
-    
-    // Get document type to determine root directory
-    let doc_type = crate::storage::document_type::get_document_type(&pool, &TypeCode::new(&req.type_code))
-        .await?
-        .ok_or_else(|| crate::error::Error::NotFound(format!("Document type: {}", req.type_code)))?;
-    
-    let doc = document_service::create_document_auto(
+
+    // Get document type to determine root directory (used without a path template)
+    let doc_type =
+        crate::storage::document_type::get_document_type(&pool, &TypeCode::new(&req.type_code))
+            .await?
+            .ok_or_else(|| {
+                crate::error::Error::NotFound(format!("Document type: {}", req.type_code))
+            })?;
+
+    let created = document_service::create_document_auto(
         &pool,
-        TypeCode::new(&req.type_code),
-        user.department,
-        user.section,
-        UserId::new(&req.user_id),
-        PathBuf::from(&doc_type.root_directory),
-        req.task_id.filter(|t| !t.is_empty()).map(|t| TaskId::new(&t)),
+        &clock,
+        AutoDocumentRequest {
+            type_code: TypeCode::new(&req.type_code),
+            dept_code: user.department,
+            section_code: user.section,
+            user_id: UserId::new(&req.user_id),
+            file_path: doc_type.root_directory.clone(),
+            business_task: req
+                .task_id
+                .filter(|t| !t.is_empty())
+                .map(|t| TaskId::new(&t)),
+            extension: req.extension,
+        },
     )
     .await?;
 
-    Ok((StatusCode::CREATED, Json(doc.into())))
+    let response = CreateDocumentResponse {
+        warnings: created.warnings,
+        ..created.document.into()
+    };
+    Ok((StatusCode::CREATED, Json(response)))
 }
 
 #[cfg(test)]
@@ -87,6 +110,7 @@ mod tests {
     #[tokio::test]
     async fn test_create_document_auto_signature() {
         // Compile-time type check
-        let _: fn(State<SqlitePool>, Json<CreateDocumentAutoRequest>) -> _ = create_document_auto;
+        let _: fn(State<SqlitePool>, State<GenerationClock>, Json<CreateDocumentAutoRequest>) -> _ =
+            create_document_auto;
     }
 }
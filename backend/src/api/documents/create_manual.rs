@@ -3,11 +3,11 @@
 use axum::{Json, extract::State, http::StatusCode};
 use serde::Deserialize;
 use sqlx::SqlitePool;
-use std::path::PathBuf;
 
 use super::create_auto::CreateDocumentResponse;
 use crate::error::Result;
-use crate::models::{DeptCode, SectionCode, TaskId, TypeCode, UserId};
+use crate::models::{DeptCode, SectionCode, StoredPath, TaskId, TypeCode, UserId};
+use crate::services::clock::GenerationClock;
 use crate::services::document_service::{self, ManualDocumentRequest};
 
 #[derive(Debug, Deserialize)]
@@ -24,12 +24,14 @@ pub struct CreateDocumentManualRequest {
 /// POST /api/documents/manual - Create document with manual number
 pub async fn create_document_manual(
     State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
     Json(req): Json<CreateDocumentManualRequest>,
 ) -> Result<(StatusCode, Json<CreateDocumentResponse>)> {
-    let file_path = PathBuf::from(&req.file_path);
+    let file_path = StoredPath::new(&req.file_path);
 
-    let doc = document_service::create_document_manual(
+    let created = document_service::create_document_manual(
         &pool,
+        &clock,
         ManualDocumentRequest {
             document_number: req.document_number,
             type_code: TypeCode::new(&req.type_code),
@@ -42,7 +44,11 @@ pub async fn create_document_manual(
     )
     .await?;
 
-    Ok((StatusCode::CREATED, Json(doc.into())))
+    let response = CreateDocumentResponse {
+        warnings: created.warnings,
+        ..created.document.into()
+    };
+    Ok((StatusCode::CREATED, Json(response)))
 }
 
 #[cfg(test)]
@@ -52,7 +58,10 @@ mod tests {
     #[tokio::test]
     async fn test_create_document_manual_signature() {
         // Compile-time type check
-        let _: fn(State<SqlitePool>, Json<CreateDocumentManualRequest>) -> _ =
-            create_document_manual;
+        let _: fn(
+            State<SqlitePool>,
+            State<GenerationClock>,
+            Json<CreateDocumentManualRequest>,
+        ) -> _ = create_document_manual;
     }
 }
@@ -0,0 +1,25 @@
+//! GET /api/documents/duplicate-paths
+
+use axum::{Json, extract::State};
+use sqlx::SqlitePool;
+
+use crate::error::Result;
+use crate::services::path_service::{self, PathDuplicate};
+
+/// GET /api/documents/duplicate-paths - Live documents sharing a file path
+pub async fn list_duplicate_paths(
+    State(pool): State<SqlitePool>,
+) -> Result<Json<Vec<PathDuplicate>>> {
+    Ok(Json(path_service::find_duplicate_paths(&pool).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_duplicate_paths_signature() {
+        // Compile-time type check
+        let _: fn(State<SqlitePool>) -> _ = list_duplicate_paths;
+    }
+}
@@ -0,0 +1,39 @@
+//! GET /api/documents/missing-files
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::error::Result;
+use crate::models::TypeCode;
+use crate::services::file_check_service::{self, MissingFile};
+
+#[derive(Debug, Deserialize)]
+pub struct MissingFilesQuery {
+    /// Only report documents of this type
+    pub type_code: Option<String>,
+}
+
+/// GET /api/documents/missing-files - Live documents whose file was not found by the last scan
+pub async fn list_missing_files(
+    State(pool): State<SqlitePool>,
+    Query(query_params): Query<MissingFilesQuery>,
+) -> Result<Json<Vec<MissingFile>>> {
+    let type_code = query_params.type_code.map(TypeCode::new);
+    let missing = file_check_service::find_missing_files(&pool, type_code.as_ref()).await?;
+    Ok(Json(missing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_missing_files_signature() {
+        // Compile-time type check
+        let _: fn(State<SqlitePool>, Query<MissingFilesQuery>) -> _ = list_missing_files;
+    }
+}
@@ -1,19 +1,31 @@
 //! Document API handlers module
 
+pub mod capacity;
+pub mod containment_violations;
 pub mod create_auto;
 pub mod create_manual;
 pub mod delete;
+pub mod duplicate_paths;
 pub mod get_all;
 pub mod get_by_id;
 pub mod get_by_number;
+pub mod missing_files;
+pub mod parse;
+pub mod preview;
 pub mod search;
 pub mod update_path;
 
+pub use capacity::get_counter_capacity;
+pub use containment_violations::list_containment_violations;
 pub use create_auto::create_document_auto;
 pub use create_manual::create_document_manual;
 pub use delete::delete_document;
+pub use duplicate_paths::list_duplicate_paths;
 pub use get_all::get_all_documents;
 pub use get_by_id::get_document_by_id;
 pub use get_by_number::get_document_by_number;
+pub use missing_files::list_missing_files;
+pub use parse::parse_document_number;
+pub use preview::preview_document;
 pub use search::search_documents;
 pub use update_path::update_document_path;
@@ -0,0 +1,54 @@
+//! GET /api/documents/parse
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+use crate::error::{Error, Result};
+use crate::services::generation_service::{self, NumberCandidate};
+
+#[derive(Debug, Deserialize)]
+pub struct ParseDocumentNumberQuery {
+    pub number: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ParseDocumentNumberResponse {
+    pub document_number: String,
+    /// One entry per active document type rule that can read the number
+    pub candidates: Vec<NumberCandidate>,
+}
+
+/// GET /api/documents/parse - Decompose a number into rule components (no lookup)
+pub async fn parse_document_number(
+    State(pool): State<SqlitePool>,
+    Query(query_params): Query<ParseDocumentNumberQuery>,
+) -> Result<Json<ParseDocumentNumberResponse>> {
+    let number = query_params.number.trim();
+    if number.is_empty() {
+        return Err(Error::Validation(
+            "Document number cannot be empty".to_string(),
+        ));
+    }
+
+    let candidates = generation_service::parse_number_candidates(&pool, number).await?;
+
+    Ok(Json(ParseDocumentNumberResponse {
+        document_number: number.to_string(),
+        candidates,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parse_document_number_signature() {
+        // Compile-time type check
+        let _: fn(State<SqlitePool>, Query<ParseDocumentNumberQuery>) -> _ = parse_document_number;
+    }
+}
@@ -0,0 +1,103 @@
+//! GET /api/documents/preview
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+use crate::error::{Error, Result};
+use crate::models::{PathValues, TaskId, TypeCode, UserId};
+use crate::services::clock::GenerationClock;
+use crate::services::generation_service::{self, ScopeOwner};
+use crate::services::rule_service;
+use crate::storage::{document_type, user};
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewDocumentQuery {
+    pub type_code: String,
+    pub user_id: String,
+    pub task_id: Option<String>,
+    /// File extension for the type's path template
+    pub extension: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreviewDocumentResponse {
+    pub document_number: String,
+    pub file_path: String,
+    pub scope_key: String,
+}
+
+/// GET /api/documents/preview - Preview the next auto-generated number (dry run)
+pub async fn preview_document(
+    State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
+    Query(query_params): Query<PreviewDocumentQuery>,
+) -> Result<Json<PreviewDocumentResponse>> {
+    let user = user::get_user(&pool, &UserId::new(&query_params.user_id))
+        .await?
+        .ok_or_else(|| Error::UserNotFound(query_params.user_id.clone()))?;
+
+    let type_code = TypeCode::new(&query_params.type_code);
+    let doc_type = document_type::get_document_type(&pool, &type_code)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Document type: {}", query_params.type_code)))?;
+
+    if !doc_type.active {
+        return Err(Error::Validation(format!(
+            "Document type '{}' is not active",
+            type_code.0
+        )));
+    }
+
+    let task_id = query_params.task_id.as_deref().map(TaskId::new);
+    let date = clock.today(doc_type.timezone);
+    let rule = rule_service::effective_rule(&pool, &doc_type, date).await?;
+    let preview = generation_service::preview_document_number(
+        &pool,
+        &rule,
+        &type_code,
+        &user.department,
+        &user.section,
+        ScopeOwner::new(&user.id, task_id.as_ref()),
+        date,
+    )
+    .await?;
+
+    let file_path = match &doc_type.path_template {
+        Some(template) => template
+            .render(&PathValues {
+                root: doc_type.root_directory.as_str(),
+                number: &preview.document_number,
+                extension: query_params.extension.as_deref(),
+                document_type: &type_code,
+                department: &user.department,
+                section: &user.section,
+                date,
+                user: &user.id,
+                task: task_id.as_ref(),
+            })?
+            .to_string(),
+        None => doc_type.root_directory.to_string(),
+    };
+
+    Ok(Json(PreviewDocumentResponse {
+        document_number: preview.document_number,
+        file_path,
+        scope_key: preview.scope_key,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_preview_document_signature() {
+        // Compile-time type check
+        let _: fn(State<SqlitePool>, State<GenerationClock>, Query<PreviewDocumentQuery>) -> _ =
+            preview_document;
+    }
+}
@@ -6,11 +6,11 @@ use axum::{
 };
 use serde::Deserialize;
 use sqlx::SqlitePool;
-use std::path::PathBuf;
 
 use super::create_auto::CreateDocumentResponse;
 use crate::error::Result;
-use crate::models::DocumentId;
+use crate::models::{DocumentId, StoredPath};
+use crate::services::clock::GenerationClock;
 use crate::services::document_service;
 
 #[derive(Debug, Deserialize)]
@@ -21,25 +21,36 @@ pub struct UpdateDocumentPathRequest {
 /// PUT /api/documents/:id/path - Update document file path
 pub async fn update_document_path(
     State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
     Path(id): Path<String>,
     Json(req): Json<UpdateDocumentPathRequest>,
 ) -> Result<Json<CreateDocumentResponse>> {
-    let file_path = PathBuf::from(&req.file_path);
+    let file_path = StoredPath::new(&req.file_path);
 
-    let doc =
-        document_service::update_document_path(&pool, &DocumentId::new(&id), file_path).await?;
+    let updated =
+        document_service::update_document_path(&pool, &clock, &DocumentId::new(&id), file_path)
+            .await?;
 
-    Ok(Json(doc.into()))
+    Ok(Json(CreateDocumentResponse {
+        warnings: updated.warnings,
+        ..updated.document.into()
+    }))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    type UpdatePathHandler<R> = fn(
+        State<SqlitePool>,
+        State<GenerationClock>,
+        Path<String>,
+        Json<UpdateDocumentPathRequest>,
+    ) -> R;
+
     #[tokio::test]
     async fn test_update_document_path_signature() {
         // Compile-time type check
-        let _: fn(State<SqlitePool>, Path<String>, Json<UpdateDocumentPathRequest>) -> _ =
-            update_document_path;
+        let _: UpdatePathHandler<_> = update_document_path;
     }
 }
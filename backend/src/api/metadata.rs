@@ -1,10 +1,86 @@
 //! Metadata API handlers (departments, document types)
 
-use axum::{extract::State, Json};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Deserialize;
 use sqlx::SqlitePool;
 
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::models::{
+    CounterEncoding, CounterScope, DocumentType, ManualNumberPolicy, PathContainment,
+    PathGenerationRule, PathTemplate, PathUniqueness, RuleChangeSchedule, StoredPath, TypeCode,
+    WatchPattern,
+};
+use crate::services::clock::GenerationClock;
+use crate::services::rule_service;
 use crate::storage::{department, document_type};
+use crate::validation;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateDocumentTypeRequest {
+    pub code: String,
+    pub description: String,
+    pub root_directory: String,
+    /// Rule pattern (e.g., "[TYPE][DEPT][SECT][YYMM][NNN]")
+    pub pattern: String,
+    pub counter_scope: CounterScope,
+    pub fiscal_year_start_month: Option<u8>,
+    /// Counter encoding (default: zero-padded decimal)
+    pub counter_encoding: Option<CounterEncoding>,
+    /// First counter value of each new scope (default: 1)
+    pub counter_start: Option<i64>,
+    /// IANA timezone name (e.g., "Asia/Tokyo")
+    pub timezone: Option<String>,
+    /// Manual number checks (default: off)
+    pub manual_numbers: Option<ManualNumberPolicy>,
+    /// File path template (e.g., "{root}/{yyyy}/{dept}{section}/{number}.{ext}")
+    pub path_template: Option<String>,
+    /// Documents sharing a file path (default: allow)
+    pub path_uniqueness: Option<PathUniqueness>,
+    /// File paths outside the root directory (default: off)
+    pub path_containment: Option<PathContainment>,
+    /// File name pattern for the filesystem watcher (e.g., "{number}.pdf")
+    pub watch_pattern: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPathUniquenessRequest {
+    pub path_uniqueness: PathUniqueness,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPathContainmentRequest {
+    pub path_containment: PathContainment,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPathTemplateRequest {
+    /// File path template; null keeps the root directory as the file path
+    pub path_template: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetWatchPatternRequest {
+    /// File name pattern; null stops watching the type's root directory
+    pub watch_pattern: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleRuleChangeRequest {
+    /// Rule pattern (e.g., "[TYPE][DEPT][SECT][YYMM][NNN]")
+    pub pattern: String,
+    pub counter_scope: CounterScope,
+    pub fiscal_year_start_month: Option<u8>,
+    /// Counter encoding (default: zero-padded decimal)
+    pub counter_encoding: Option<CounterEncoding>,
+    /// First counter value of each new scope (default: 1)
+    pub counter_start: Option<i64>,
+    /// When the new rule takes effect
+    pub effective: RuleChangeSchedule,
+}
 
 /// GET /api/departments
 pub async fn list_departments(State(pool): State<SqlitePool>) -> Result<Json<serde_json::Value>> {
@@ -13,7 +89,224 @@ pub async fn list_departments(State(pool): State<SqlitePool>) -> Result<Json<ser
 }
 
 /// GET /api/document-types
-pub async fn list_document_types(State(pool): State<SqlitePool>) -> Result<Json<serde_json::Value>> {
+pub async fn list_document_types(
+    State(pool): State<SqlitePool>,
+) -> Result<Json<serde_json::Value>> {
     let types = document_type::list_active_document_types(&pool).await?;
     Ok(Json(serde_json::to_value(types)?))
 }
+
+/// POST /api/document-types - Create a document type from a rule pattern
+pub async fn create_document_type(
+    State(pool): State<SqlitePool>,
+    Json(req): Json<CreateDocumentTypeRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>)> {
+    validation::validate_type_code(&req.code)?;
+
+    let mut rule = PathGenerationRule::from_pattern(&req.pattern, req.counter_scope)?;
+    if let Some(month) = req.fiscal_year_start_month {
+        rule = with_fiscal_year_start_month(rule, month)?;
+    }
+    if let Some(encoding) = req.counter_encoding {
+        rule = rule.with_counter_encoding(encoding);
+    }
+    if let Some(start) = req.counter_start {
+        rule = with_counter_start(rule, start)?;
+    }
+
+    let root_directory = StoredPath::parse(&req.root_directory)?;
+    let mut doc_type = DocumentType::new(&req.code, req.description, root_directory, rule);
+    if let Some(name) = &req.timezone {
+        let timezone = name
+            .parse()
+            .map_err(|_| Error::Validation(format!("Unknown timezone '{}'", name)))?;
+        doc_type = doc_type.with_timezone(timezone);
+    }
+    if let Some(policy) = req.manual_numbers {
+        doc_type = doc_type.with_manual_numbers(policy);
+    }
+    if let Some(template) = &req.path_template {
+        doc_type = doc_type.with_path_template(template.parse()?);
+    }
+    if let Some(policy) = req.path_uniqueness {
+        doc_type = doc_type.with_path_uniqueness(policy);
+    }
+    if let Some(policy) = req.path_containment {
+        doc_type = doc_type.with_path_containment(policy);
+    }
+    if let Some(pattern) = &req.watch_pattern {
+        doc_type = doc_type.with_watch_pattern(pattern.parse()?);
+    }
+
+    document_type::create_document_type(&pool, &doc_type).await?;
+
+    Ok((StatusCode::CREATED, Json(serde_json::to_value(doc_type)?)))
+}
+
+/// PUT /api/document-types/{code}/manual-numbers - Set the manual number policy
+pub async fn set_manual_number_policy(
+    State(pool): State<SqlitePool>,
+    Path(code): Path<String>,
+    Json(policy): Json<ManualNumberPolicy>,
+) -> Result<Json<serde_json::Value>> {
+    let doc_type = document_type::get_document_type(&pool, &TypeCode::new(&code))
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Document type '{}' not found", code)))?
+        .with_manual_numbers(policy);
+
+    document_type::update_document_type(&pool, &doc_type).await?;
+
+    Ok(Json(serde_json::to_value(doc_type)?))
+}
+
+/// PUT /api/document-types/{code}/path-template - Set the file path template
+pub async fn set_path_template(
+    State(pool): State<SqlitePool>,
+    Path(code): Path<String>,
+    Json(req): Json<SetPathTemplateRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let template = req
+        .path_template
+        .as_deref()
+        .map(str::parse::<PathTemplate>)
+        .transpose()?;
+    let mut doc_type = document_type::get_document_type(&pool, &TypeCode::new(&code))
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Document type '{}' not found", code)))?;
+    doc_type.path_template = template;
+
+    document_type::update_document_type(&pool, &doc_type).await?;
+
+    Ok(Json(serde_json::to_value(doc_type)?))
+}
+
+/// PUT /api/document-types/{code}/path-uniqueness - Set the path uniqueness policy
+pub async fn set_path_uniqueness(
+    State(pool): State<SqlitePool>,
+    Path(code): Path<String>,
+    Json(req): Json<SetPathUniquenessRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let doc_type = document_type::get_document_type(&pool, &TypeCode::new(&code))
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Document type '{}' not found", code)))?
+        .with_path_uniqueness(req.path_uniqueness);
+
+    document_type::update_document_type(&pool, &doc_type).await?;
+
+    Ok(Json(serde_json::to_value(doc_type)?))
+}
+
+/// PUT /api/document-types/{code}/path-containment - Set the path containment policy
+pub async fn set_path_containment(
+    State(pool): State<SqlitePool>,
+    Path(code): Path<String>,
+    Json(req): Json<SetPathContainmentRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let doc_type = document_type::get_document_type(&pool, &TypeCode::new(&code))
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Document type '{}' not found", code)))?
+        .with_path_containment(req.path_containment);
+
+    document_type::update_document_type(&pool, &doc_type).await?;
+
+    Ok(Json(serde_json::to_value(doc_type)?))
+}
+
+/// PUT /api/document-types/{code}/watch-pattern - Set the watcher's file name pattern
+pub async fn set_watch_pattern(
+    State(pool): State<SqlitePool>,
+    Path(code): Path<String>,
+    Json(req): Json<SetWatchPatternRequest>,
+) -> Result<Json<serde_json::Value>> {
+    let pattern = req
+        .watch_pattern
+        .as_deref()
+        .map(str::parse::<WatchPattern>)
+        .transpose()?;
+    let mut doc_type = document_type::get_document_type(&pool, &TypeCode::new(&code))
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Document type '{}' not found", code)))?;
+    doc_type.watch_pattern = pattern;
+
+    document_type::update_document_type(&pool, &doc_type).await?;
+
+    Ok(Json(serde_json::to_value(doc_type)?))
+}
+
+/// GET /api/document-types/{code}/rules - List rule versions, oldest first
+pub async fn list_rule_versions(
+    State(pool): State<SqlitePool>,
+    Path(code): Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    let versions = rule_service::list_rule_versions(&pool, &TypeCode::new(code)).await?;
+    Ok(Json(serde_json::to_value(versions)?))
+}
+
+/// POST /api/document-types/{code}/rules - Schedule a new rule version
+pub async fn schedule_rule_change(
+    State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
+    Path(code): Path<String>,
+    Json(req): Json<ScheduleRuleChangeRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>)> {
+    let mut rule = PathGenerationRule::from_pattern(&req.pattern, req.counter_scope)?;
+    if let Some(month) = req.fiscal_year_start_month {
+        rule = with_fiscal_year_start_month(rule, month)?;
+    }
+    if let Some(encoding) = req.counter_encoding {
+        rule = rule.with_counter_encoding(encoding);
+    }
+    if let Some(start) = req.counter_start {
+        rule = with_counter_start(rule, start)?;
+    }
+
+    let version = rule_service::schedule_rule_change(
+        &pool,
+        &clock,
+        &TypeCode::new(code),
+        rule,
+        req.effective,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(serde_json::to_value(version)?)))
+}
+
+/// Apply a counter start value, which must fit the rule's counter width
+fn with_counter_start(rule: PathGenerationRule, start: i64) -> Result<PathGenerationRule> {
+    let capacity = rule.counter_capacity();
+    if !(1..=capacity).contains(&start) {
+        return Err(Error::Validation(format!(
+            "Counter start must be between 1 and {}, got {}",
+            capacity, start
+        )));
+    }
+    Ok(rule.with_counter_start(start))
+}
+
+/// Apply a fiscal year start month, which must be a calendar month (1-12)
+fn with_fiscal_year_start_month(rule: PathGenerationRule, month: u8) -> Result<PathGenerationRule> {
+    if !(1..=12).contains(&month) {
+        return Err(Error::Validation(format!(
+            "Fiscal year start month must be 1-12, got {}",
+            month
+        )));
+    }
+    Ok(rule.with_fiscal_year_start_month(month))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fiscal_year_start_month_must_be_a_month() {
+        let rule = PathGenerationRule::example_agi();
+        for month in [0, 13] {
+            let result = with_fiscal_year_start_month(rule.clone(), month);
+            assert!(matches!(result, Err(Error::Validation(_))));
+        }
+        let april = with_fiscal_year_start_month(rule, 4);
+        assert!(april.is_ok_and(|rule| rule.fiscal_year_start_month == 4));
+    }
+}
@@ -1,10 +1,14 @@
 //! API handlers
 
+pub mod audit;
+pub mod counters;
 pub mod documents;
 pub mod metadata;
+pub mod reservations;
 
 use axum::{
     Json, Router,
+    extract::FromRef,
     http::StatusCode,
     response::IntoResponse,
     routing::{get, post, put},
@@ -14,9 +18,29 @@ use serde_json::json;
 use sqlx::SqlitePool;
 
 use crate::error::Error;
+use crate::services::clock::GenerationClock;
+
+/// Shared application state
+#[derive(Debug, Clone)]
+pub struct AppState {
+    pub pool: SqlitePool,
+    pub clock: GenerationClock,
+}
+
+impl FromRef<AppState> for SqlitePool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for GenerationClock {
+    fn from_ref(state: &AppState) -> Self {
+        state.clock.clone()
+    }
+}
 
 /// Create the API router
-pub fn create_router(pool: SqlitePool) -> Router {
+pub fn create_router(pool: SqlitePool, clock: GenerationClock) -> Router {
     let router = Router::new()
         // Document endpoints
         .route(
@@ -28,6 +52,24 @@ pub fn create_router(pool: SqlitePool) -> Router {
             post(documents::create_document_manual),
         )
         .route("/api/documents/search", get(documents::search_documents))
+        .route("/api/documents/preview", get(documents::preview_document))
+        .route("/api/documents/parse", get(documents::parse_document_number))
+        .route(
+            "/api/documents/capacity",
+            get(documents::get_counter_capacity),
+        )
+        .route(
+            "/api/documents/duplicate-paths",
+            get(documents::list_duplicate_paths),
+        )
+        .route(
+            "/api/documents/containment-violations",
+            get(documents::list_containment_violations),
+        )
+        .route(
+            "/api/documents/missing-files",
+            get(documents::list_missing_files),
+        )
         .route(
             "/api/documents/{id}",
             get(documents::get_document_by_id).delete(documents::delete_document),
@@ -40,16 +82,96 @@ pub fn create_router(pool: SqlitePool) -> Router {
             "/api/documents/number/{number}",
             get(documents::get_document_by_number),
         )
+        // Reservation endpoints
+        .route(
+            "/api/reservations",
+            get(reservations::list_reservations).post(reservations::reserve_number),
+        )
+        .route(
+            "/api/reservations/unused",
+            get(reservations::list_unused_reservations),
+        )
+        .route(
+            "/api/reservations/{id}/confirm",
+            post(reservations::confirm_reservation),
+        )
+        .route(
+            "/api/reservations/{id}/release",
+            post(reservations::release_reservation),
+        )
+        // Audit endpoints
+        .route("/api/audit/counters", get(audit::audit_counters))
+        // Counter administration endpoints
+        .route("/api/counters", get(counters::list_counters))
+        .route(
+            "/api/counters/reconcile",
+            get(counters::check_counters).post(counters::reconcile_counters),
+        )
+        .route("/api/counters/{scope_key}", put(counters::seed_counter))
         // Metadata endpoints
         .route("/api/departments", get(metadata::list_departments))
-        .route("/api/document-types", get(metadata::list_document_types));
+        .route(
+            "/api/document-types",
+            get(metadata::list_document_types).post(metadata::create_document_type),
+        )
+        .route(
+            "/api/document-types/{code}/manual-numbers",
+            put(metadata::set_manual_number_policy),
+        )
+        .route(
+            "/api/document-types/{code}/path-template",
+            put(metadata::set_path_template),
+        )
+        .route(
+            "/api/document-types/{code}/path-uniqueness",
+            put(metadata::set_path_uniqueness),
+        )
+        .route(
+            "/api/document-types/{code}/path-containment",
+            put(metadata::set_path_containment),
+        )
+        .route(
+            "/api/document-types/{code}/watch-pattern",
+            put(metadata::set_watch_pattern),
+        )
+        .route(
+            "/api/document-types/{code}/rules",
+            get(metadata::list_rule_versions).post(metadata::schedule_rule_change),
+        );
 
-    router.with_state(pool)
+    router.with_state(AppState { pool, clock })
 }
 
-/// Convert Error to HTTP response
+/// Convert Error to HTTP response.
+/// The body carries the message under `error`, the machine-readable
+/// `Error::code` under `code`, and the variant's fields where useful.
 impl IntoResponse for Error {
     fn into_response(self) -> axum::response::Response {
+        let mut body = match &self {
+            Error::DuplicateDocumentNumber(number) | Error::ReservedDocumentNumber(number) => {
+                json!({ "document_number": number })
+            }
+            Error::DuplicateFilePath {
+                file_path,
+                document_numbers,
+            } => json!({ "file_path": file_path, "document_numbers": document_numbers }),
+            Error::PathOutsideRoot {
+                file_path,
+                root_directory,
+            } => json!({ "file_path": file_path, "root_directory": root_directory }),
+            Error::AlreadyExists { entity, key } => json!({ "entity": entity, "key": key }),
+            Error::InvalidCheckDigit {
+                number,
+                suggestions,
+            } => json!({ "document_number": number, "suggestions": suggestions }),
+            Error::CounterExhausted {
+                scope_key,
+                max_value,
+            } => json!({ "scope_key": scope_key, "max_value": max_value }),
+            _ => json!({}),
+        };
+        body["code"] = json!(self.code());
+
         let (status, message) = match self {
             Error::NotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
             Error::DocumentNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
@@ -59,9 +181,16 @@ impl IntoResponse for Error {
             Error::BusinessTaskNotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
             Error::Validation(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             Error::RelativePathNotAllowed => (StatusCode::BAD_REQUEST, self.to_string()),
+            Error::InvalidPath(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             Error::InvalidTypeCode(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            Error::InvalidCheckDigit { .. } => (StatusCode::BAD_REQUEST, self.to_string()),
             Error::DuplicateDocumentNumber(_) => (StatusCode::CONFLICT, self.to_string()),
+            Error::ReservedDocumentNumber(_) => (StatusCode::CONFLICT, self.to_string()),
+            Error::DuplicateFilePath { .. } => (StatusCode::CONFLICT, self.to_string()),
+            Error::PathOutsideRoot { .. } => (StatusCode::BAD_REQUEST, self.to_string()),
+            Error::AlreadyExists { .. } => (StatusCode::CONFLICT, self.to_string()),
             Error::UnauthorizedDocumentType => (StatusCode::FORBIDDEN, self.to_string()),
+            Error::CounterExhausted { .. } => (StatusCode::CONFLICT, self.to_string()),
             Error::ConcurrentModification => (StatusCode::CONFLICT, self.to_string()),
             Error::InvalidRuleComponent(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             Error::Database(_) => (
@@ -80,10 +209,53 @@ impl IntoResponse for Error {
             Error::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, self.to_string()),
         };
 
-        let body = Json(json!({
-            "error": message,
-        }));
+        body["error"] = json!(message);
+
+        (status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn error_body(error: Error) -> anyhow::Result<(StatusCode, serde_json::Value)> {
+        let response = error.into_response();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+        Ok((status, serde_json::from_slice(&bytes)?))
+    }
+
+    #[tokio::test]
+    async fn test_error_body_has_code_and_fields() -> anyhow::Result<()> {
+        let (status, body) =
+            error_body(Error::DuplicateDocumentNumber("AGI2510001".to_string())).await?;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "duplicate_document_number");
+        assert_eq!(body["document_number"], "AGI2510001");
+        assert_eq!(body["error"], "Duplicate document number: AGI2510001");
+
+        let (status, body) = error_body(Error::InvalidCheckDigit {
+            number: "AGI25100129".to_string(),
+            suggestions: vec!["AGI25100128".to_string()],
+        })
+        .await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_check_digit");
+        assert_eq!(body["suggestions"][0], "AGI25100128");
+
+        let (status, body) = error_body(Error::Validation("bad".to_string())).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "validation");
+        Ok(())
+    }
 
-        (status, body).into_response()
+    #[tokio::test]
+    async fn test_database_error_details_are_hidden() -> anyhow::Result<()> {
+        let (status, body) = error_body(Error::Database(sqlx::Error::RowNotFound)).await?;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], "database");
+        assert_eq!(body["error"], "Database error");
+        Ok(())
     }
 }
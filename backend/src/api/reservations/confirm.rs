@@ -0,0 +1,64 @@
+//! POST /api/reservations/:id/confirm
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use crate::api::documents::create_auto::CreateDocumentResponse;
+use crate::error::Result;
+use crate::models::{ReservationId, StoredPath, TaskId};
+use crate::services::clock::GenerationClock;
+use crate::services::reservation_service;
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmReservationRequest {
+    pub file_path: String,
+    pub task_id: Option<String>,
+}
+
+/// POST /api/reservations/:id/confirm - Create the document for a reserved number
+pub async fn confirm_reservation(
+    State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
+    Path(id): Path<String>,
+    Json(req): Json<ConfirmReservationRequest>,
+) -> Result<(StatusCode, Json<CreateDocumentResponse>)> {
+    let confirmed = reservation_service::confirm_reservation(
+        &pool,
+        &clock,
+        &ReservationId::new(&id),
+        StoredPath::new(&req.file_path),
+        req.task_id
+            .filter(|t| !t.is_empty())
+            .map(|t| TaskId::new(&t)),
+    )
+    .await?;
+
+    let response = CreateDocumentResponse {
+        warnings: confirmed.warnings,
+        ..confirmed.document.into()
+    };
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type ConfirmHandler<R> = fn(
+        State<SqlitePool>,
+        State<GenerationClock>,
+        Path<String>,
+        Json<ConfirmReservationRequest>,
+    ) -> R;
+
+    #[tokio::test]
+    async fn test_confirm_reservation_signature() {
+        // Compile-time type check
+        let _: ConfirmHandler<_> = confirm_reservation;
+    }
+}
@@ -0,0 +1,58 @@
+//! GET /api/reservations
+
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+
+use super::reserve::ReservationResponse;
+use crate::error::Result;
+use crate::models::ReservationStatus;
+use crate::services::clock::GenerationClock;
+use crate::services::reservation_service;
+
+#[derive(Debug, Deserialize)]
+pub struct ListReservationsQuery {
+    /// reserved, confirmed, released or expired
+    pub status: Option<String>,
+}
+
+/// GET /api/reservations - List reservations (optionally by status)
+pub async fn list_reservations(
+    State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
+    Query(query_params): Query<ListReservationsQuery>,
+) -> Result<Json<Vec<ReservationResponse>>> {
+    let status = query_params
+        .status
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<ReservationStatus>())
+        .transpose()?;
+
+    let reservations = reservation_service::list_reservations(&pool, &clock, status).await?;
+    Ok(Json(reservations.into_iter().map(|r| r.into()).collect()))
+}
+
+/// GET /api/reservations/unused - List numbers reserved but never used
+pub async fn list_unused_reservations(
+    State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
+) -> Result<Json<Vec<ReservationResponse>>> {
+    let reservations = reservation_service::list_unused_reservations(&pool, &clock).await?;
+    Ok(Json(reservations.into_iter().map(|r| r.into()).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_reservations_signature() {
+        // Compile-time type check
+        let _: fn(State<SqlitePool>, State<GenerationClock>, Query<ListReservationsQuery>) -> _ =
+            list_reservations;
+        let _: fn(State<SqlitePool>, State<GenerationClock>) -> _ = list_unused_reservations;
+    }
+}
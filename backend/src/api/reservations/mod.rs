@@ -0,0 +1,11 @@
+//! Number reservation API handlers module
+
+pub mod confirm;
+pub mod list;
+pub mod release;
+pub mod reserve;
+
+pub use confirm::confirm_reservation;
+pub use list::{list_reservations, list_unused_reservations};
+pub use release::release_reservation;
+pub use reserve::reserve_number;
@@ -0,0 +1,37 @@
+//! POST /api/reservations/:id/release
+
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use sqlx::SqlitePool;
+
+use super::reserve::ReservationResponse;
+use crate::error::Result;
+use crate::models::ReservationId;
+use crate::services::clock::GenerationClock;
+use crate::services::reservation_service;
+
+/// POST /api/reservations/:id/release - Give up a reserved number
+pub async fn release_reservation(
+    State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
+    Path(id): Path<String>,
+) -> Result<Json<ReservationResponse>> {
+    let reservation =
+        reservation_service::release_reservation(&pool, &clock, &ReservationId::new(&id)).await?;
+
+    Ok(Json(reservation.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_release_reservation_signature() {
+        // Compile-time type check
+        let _: fn(State<SqlitePool>, State<GenerationClock>, Path<String>) -> _ =
+            release_reservation;
+    }
+}
@@ -0,0 +1,107 @@
+//! POST /api/reservations
+
+use axum::{Json, extract::State, http::StatusCode};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+use crate::error::Result;
+use crate::models::{Reservation, TaskId, TypeCode, UserId};
+use crate::services::clock::GenerationClock;
+use crate::services::reservation_service::{
+    self, DEFAULT_RESERVATION_TTL_HOURS, ReserveNumberRequest,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ReserveNumberApiRequest {
+    pub type_code: String,
+    pub user_id: String,
+    /// Business task the number is drawn for
+    pub task_id: Option<String>,
+    /// Hours until the reservation expires (default: 7 days)
+    pub ttl_hours: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReservationResponse {
+    pub id: String,
+    pub document_number: String,
+    pub document_type: String,
+    pub department: char,
+    pub section: char,
+    pub user_id: String,
+    pub scope_key: String,
+    pub counter_value: i64,
+    pub status: String,
+    pub document_id: Option<String>,
+    pub reserved_at: String,
+    pub expires_at: String,
+    pub resolved_at: Option<String>,
+    pub generation_rule_id: Option<i64>,
+    pub business_task: Option<String>,
+}
+
+impl From<Reservation> for ReservationResponse {
+    fn from(reservation: Reservation) -> Self {
+        Self {
+            id: reservation.id.0,
+            document_number: reservation.document_number,
+            document_type: reservation.document_type.0,
+            department: reservation.department.0,
+            section: reservation.section.0,
+            user_id: reservation.user.0,
+            scope_key: reservation.scope_key,
+            counter_value: reservation.counter_value,
+            status: reservation.status.to_string(),
+            document_id: reservation.document_id.map(|d| d.0),
+            reserved_at: reservation.reserved_at.to_rfc3339(),
+            expires_at: reservation.expires_at.to_rfc3339(),
+            resolved_at: reservation.resolved_at.map(|t| t.to_rfc3339()),
+            generation_rule_id: reservation.generation_rule_id,
+            business_task: reservation.business_task.map(|t| t.0),
+        }
+    }
+}
+
+/// POST /api/reservations - Reserve the next number without creating a document
+pub async fn reserve_number(
+    State(pool): State<SqlitePool>,
+    State(clock): State<GenerationClock>,
+    Json(req): Json<ReserveNumberApiRequest>,
+) -> Result<(StatusCode, Json<ReservationResponse>)> {
+    // Get user to determine department and section
+    let user = crate::storage::user::get_user(&pool, &UserId::new(&req.user_id))
+        .await?
+        .ok_or_else(|| crate::error::Error::UserNotFound(req.user_id.clone()))?;
+
+    let reservation = reservation_service::reserve_number(
+        &pool,
+        &clock,
+        ReserveNumberRequest {
+            type_code: TypeCode::new(&req.type_code),
+            dept_code: user.department,
+            section_code: user.section,
+            user_id: UserId::new(&req.user_id),
+            business_task: req
+                .task_id
+                .filter(|t| !t.is_empty())
+                .map(|t| TaskId::new(&t)),
+            ttl: Duration::hours(req.ttl_hours.unwrap_or(DEFAULT_RESERVATION_TTL_HOURS)),
+        },
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(reservation.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reserve_number_signature() {
+        // Compile-time type check
+        let _: fn(State<SqlitePool>, State<GenerationClock>, Json<ReserveNumberApiRequest>) -> _ =
+            reserve_number;
+    }
+}
@@ -0,0 +1,247 @@
+//! Document Path Management administration CLI
+//!
+//! Run with: cargo run --bin document-path-admin -- <command> [options]
+
+use document_path_db::services::audit_service::{self, AuditReport, AuditedNumber};
+use document_path_db::services::clock::{DEFAULT_TIMEZONE, GenerationClock};
+use document_path_db::services::counter_service::{self, ReconcileReport};
+use document_path_db::services::file_check_service::{self, FileVerifier};
+use document_path_db::storage::init_db_pool;
+
+const USAGE: &str = "\
+Usage: document-path-admin <command> [options]
+
+Commands:
+  audit [--scope <scope_key>] [--json]
+      Numbering audit per counter scope: issued, deleted, reserved and
+      missing numbers, and manual numbers colliding with the sequence.
+      Exits with status 1 when gaps or collisions are found.
+  reconcile [--apply] [--json]
+      Parse existing document numbers back into their counter scopes and
+      report counters that are behind (e.g., after restoring a backup).
+      With --apply the counters are raised; without it nothing changes and
+      the command exits with status 1 when repairs are needed.
+  counters
+      List counters with their current and seeded values.
+  seed --scope <scope_key> --next <value>
+      Seed a counter so that the next number issued in the scope uses
+      <value>. Refused if the key does not match the counter scope of a
+      document type's current rule (e.g., A_G_I_2025_10), if <value> does
+      not fit the counter, or if a number at or above <value> was already
+      issued.
+  verify-files [--json]
+      Check that the file of every live document exists on this machine
+      and record size and modification time. Lists documents whose file is
+      missing or unreadable and exits with status 1 if there are any.
+
+Environment:
+  DATABASE_URL       Database (default: sqlite:./data/documents.db)
+  BUSINESS_TIMEZONE  Business timezone (default: Asia/Tokyo)
+  FILE_PATH_MAPPINGS Stored path prefixes mounted elsewhere on this machine,
+                     e.g. \\\\fs01\\docs=/mnt/docs;/archive=/srv/archive";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((command, options)) = args.split_first() else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+
+    let database_url =
+        std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:./data/documents.db".to_string());
+    let timezone = match std::env::var("BUSINESS_TIMEZONE") {
+        Ok(name) => name
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid BUSINESS_TIMEZONE '{}': {}", name, e))?,
+        Err(_) => DEFAULT_TIMEZONE,
+    };
+    let clock = GenerationClock::system(timezone);
+
+    match command.as_str() {
+        "audit" => {
+            let scope_key = option_value(options, "--scope")?;
+            let json = options.iter().any(|o| o == "--json");
+
+            let pool = init_db_pool(&database_url).await?;
+            let report = audit_service::audit_counters(&pool, &clock, scope_key).await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_audit(&report);
+            }
+            if !report.scopes.iter().all(|s| s.is_clean()) {
+                std::process::exit(1);
+            }
+        }
+        "reconcile" => {
+            let apply = options.iter().any(|o| o == "--apply");
+            let json = options.iter().any(|o| o == "--json");
+
+            let pool = init_db_pool(&database_url).await?;
+            let report = counter_service::reconcile_counters(&pool, &clock, apply).await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_reconcile(&report);
+            }
+            if !apply && !report.repairs.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        "counters" => {
+            let pool = init_db_pool(&database_url).await?;
+            let counters = counter_service::list_counters(&pool).await?;
+            if counters.is_empty() {
+                println!("No counters found");
+            }
+            for counter in counters {
+                println!(
+                    "{}: current {} (seeded {})",
+                    counter.scope_key, counter.current_value, counter.seeded_value
+                );
+            }
+        }
+        "seed" => {
+            let scope_key = option_value(options, "--scope")?
+                .ok_or_else(|| anyhow::anyhow!("seed requires --scope\n\n{}", USAGE))?;
+            let next_value: i64 = option_value(options, "--next")?
+                .ok_or_else(|| anyhow::anyhow!("seed requires --next\n\n{}", USAGE))?
+                .parse()
+                .map_err(|e| anyhow::anyhow!("Invalid --next value: {}", e))?;
+
+            let pool = init_db_pool(&database_url).await?;
+            let seed = counter_service::seed_counter(&pool, &clock, scope_key, next_value).await?;
+            match seed.previous_value {
+                Some(previous) => println!(
+                    "{}: counter moved from {} to {}, next number uses {}",
+                    seed.scope_key,
+                    previous,
+                    seed.next_value - 1,
+                    seed.next_value
+                ),
+                None => println!(
+                    "{}: counter created, next number uses {}",
+                    seed.scope_key, seed.next_value
+                ),
+            }
+        }
+        "verify-files" => {
+            let json = options.iter().any(|o| o == "--json");
+            let verifier = FileVerifier::from_mappings(
+                &std::env::var("FILE_PATH_MAPPINGS").unwrap_or_default(),
+            )?;
+
+            let pool = init_db_pool(&database_url).await?;
+            let summary = file_check_service::verify_files(&pool, &clock, &verifier).await?;
+            let missing = file_check_service::find_missing_files(&pool, None).await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&missing)?);
+            } else {
+                println!(
+                    "{} checked: {} present, {} missing, {} inaccessible, {} unmapped",
+                    summary.checked,
+                    summary.present,
+                    summary.missing,
+                    summary.inaccessible,
+                    summary.unmapped
+                );
+                for file in &missing {
+                    let detail = file.check.error.as_deref().unwrap_or("");
+                    println!(
+                        "  [{}] {} {} {}",
+                        file.check.status,
+                        file.document.document_number,
+                        file.document.file_path,
+                        detail
+                    );
+                }
+            }
+            if !missing.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        "help" | "--help" | "-h" => println!("{}", USAGE),
+        other => anyhow::bail!("Unknown command '{}'\n\n{}", other, USAGE),
+    }
+
+    Ok(())
+}
+
+/// Value following `name` in the option list, if present
+fn option_value<'a>(options: &'a [String], name: &str) -> anyhow::Result<Option<&'a str>> {
+    match options.iter().position(|o| o == name) {
+        Some(i) => options
+            .get(i + 1)
+            .map(|v| Some(v.as_str()))
+            .ok_or_else(|| anyhow::anyhow!("{} requires a value", name)),
+        None => Ok(None),
+    }
+}
+
+fn print_audit(report: &AuditReport) {
+    if report.scopes.is_empty() {
+        println!("No counters found");
+    }
+
+    for scope in &report.scopes {
+        let status = if scope.is_clean() { "OK" } else { "NG" };
+        let current = scope
+            .current_value
+            .map_or_else(|| "missing".to_string(), |v| v.to_string());
+        println!("[{}] {} (counter: {})", status, scope.scope_key, current);
+        print_numbers("issued", &scope.issued);
+        print_numbers("deleted", &scope.deleted);
+        print_numbers("reserved", &scope.reserved);
+        print_numbers("manual collisions", &scope.manual_collisions);
+        if !scope.missing.is_empty() {
+            let missing: Vec<String> = scope.missing.iter().map(|v| v.to_string()).collect();
+            println!("  missing ({}): {}", missing.len(), missing.join(", "));
+        }
+    }
+
+    if !report.unmatched.is_empty() {
+        println!(
+            "Unmatched auto-generated numbers ({}): {}",
+            report.unmatched.len(),
+            report.unmatched.join(", ")
+        );
+    }
+}
+
+fn print_numbers(label: &str, numbers: &[AuditedNumber]) {
+    if numbers.is_empty() {
+        return;
+    }
+    let rendered: Vec<&str> = numbers.iter().map(|n| n.document_number.as_str()).collect();
+    println!("  {} ({}): {}", label, numbers.len(), rendered.join(", "));
+}
+
+fn print_reconcile(report: &ReconcileReport) {
+    if report.repairs.is_empty() {
+        println!("All counters are up to date");
+    }
+    for repair in &report.repairs {
+        let current = repair
+            .current_value
+            .map_or_else(|| "missing".to_string(), |v| v.to_string());
+        let action = if report.applied {
+            "raised to"
+        } else {
+            "should be"
+        };
+        println!(
+            "{}: counter {} {} {}",
+            repair.scope_key, current, action, repair.highest_in_use
+        );
+    }
+    if !report.unmatched.is_empty() {
+        println!(
+            "Generated numbers not traceable to a counter: {}",
+            report.unmatched.join(", ")
+        );
+    }
+}
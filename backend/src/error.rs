@@ -18,6 +18,9 @@ pub enum Error {
     #[error("Invalid path: must be absolute path")]
     RelativePathNotAllowed,
 
+    #[error("Invalid path: {0}")]
+    InvalidPath(String),
+
     #[error("Invalid type code: {0}")]
     InvalidTypeCode(String),
 
@@ -39,6 +42,35 @@ pub enum Error {
     #[error("Duplicate document number: {0}")]
     DuplicateDocumentNumber(String),
 
+    #[error("Document number is reserved: {0}")]
+    ReservedDocumentNumber(String),
+
+    #[error("File path {file_path} is already used by {}", document_numbers.join(", "))]
+    DuplicateFilePath {
+        file_path: String,
+        /// Live documents at the same path
+        document_numbers: Vec<String>,
+    },
+
+    #[error("File path {file_path} is outside the root directory {root_directory}")]
+    PathOutsideRoot {
+        file_path: String,
+        root_directory: String,
+    },
+
+    #[error("{entity} already exists: {key}")]
+    AlreadyExists { entity: &'static str, key: String },
+
+    #[error("Invalid check digit in document number '{number}'")]
+    InvalidCheckDigit {
+        number: String,
+        /// Valid numbers one typing mistake away
+        suggestions: Vec<String>,
+    },
+
+    #[error("Counter exhausted for scope {scope_key}: maximum value {max_value} reached")]
+    CounterExhausted { scope_key: String, max_value: i64 },
+
     #[error("Concurrent modification detected")]
     ConcurrentModification,
 
@@ -60,3 +92,36 @@ pub enum Error {
     #[error("Internal error: {0}")]
     Internal(String),
 }
+
+impl Error {
+    /// Machine-readable error code (stable, snake_case)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::NotFound(_) => "not_found",
+            Error::Validation(_) => "validation",
+            Error::DocumentNotFound(_) => "document_not_found",
+            Error::RelativePathNotAllowed => "relative_path_not_allowed",
+            Error::InvalidPath(_) => "invalid_path",
+            Error::InvalidTypeCode(_) => "invalid_type_code",
+            Error::DepartmentNotFound(_) => "department_not_found",
+            Error::SectionNotFound(_) => "section_not_found",
+            Error::UserNotFound(_) => "user_not_found",
+            Error::BusinessTaskNotFound(_) => "business_task_not_found",
+            Error::UnauthorizedDocumentType => "unauthorized_document_type",
+            Error::DuplicateDocumentNumber(_) => "duplicate_document_number",
+            Error::ReservedDocumentNumber(_) => "reserved_document_number",
+            Error::DuplicateFilePath { .. } => "duplicate_file_path",
+            Error::PathOutsideRoot { .. } => "path_outside_root",
+            Error::AlreadyExists { .. } => "already_exists",
+            Error::InvalidCheckDigit { .. } => "invalid_check_digit",
+            Error::CounterExhausted { .. } => "counter_exhausted",
+            Error::ConcurrentModification => "concurrent_modification",
+            Error::InvalidRuleComponent(_) => "invalid_rule_component",
+            Error::Database(_) => "database",
+            Error::Migration(_) => "migration",
+            Error::Serialization(_) => "serialization",
+            Error::Io(_) => "io",
+            Error::Internal(_) => "internal",
+        }
+    }
+}
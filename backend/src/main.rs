@@ -6,6 +6,11 @@ use tower_http::cors::CorsLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use document_path_db::api::create_router;
+use document_path_db::models::UserId;
+use document_path_db::services::clock::{DEFAULT_TIMEZONE, GenerationClock};
+use document_path_db::services::file_check_service::{self, FileVerifier};
+use document_path_db::services::{path_service, reservation_service};
+use document_path_db::services::watch_service::{FileWatcher, WatchMode};
 use document_path_db::storage::init_db_pool;
 
 #[tokio::main]
@@ -32,8 +37,68 @@ async fn main() -> anyhow::Result<()> {
     sqlx::migrate!("./migrations").run(&pool).await?;
     tracing::info!("Migrations completed");
 
+    // Comparison keys of rows written before paths were canonicalised
+    let refreshed = path_service::refresh_path_keys(&pool).await?;
+    if refreshed > 0 {
+        tracing::info!("Recomputed path keys of {} document(s)", refreshed);
+    }
+
+    // Business timezone for number generation (year/month/day components)
+    let timezone = match std::env::var("BUSINESS_TIMEZONE") {
+        Ok(name) => name
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid BUSINESS_TIMEZONE '{}': {}", name, e))?,
+        Err(_) => DEFAULT_TIMEZONE,
+    };
+    tracing::info!("Business timezone: {}", timezone);
+
+    let clock = GenerationClock::system(timezone);
+
+    // Expire unconfirmed number reservations in the background
+    tokio::spawn(expire_reservations_periodically(pool.clone(), clock.clone()));
+
+    // Optional on-disk file verification (off unless an interval is set)
+    if let Ok(seconds) = std::env::var("FILE_VERIFY_INTERVAL_SECS") {
+        let seconds: u64 = seconds
+            .parse()
+            .ok()
+            .filter(|seconds| *seconds > 0)
+            .ok_or_else(|| anyhow::anyhow!("Invalid FILE_VERIFY_INTERVAL_SECS '{}'", seconds))?;
+        let verifier =
+            FileVerifier::from_mappings(&std::env::var("FILE_PATH_MAPPINGS").unwrap_or_default())?;
+        tracing::info!("File verification every {} second(s)", seconds);
+        tokio::spawn(verify_files_periodically(
+            pool.clone(),
+            clock.clone(),
+            verifier,
+            std::time::Duration::from_secs(seconds),
+        ));
+    }
+
+    // Optional filesystem watcher registering files dropped into type roots
+    if let Ok(mode) = std::env::var("FILE_WATCH") {
+        let mode: WatchMode = mode.parse()?;
+        let user_id = std::env::var("WATCH_USER")
+            .map_err(|_| anyhow::anyhow!("FILE_WATCH requires WATCH_USER"))?;
+        let verifier =
+            FileVerifier::from_mappings(&std::env::var("FILE_PATH_MAPPINGS").unwrap_or_default())?;
+        let watcher = FileWatcher::new(
+            pool.clone(),
+            clock.clone(),
+            mode,
+            &UserId::new(user_id),
+            &verifier,
+        )
+        .await?;
+        tokio::spawn(async move {
+            if let Err(e) = watcher.run().await {
+                tracing::warn!("File watcher stopped: {}", e);
+            }
+        });
+    }
+
     // Build router with API endpoints
-    let app = create_router(pool.clone())
+    let app = create_router(pool.clone(), clock)
         .route("/health", get(health_check))
         .layer(CorsLayer::permissive());
 
@@ -49,6 +114,31 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("  PUT    /api/documents/:id/path  - Update document path");
     tracing::info!("  DELETE /api/documents/:id       - Delete document (logical)");
     tracing::info!("  GET    /api/documents/search    - Search documents");
+    tracing::info!("  GET    /api/documents/preview   - Preview next number (dry run)");
+    tracing::info!("  GET    /api/documents/capacity  - Remaining counter capacity");
+    tracing::info!("  GET    /api/documents/duplicate-paths - Live documents sharing a file path");
+    tracing::info!("  GET    /api/documents/containment-violations - Documents outside their type's root");
+    tracing::info!("  GET    /api/documents/missing-files - Documents whose file is missing");
+    tracing::info!("  GET    /api/documents/parse     - Decompose number into rule components");
+    tracing::info!("  POST   /api/reservations        - Reserve next number");
+    tracing::info!("  GET    /api/reservations        - List reservations");
+    tracing::info!("  GET    /api/reservations/unused - Numbers reserved but never used");
+    tracing::info!("  POST   /api/reservations/:id/confirm - Create document for reservation");
+    tracing::info!("  POST   /api/reservations/:id/release - Release reservation");
+    tracing::info!("  GET    /api/audit/counters      - Numbering audit report");
+    tracing::info!("  GET    /api/counters            - List counters");
+    tracing::info!("  PUT    /api/counters/:scope_key - Seed counter (next value)");
+    tracing::info!("  GET    /api/counters/reconcile  - Counters behind their documents");
+    tracing::info!("  POST   /api/counters/reconcile  - Repair counters behind their documents");
+    tracing::info!("  GET    /api/document-types      - List active document types");
+    tracing::info!("  POST   /api/document-types      - Create document type (rule pattern)");
+    tracing::info!("  PUT    /api/document-types/:code/manual-numbers - Set manual number policy");
+    tracing::info!("  PUT    /api/document-types/:code/path-template - Set file path template");
+    tracing::info!("  PUT    /api/document-types/:code/path-uniqueness - Set path uniqueness policy");
+    tracing::info!("  PUT    /api/document-types/:code/path-containment - Set path containment policy");
+    tracing::info!("  PUT    /api/document-types/:code/watch-pattern - Set watcher file name pattern");
+    tracing::info!("  GET    /api/document-types/:code/rules - List generation rule versions");
+    tracing::info!("  POST   /api/document-types/:code/rules - Schedule generation rule change");
     tracing::info!("  GET    /health                  - Health check");
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
@@ -57,6 +147,48 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Interval between reservation expiry sweeps
+const RESERVATION_EXPIRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+async fn expire_reservations_periodically(pool: sqlx::SqlitePool, clock: GenerationClock) {
+    let mut interval = tokio::time::interval(RESERVATION_EXPIRY_INTERVAL);
+    loop {
+        interval.tick().await;
+        match reservation_service::expire_reservations(&pool, &clock).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Expired {} number reservation(s)", count),
+            Err(e) => tracing::warn!("Reservation expiry sweep failed: {}", e),
+        }
+    }
+}
+
+async fn verify_files_periodically(
+    pool: sqlx::SqlitePool,
+    clock: GenerationClock,
+    verifier: FileVerifier,
+    period: std::time::Duration,
+) {
+    let mut interval = tokio::time::interval(period);
+    loop {
+        interval.tick().await;
+        match file_check_service::verify_files(&pool, &clock, &verifier).await {
+            Ok(summary) if summary.missing + summary.inaccessible > 0 => tracing::warn!(
+                "File verification: {} checked, {} missing, {} inaccessible, {} unmapped",
+                summary.checked,
+                summary.missing,
+                summary.inaccessible,
+                summary.unmapped
+            ),
+            Ok(summary) => tracing::info!(
+                "File verification: {} checked, {} unmapped",
+                summary.checked,
+                summary.unmapped
+            ),
+            Err(e) => tracing::warn!("File verification failed: {}", e),
+        }
+    }
+}
+
 async fn health_check() -> &'static str {
     "OK"
 }
@@ -0,0 +1,233 @@
+//! Check digits for hand-typed document numbers
+//!
+//! The check character is computed over the text before it. Characters the
+//! algorithm does not cover (separators, kana/kanji type names) are skipped,
+//! so only the digits (or alphanumerics for MOD 37,36) are protected.
+
+use serde::{Deserialize, Serialize};
+
+const DIGITS: &[u8] = b"0123456789";
+const ALPHANUMERICS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Check digit algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CheckDigitAlgorithm {
+    /// Luhn mod 10 over the digits (check: 0-9)
+    Luhn,
+    /// Weighted mod 11 over the digits, weights 2..=10 repeating from the right
+    /// (check: 0-9 or X)
+    Mod11,
+    /// ISO 7064 MOD 37,36 over digits and uppercase letters (check: 0-9 or A-Z)
+    Mod37_36,
+}
+
+impl CheckDigitAlgorithm {
+    /// Characters covered by the algorithm
+    fn alphabet(&self) -> &'static [u8] {
+        match self {
+            CheckDigitAlgorithm::Luhn | CheckDigitAlgorithm::Mod11 => DIGITS,
+            CheckDigitAlgorithm::Mod37_36 => ALPHANUMERICS,
+        }
+    }
+
+    /// Values of the covered characters in `text`, left to right
+    fn values(&self, text: &str) -> Vec<u32> {
+        let alphabet = self.alphabet();
+        text.bytes()
+            .filter_map(|b| alphabet.iter().position(|a| *a == b))
+            .map(|v| v as u32)
+            .collect()
+    }
+
+    /// Check character for `payload`
+    pub fn check_char(&self, payload: &str) -> char {
+        let values = self.values(payload);
+        match self {
+            CheckDigitAlgorithm::Luhn => {
+                let sum: u32 = values
+                    .iter()
+                    .rev()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        if i % 2 == 0 {
+                            let doubled = v * 2;
+                            if doubled > 9 { doubled - 9 } else { doubled }
+                        } else {
+                            *v
+                        }
+                    })
+                    .sum();
+                char::from(DIGITS[((10 - sum % 10) % 10) as usize])
+            }
+            CheckDigitAlgorithm::Mod11 => {
+                let sum = values.iter().rev().enumerate().fold(0_u64, |acc, (i, v)| {
+                    (acc + mod11_weight(i) * *v as u64) % 11
+                });
+                match (11 - sum) % 11 {
+                    10 => 'X',
+                    check => char::from(DIGITS[check as usize]),
+                }
+            }
+            CheckDigitAlgorithm::Mod37_36 => {
+                let check = values
+                    .iter()
+                    .fold(36 / 2, |check, v| mod37_36_step(check, *v));
+                let adjusted = (1 + 36 - (if check == 0 { 36 } else { check }) * 2 % 37) % 36;
+                char::from(ALPHANUMERICS[adjusted as usize])
+            }
+        }
+    }
+
+    /// Whether the last character of `number` is the check character
+    /// of the text before it
+    pub fn is_valid(&self, number: &str) -> bool {
+        let mut chars = number.chars();
+        match chars.next_back() {
+            Some(check) => self.check_char(chars.as_str()) == check,
+            None => false,
+        }
+    }
+
+    /// `payload` followed by its check character
+    pub fn append_check_char(&self, payload: &str) -> String {
+        format!("{}{}", payload, self.check_char(payload))
+    }
+
+    /// Valid numbers one typing mistake away from `number`: the check
+    /// character recomputed, swapped adjacent characters, and single
+    /// mistyped characters. Closest guesses first, without duplicates.
+    pub fn corrections(&self, number: &str) -> Vec<String> {
+        let Some((index, _)) = number.char_indices().next_back() else {
+            return Vec::new();
+        };
+        let payload = &number[..index];
+        let mut candidates = vec![self.append_check_char(payload)];
+
+        let alphabet = self.alphabet();
+        let covered = |c: char| c.is_ascii() && (alphabet.contains(&(c as u8)) || c == 'X');
+        let chars: Vec<char> = number.chars().collect();
+
+        for i in 1..chars.len() {
+            if chars[i - 1] != chars[i] && covered(chars[i - 1]) && covered(chars[i]) {
+                let mut swapped = chars.clone();
+                swapped.swap(i - 1, i);
+                candidates.push(swapped.into_iter().collect());
+            }
+        }
+        for (i, c) in chars.iter().enumerate().take(chars.len() - 1) {
+            if !covered(*c) {
+                continue;
+            }
+            for replacement in alphabet.iter().map(|b| char::from(*b)) {
+                if replacement != *c {
+                    let mut mistyped = chars.clone();
+                    mistyped[i] = replacement;
+                    candidates.push(mistyped.into_iter().collect());
+                }
+            }
+        }
+
+        let mut corrections: Vec<String> = Vec::new();
+        for candidate in candidates {
+            if candidate != number && self.is_valid(&candidate) && !corrections.contains(&candidate)
+            {
+                corrections.push(candidate);
+            }
+        }
+        corrections
+    }
+}
+
+/// Weight of the `i`-th digit from the right. The weights cycle through
+/// 2..=10 so none is a multiple of 11 and every single-digit error changes
+/// the sum.
+fn mod11_weight(i: usize) -> u64 {
+    (i % 9) as u64 + 2
+}
+
+/// One step of the ISO 7064 hybrid system with M = 36
+fn mod37_36_step(check: u32, value: u32) -> u32 {
+    let carried = if check == 0 { 36 } else { check };
+    ((carried * 2) % 37 + value) % 36
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_luhn() {
+        // Well-known Luhn example
+        assert_eq!(CheckDigitAlgorithm::Luhn.check_char("7992739871"), '3');
+        assert!(CheckDigitAlgorithm::Luhn.is_valid("79927398713"));
+        assert!(!CheckDigitAlgorithm::Luhn.is_valid("79927398731"));
+        // Letters and separators are skipped
+        assert_eq!(CheckDigitAlgorithm::Luhn.check_char("AGI-7992739871-"), '3');
+    }
+
+    #[test]
+    fn test_mod11() {
+        // ISBN-10 uses the same weights
+        assert_eq!(CheckDigitAlgorithm::Mod11.check_char("030640615"), '2');
+        assert_eq!(CheckDigitAlgorithm::Mod11.check_char("080442957"), 'X');
+        assert!(CheckDigitAlgorithm::Mod11.is_valid("080442957X"));
+    }
+
+    #[test]
+    fn test_mod11_detects_substitutions_in_long_numbers() {
+        let algorithm = CheckDigitAlgorithm::Mod11;
+        let number = algorithm.append_check_char("AGI25100123456");
+        let digits: Vec<usize> = number
+            .char_indices()
+            .filter(|(_, c)| c.is_ascii_digit())
+            .map(|(i, _)| i)
+            .collect();
+        assert!(digits.len() > 10);
+
+        for i in digits.into_iter().take_while(|i| *i < number.len() - 1) {
+            for replacement in '0'..='9' {
+                let mut typo = number.clone();
+                if typo[i..].starts_with(replacement) {
+                    continue;
+                }
+                typo.replace_range(i..i + 1, &replacement.to_string());
+                assert!(!algorithm.is_valid(&typo), "{} not detected", typo);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mod37_36() {
+        // ISO 7064 MOD 37,36 reference value
+        assert_eq!(
+            CheckDigitAlgorithm::Mod37_36.check_char("A12425GABC1234002"),
+            'M'
+        );
+        assert!(CheckDigitAlgorithm::Mod37_36.is_valid("A12425GABC1234002M"));
+        assert!(!CheckDigitAlgorithm::Mod37_36.is_valid("A12425GACB1234002M"));
+    }
+
+    #[test]
+    fn test_corrections_cover_transposition_and_check_digit() {
+        let algorithm = CheckDigitAlgorithm::Mod37_36;
+        let number = algorithm.append_check_char("AGI2510012");
+
+        // Swapped digits
+        let typo = number.replacen("12", "21", 1);
+        assert!(!algorithm.is_valid(&typo));
+        assert!(algorithm.corrections(&typo).contains(&number));
+
+        // Wrong check character: the recomputed one comes first
+        let mut wrong_check = number.clone();
+        wrong_check.pop();
+        wrong_check.push(if number.ends_with('0') { '1' } else { '0' });
+        assert_eq!(algorithm.corrections(&wrong_check).first(), Some(&number));
+
+        assert!(
+            algorithm
+                .corrections(&number)
+                .iter()
+                .all(|c| algorithm.is_valid(c) && c != &number)
+        );
+    }
+}
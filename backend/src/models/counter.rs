@@ -0,0 +1,15 @@
+//! Counter entity
+
+use serde::{Deserialize, Serialize};
+
+/// Auto-increment counter of a single scope (カウンター)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counter {
+    /// Counter scope key (e.g., "A_G_I_2025_10")
+    pub scope_key: String,
+    /// Last value handed out
+    pub current_value: i64,
+    /// Values up to this one were issued outside the system
+    /// (ledger import or the rule's starting value)
+    pub seeded_value: i64,
+}
@@ -1,9 +1,8 @@
 //! Document Path entity
 
-use crate::models::{DeptCode, DocumentId, SectionCode, TaskId, TypeCode, UserId};
+use crate::models::{DeptCode, DocumentId, SectionCode, StoredPath, TaskId, TypeCode, UserId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
 
 /// Document Path (文書パス)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -23,7 +22,7 @@ pub struct DocumentPath {
     /// 作成ユーザー
     pub user: UserId,
     /// ファイルパス (絶対パス)
-    pub file_path: PathBuf,
+    pub file_path: StoredPath,
     /// 作成日時
     pub created_at: DateTime<Utc>,
     /// 更新日時
@@ -32,6 +31,9 @@ pub struct DocumentPath {
     pub generated: bool,
     /// 論理削除フラグ
     pub deleted: bool,
+    /// 番号を生成したルールバージョン (手動番号は None)
+    #[serde(default)]
+    pub generation_rule_id: Option<i64>,
 }
 
 impl DocumentPath {
@@ -42,7 +44,7 @@ impl DocumentPath {
         department: DeptCode,
         section: SectionCode,
         user: UserId,
-        file_path: PathBuf,
+        file_path: StoredPath,
     ) -> Self {
         let now = Utc::now();
         Self {
@@ -58,6 +60,7 @@ impl DocumentPath {
             updated_at: now,
             generated: true,
             deleted: false,
+            generation_rule_id: None,
         }
     }
 
@@ -68,7 +71,7 @@ impl DocumentPath {
         department: DeptCode,
         section: SectionCode,
         user: UserId,
-        file_path: PathBuf,
+        file_path: StoredPath,
     ) -> Self {
         let now = Utc::now();
         Self {
@@ -84,6 +87,7 @@ impl DocumentPath {
             updated_at: now,
             generated: false, // Manual documents
             deleted: false,
+            generation_rule_id: None,
         }
     }
 
@@ -94,7 +98,7 @@ impl DocumentPath {
     }
 
     /// Update the file path
-    pub fn update_path(&mut self, new_path: PathBuf) {
+    pub fn update_path(&mut self, new_path: StoredPath) {
         self.file_path = new_path;
         self.updated_at = Utc::now();
     }
@@ -134,7 +138,7 @@ impl DocumentPath {
         &self.user
     }
 
-    pub fn file_path(&self) -> &PathBuf {
+    pub fn file_path(&self) -> &StoredPath {
         &self.file_path
     }
 
@@ -167,7 +171,7 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/docs/contracts/AGI-2509001.pdf"),
+            StoredPath::new("/docs/contracts/AGI-2509001.pdf"),
         );
 
         assert_eq!(doc.document_number, "AGI-2509001");
@@ -184,7 +188,7 @@ mod tests {
             DeptCode::new('K'),
             SectionCode::new('T'),
             UserId::new("user002"),
-            PathBuf::from("/external/import.pdf"),
+            StoredPath::new("/external/import.pdf"),
         );
 
         assert_eq!(doc.document_number, "MANUAL-001");
@@ -200,7 +204,7 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/docs/test.pdf"),
+            StoredPath::new("/docs/test.pdf"),
         )
         .with_task(TaskId::new("task001"));
 
@@ -216,15 +220,15 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/old/path.pdf"),
+            StoredPath::new("/old/path.pdf"),
         );
 
         let old_updated_at = doc.updated_at;
         std::thread::sleep(std::time::Duration::from_millis(10));
 
-        doc.update_path(PathBuf::from("/new/path.pdf"));
+        doc.update_path(StoredPath::new("/new/path.pdf"));
 
-        assert_eq!(doc.file_path, PathBuf::from("/new/path.pdf"));
+        assert_eq!(doc.file_path, StoredPath::new("/new/path.pdf"));
         assert!(doc.updated_at > old_updated_at);
     }
 
@@ -236,7 +240,7 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/docs/test.pdf"),
+            StoredPath::new("/docs/test.pdf"),
         );
 
         assert!(!doc.deleted);
@@ -252,7 +256,7 @@ mod tests {
             DeptCode::new('K'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/docs/ringi/りん議I-25009.pdf"),
+            StoredPath::new("/docs/ringi/りん議I-25009.pdf"),
         );
 
         assert_eq!(doc.document_number, "りん議I-25009");
@@ -1,7 +1,160 @@
 //! Document Type entity
 
-use crate::models::{PathGenerationRule, TypeCode};
+use crate::error::{Error, Result};
+use crate::models::{PathGenerationRule, PathTemplate, StoredPath, TypeCode, WatchPattern};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How manual numbers are checked against the type's generation rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManualNumberMode {
+    /// Any unique number is accepted
+    #[default]
+    Off,
+    /// Numbers must match the rule; numbers on the live auto sequence are accepted with a warning
+    Warn,
+    /// Numbers must match the rule and stay off the live auto sequence
+    Reject,
+}
+
+impl ManualNumberMode {
+    /// Value stored in the `document_types.manual_number_mode` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ManualNumberMode::Off => "off",
+            ManualNumberMode::Warn => "warn",
+            ManualNumberMode::Reject => "reject",
+        }
+    }
+}
+
+impl fmt::Display for ManualNumberMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ManualNumberMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "off" => Ok(ManualNumberMode::Off),
+            "warn" => Ok(ManualNumberMode::Warn),
+            "reject" => Ok(ManualNumberMode::Reject),
+            other => Err(Error::Validation(format!(
+                "Unknown manual number mode: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Manual number policy of a document type (strict mode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ManualNumberPolicy {
+    pub mode: ManualNumberMode,
+    /// Advance the live counter past accepted manual numbers on its sequence,
+    /// so that auto numbering does not run into them later
+    #[serde(default)]
+    pub bump_counter: bool,
+}
+
+/// What happens when a document is given a file path another live document
+/// already uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathUniqueness {
+    /// Shared paths are accepted
+    #[default]
+    Allow,
+    /// Shared paths are accepted with a warning
+    Warn,
+    /// Shared paths are refused
+    Reject,
+}
+
+impl PathUniqueness {
+    /// Value stored in the `document_types.path_uniqueness` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PathUniqueness::Allow => "allow",
+            PathUniqueness::Warn => "warn",
+            PathUniqueness::Reject => "reject",
+        }
+    }
+}
+
+impl fmt::Display for PathUniqueness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PathUniqueness {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "allow" => Ok(PathUniqueness::Allow),
+            "warn" => Ok(PathUniqueness::Warn),
+            "reject" => Ok(PathUniqueness::Reject),
+            other => Err(Error::Validation(format!(
+                "Unknown path uniqueness policy: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Whether document file paths must lie under the type's root directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathContainment {
+    /// Paths are not checked
+    #[default]
+    Off,
+    /// Paths outside the root are accepted with a warning
+    Warn,
+    /// Paths outside the root are refused
+    Strict,
+}
+
+impl PathContainment {
+    /// Value stored in the `document_types.path_containment` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PathContainment::Off => "off",
+            PathContainment::Warn => "warn",
+            PathContainment::Strict => "strict",
+        }
+    }
+}
+
+impl fmt::Display for PathContainment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PathContainment {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "off" => Ok(PathContainment::Off),
+            "warn" => Ok(PathContainment::Warn),
+            "strict" => Ok(PathContainment::Strict),
+            other => Err(Error::Validation(format!(
+                "Unknown path containment policy: {}",
+                other
+            ))),
+        }
+    }
+}
 
 /// Document Type (文書種類)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -11,18 +164,36 @@ pub struct DocumentType {
     /// 説明
     pub description: String,
     /// ルートディレクトリ
-    pub root_directory: String,
+    pub root_directory: StoredPath,
     /// 番号生成ルール
     pub generation_rule: PathGenerationRule,
     /// アクティブ/非アクティブ状態
     pub active: bool,
+    /// 番号生成に使う業務タイムゾーン (None = デプロイ既定値)
+    #[serde(default)]
+    pub timezone: Option<Tz>,
+    /// 手動番号の検証ポリシー
+    #[serde(default)]
+    pub manual_numbers: ManualNumberPolicy,
+    /// 自動生成文書のファイルパステンプレート (None = ルートディレクトリ)
+    #[serde(default)]
+    pub path_template: Option<PathTemplate>,
+    /// 同一ファイルパスを共有する文書の扱い
+    #[serde(default)]
+    pub path_uniqueness: PathUniqueness,
+    /// ルートディレクトリ外のファイルパスの扱い
+    #[serde(default)]
+    pub path_containment: PathContainment,
+    /// ファイル監視で自動登録するファイル名パターン (None = 監視しない)
+    #[serde(default)]
+    pub watch_pattern: Option<WatchPattern>,
 }
 
 impl DocumentType {
     pub fn new(
         code: impl Into<String>,
         description: impl Into<String>,
-        root_directory: impl Into<String>,
+        root_directory: impl Into<StoredPath>,
         generation_rule: PathGenerationRule,
     ) -> Self {
         Self {
@@ -31,6 +202,12 @@ impl DocumentType {
             root_directory: root_directory.into(),
             generation_rule,
             active: true,
+            timezone: None,
+            manual_numbers: ManualNumberPolicy::default(),
+            path_template: None,
+            path_uniqueness: PathUniqueness::default(),
+            path_containment: PathContainment::default(),
+            watch_pattern: None,
         }
     }
 
@@ -39,6 +216,36 @@ impl DocumentType {
         self
     }
 
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = Some(timezone);
+        self
+    }
+
+    pub fn with_manual_numbers(mut self, policy: ManualNumberPolicy) -> Self {
+        self.manual_numbers = policy;
+        self
+    }
+
+    pub fn with_path_template(mut self, template: PathTemplate) -> Self {
+        self.path_template = Some(template);
+        self
+    }
+
+    pub fn with_path_uniqueness(mut self, policy: PathUniqueness) -> Self {
+        self.path_uniqueness = policy;
+        self
+    }
+
+    pub fn with_path_containment(mut self, policy: PathContainment) -> Self {
+        self.path_containment = policy;
+        self
+    }
+
+    pub fn with_watch_pattern(mut self, pattern: WatchPattern) -> Self {
+        self.watch_pattern = Some(pattern);
+        self
+    }
+
     // Getters
     pub fn code(&self) -> &TypeCode {
         &self.code
@@ -48,7 +255,7 @@ impl DocumentType {
         &self.description
     }
 
-    pub fn root_directory(&self) -> &str {
+    pub fn root_directory(&self) -> &StoredPath {
         &self.root_directory
     }
 
@@ -59,6 +266,30 @@ impl DocumentType {
     pub fn active(&self) -> bool {
         self.active
     }
+
+    pub fn timezone(&self) -> Option<Tz> {
+        self.timezone
+    }
+
+    pub fn manual_numbers(&self) -> ManualNumberPolicy {
+        self.manual_numbers
+    }
+
+    pub fn path_template(&self) -> Option<&PathTemplate> {
+        self.path_template.as_ref()
+    }
+
+    pub fn path_uniqueness(&self) -> PathUniqueness {
+        self.path_uniqueness
+    }
+
+    pub fn path_containment(&self) -> PathContainment {
+        self.path_containment
+    }
+
+    pub fn watch_pattern(&self) -> Option<&WatchPattern> {
+        self.watch_pattern.as_ref()
+    }
 }
 
 #[cfg(test)]
@@ -72,8 +303,9 @@ mod tests {
 
         assert_eq!(doc_type.code.0, "A");
         assert_eq!(doc_type.description, "契約書");
-        assert_eq!(doc_type.root_directory, "/docs/contracts/");
+        assert_eq!(doc_type.root_directory.as_str(), "/docs/contracts");
         assert!(doc_type.active);
+        assert!(doc_type.timezone.is_none());
     }
 
     #[test]
@@ -91,4 +323,59 @@ mod tests {
         let doc_type = DocumentType::new("A", "契約書", "/docs/", rule).inactive();
         assert!(!doc_type.active);
     }
+
+    #[test]
+    fn test_document_type_with_timezone() {
+        let rule = PathGenerationRule::example_agi();
+        let doc_type =
+            DocumentType::new("A", "契約書", "/docs/", rule).with_timezone(chrono_tz::Asia::Tokyo);
+        assert_eq!(doc_type.timezone(), Some(chrono_tz::Asia::Tokyo));
+    }
+
+    #[test]
+    fn test_manual_number_mode_roundtrip() -> Result<()> {
+        for mode in [
+            ManualNumberMode::Off,
+            ManualNumberMode::Warn,
+            ManualNumberMode::Reject,
+        ] {
+            assert_eq!(mode.as_str().parse::<ManualNumberMode>()?, mode);
+        }
+        assert!("strict".parse::<ManualNumberMode>().is_err());
+
+        let rule = PathGenerationRule::example_agi();
+        let doc_type = DocumentType::new("A", "契約書", "/docs/", rule);
+        assert_eq!(doc_type.manual_numbers().mode, ManualNumberMode::Off);
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_uniqueness_roundtrip() -> Result<()> {
+        for policy in [
+            PathUniqueness::Allow,
+            PathUniqueness::Warn,
+            PathUniqueness::Reject,
+        ] {
+            assert_eq!(policy.as_str().parse::<PathUniqueness>()?, policy);
+        }
+        assert!("unique".parse::<PathUniqueness>().is_err());
+
+        let rule = PathGenerationRule::example_agi();
+        let doc_type = DocumentType::new("A", "契約書", "/docs/", rule);
+        assert_eq!(doc_type.path_uniqueness(), PathUniqueness::Allow);
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_containment_roundtrip() -> Result<()> {
+        for policy in [
+            PathContainment::Off,
+            PathContainment::Warn,
+            PathContainment::Strict,
+        ] {
+            assert_eq!(policy.as_str().parse::<PathContainment>()?, policy);
+        }
+        assert!("reject".parse::<PathContainment>().is_err());
+        Ok(())
+    }
 }
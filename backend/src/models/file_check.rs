@@ -0,0 +1,167 @@
+//! On-disk file check entity
+
+use crate::error::{Error, Result};
+use crate::models::{DocumentId, StoredPath};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Outcome of checking a document's file on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    /// File exists
+    Present,
+    /// Nothing exists at the path
+    Missing,
+    /// The path could not be read (permissions, not a file, I/O error)
+    Inaccessible,
+    /// The path has no local equivalent (e.g. a UNC path without a mapping)
+    Unmapped,
+}
+
+impl FileStatus {
+    /// Value stored in the `file_checks.status` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileStatus::Present => "present",
+            FileStatus::Missing => "missing",
+            FileStatus::Inaccessible => "inaccessible",
+            FileStatus::Unmapped => "unmapped",
+        }
+    }
+}
+
+impl fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FileStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "present" => Ok(FileStatus::Present),
+            "missing" => Ok(FileStatus::Missing),
+            "inaccessible" => Ok(FileStatus::Inaccessible),
+            "unmapped" => Ok(FileStatus::Unmapped),
+            other => Err(Error::Validation(format!("Unknown file status: {}", other))),
+        }
+    }
+}
+
+/// Last check of a document's file (ファイル存在確認)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileCheck {
+    /// 文書識別子
+    pub document_id: DocumentId,
+    /// 確認したパス
+    pub file_path: StoredPath,
+    /// 状態
+    pub status: FileStatus,
+    /// 最後に存在した時のサイズ (bytes)
+    pub size: Option<i64>,
+    /// 最後に存在した時の更新日時
+    pub modified_at: Option<DateTime<Utc>>,
+    /// 最後に存在を確認した日時
+    pub last_seen_at: Option<DateTime<Utc>>,
+    /// 確認日時
+    pub checked_at: DateTime<Utc>,
+    /// 読み取りエラー
+    pub error: Option<String>,
+}
+
+/// Maps stored paths under `prefix` to a local directory, for shares that
+/// are mounted elsewhere on the server (e.g. `\\fs01\docs=/mnt/docs`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathMapping {
+    pub prefix: StoredPath,
+    pub local: PathBuf,
+}
+
+impl PathMapping {
+    /// Local path of `path` if it lies under the prefix
+    pub fn apply(&self, path: &StoredPath) -> Option<PathBuf> {
+        if !path.is_within(&self.prefix) {
+            return None;
+        }
+        let rest = path.as_str().get(self.prefix.as_str().len()..)?;
+        Some(join_segments(&self.local, rest, path.flavor().separator()))
+    }
+}
+
+impl FromStr for PathMapping {
+    type Err = Error;
+
+    /// Parse `<stored prefix>=<local directory>`
+    fn from_str(s: &str) -> Result<Self> {
+        let (prefix, local) = s
+            .split_once('=')
+            .map(|(prefix, local)| (prefix.trim(), local.trim()))
+            .filter(|(prefix, local)| !prefix.is_empty() && !local.is_empty())
+            .ok_or_else(|| {
+                Error::Validation(format!(
+                    "Invalid path mapping '{}': expected <prefix>=<local directory>",
+                    s
+                ))
+            })?;
+        Ok(Self {
+            prefix: StoredPath::parse(prefix)?,
+            local: PathBuf::from(local),
+        })
+    }
+}
+
+/// Append the separator-delimited segments of `rest` to `base`
+fn join_segments(base: &Path, rest: &str, separator: char) -> PathBuf {
+    rest.split(separator)
+        .filter(|segment| !segment.is_empty())
+        .fold(base.to_path_buf(), |path, segment| path.join(segment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_status_roundtrip() -> Result<()> {
+        for status in [
+            FileStatus::Present,
+            FileStatus::Missing,
+            FileStatus::Inaccessible,
+            FileStatus::Unmapped,
+        ] {
+            assert_eq!(status.as_str().parse::<FileStatus>()?, status);
+        }
+        assert!("gone".parse::<FileStatus>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_path_mapping() -> Result<()> {
+        let mapping: PathMapping = r"\\fs01\docs=/mnt/docs".parse()?;
+        assert_eq!(
+            mapping.apply(&StoredPath::new(r"\\FS01\Docs\contracts\a.pdf")),
+            Some(PathBuf::from("/mnt/docs/contracts/a.pdf"))
+        );
+        assert_eq!(
+            mapping.apply(&StoredPath::new(r"\\fs01\docs")),
+            Some(PathBuf::from("/mnt/docs"))
+        );
+        assert_eq!(mapping.apply(&StoredPath::new(r"\\fs01\docs2\a.pdf")), None);
+
+        let mapping: PathMapping = "/docs = /srv/archive/docs".parse()?;
+        assert_eq!(
+            mapping.apply(&StoredPath::new("/docs/contracts/a.pdf")),
+            Some(PathBuf::from("/srv/archive/docs/contracts/a.pdf"))
+        );
+
+        assert!("/docs".parse::<PathMapping>().is_err());
+        assert!("docs=/mnt/docs".parse::<PathMapping>().is_err());
+        Ok(())
+    }
+}
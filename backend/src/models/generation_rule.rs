@@ -1,5 +1,8 @@
 //! Path Generation Rule entity
 
+use crate::error::Error;
+use crate::models::{CheckDigitAlgorithm, DeptCode, SectionCode, TypeCode};
+use chrono::{Datelike, NaiveDate};
 use serde::{Deserialize, Serialize};
 
 /// Rule components for document number generation
@@ -12,14 +15,32 @@ pub enum RuleComponent {
     DeptCode,
     /// Section code (e.g., "I")
     SectionCode,
+    /// Fixed department code, regardless of the creator's department
+    FixedDeptCode { code: char },
+    /// Fixed section code, regardless of the creator's section
+    FixedSectionCode { code: char },
+    /// Static text (e.g., "JP")
+    Literal { text: String },
     /// Year with specified digits (2 or 4)
     Year { digits: u8 },
+    /// Japanese era letter (e.g., "R" for 令和)
+    EraLetter,
+    /// Japanese era name in kanji (e.g., "令和")
+    EraName,
+    /// Japanese era year zero-padded to the specified digits (e.g., 2 → "07")
+    EraYear { digits: u8 },
+    /// Fiscal year with specified digits (2 or 4), named after the year it starts in
+    FiscalYear { digits: u8 },
+    /// Fiscal quarter (1 digit, 1-4)
+    FiscalQuarter,
     /// Month (2 digits, 01-12)
     Month,
     /// Day (2 digits, 01-31)
     Day,
     /// Auto-increment counter
     AutoIncrement,
+    /// Check character over everything rendered before it (must come last)
+    CheckDigit { algorithm: CheckDigitAlgorithm },
 }
 
 /// Counter scope for auto-increment
@@ -33,6 +54,240 @@ pub enum CounterScope {
     TypeSectionYear,
     /// Counter per type, dept, section, year, month (e.g., "A_G_I_2025_09" → 001)
     TypeDeptSectionYearMonth,
+    /// Counter per type and Japanese era year (e.g., "A_R07" → 001)
+    TypeAndEraYear,
+    /// Counter per type, section, and Japanese era year (e.g., "A_I_R07" → 001)
+    TypeSectionEraYear,
+    /// Counter per type and fiscal year (e.g., "A_FY2025" → 001)
+    TypeAndFiscalYear,
+    /// Counter per type, section, and fiscal year (e.g., "A_I_FY2025" → 001)
+    TypeSectionFiscalYear,
+    /// Counter per type, fiscal year, and fiscal quarter (e.g., "A_FY2025_Q1" → 001)
+    TypeFiscalYearQuarter,
+    /// Counter per custom combination of dimensions, keyed in the given order
+    /// (e.g., `[Type, Dept, Year]` → "A_G_2025" → 001)
+    Custom { dimensions: Vec<ScopeDimension> },
+}
+
+/// Dimension a counter scope can be keyed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ScopeDimension {
+    /// Document type code (e.g., "A")
+    Type,
+    /// Department code, or the code fixed by the rule (e.g., "G")
+    Dept,
+    /// Section code, or the code fixed by the rule (e.g., "I")
+    Section,
+    /// Business task ID (e.g., "T001")
+    Task,
+    /// Calendar year (e.g., "2025")
+    Year,
+    /// Japanese era year (e.g., "R07")
+    EraYear,
+    /// Fiscal year (e.g., "FY2025")
+    FiscalYear,
+    /// Fiscal quarter (e.g., "Q1")
+    FiscalQuarter,
+    /// Month (e.g., "09")
+    Month,
+    /// Day of month (e.g., "01")
+    Day,
+    /// Creating user ID (e.g., "user001")
+    User,
+}
+
+impl CounterScope {
+    /// Dimensions of the scope key, in key order.
+    /// Presets expand to the dimensions their keys were always built from,
+    /// so existing counter keys stay valid.
+    pub fn dimensions(&self) -> Vec<ScopeDimension> {
+        use ScopeDimension::*;
+        match self {
+            CounterScope::TypeOnly => vec![Type],
+            CounterScope::TypeAndYear => vec![Type, Year],
+            CounterScope::TypeSectionYear => vec![Type, Section, Year],
+            CounterScope::TypeDeptSectionYearMonth => vec![Type, Dept, Section, Year, Month],
+            CounterScope::TypeAndEraYear => vec![Type, EraYear],
+            CounterScope::TypeSectionEraYear => vec![Type, Section, EraYear],
+            CounterScope::TypeAndFiscalYear => vec![Type, FiscalYear],
+            CounterScope::TypeSectionFiscalYear => vec![Type, Section, FiscalYear],
+            CounterScope::TypeFiscalYearQuarter => vec![Type, FiscalYear, FiscalQuarter],
+            CounterScope::Custom { dimensions } => dimensions.clone(),
+        }
+    }
+
+    /// Whether the scope key depends on `dimension`
+    pub fn uses(&self, dimension: ScopeDimension) -> bool {
+        self.dimensions().contains(&dimension)
+    }
+
+    /// Check that a custom scope has at least one dimension and no repeats
+    pub fn validate(&self) -> crate::error::Result<()> {
+        let dimensions = self.dimensions();
+        if dimensions.is_empty() {
+            return Err(Error::InvalidRuleComponent(
+                "Counter scope needs at least one dimension".to_string(),
+            ));
+        }
+        for (i, dimension) in dimensions.iter().enumerate() {
+            if dimensions[..i].contains(dimension) {
+                return Err(Error::InvalidRuleComponent(format!(
+                    "Counter scope dimension {:?} appears more than once",
+                    dimension
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How the auto-increment counter is rendered in `counter_digits` characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CounterEncoding {
+    /// Zero-padded decimal (e.g., 3 digits → 001 … 999)
+    #[default]
+    Decimal,
+    /// Decimal without padding, at most `counter_digits` wide (e.g., 1 … 999)
+    DecimalUnpadded,
+    /// Zero-padded base36 with uppercase letters (e.g., 3 digits → 001 … ZZZ)
+    Base36,
+    /// Fixed-width letters (e.g., 2 digits → AA, AB … AZ, BA … ZZ)
+    Alphabetic,
+    /// Decimal, then a leading letter once decimal runs out
+    /// (e.g., 3 digits → 001 … 999, A00 … A99, B00 … Z99)
+    DecimalThenAlpha,
+}
+
+const DIGITS36: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+impl CounterEncoding {
+    /// Highest counter value that fits in `width` characters (saturates at i64::MAX)
+    pub fn capacity(&self, width: u8) -> i64 {
+        if width == 0 {
+            return 0;
+        }
+        let width = width as u32;
+        let capacity = match self {
+            CounterEncoding::Decimal | CounterEncoding::DecimalUnpadded => {
+                10_i64.checked_pow(width).map(|n| n - 1)
+            }
+            CounterEncoding::Base36 => 36_i64.checked_pow(width).map(|n| n - 1),
+            CounterEncoding::Alphabetic => 26_i64.checked_pow(width),
+            CounterEncoding::DecimalThenAlpha => 10_i64.checked_pow(width).and_then(|decimal| {
+                let overflow = 10_i64.pow(width - 1).checked_mul(26)?;
+                (decimal - 1).checked_add(overflow)
+            }),
+        };
+        capacity.unwrap_or(i64::MAX)
+    }
+
+    /// Render a counter value. Values outside 1..=capacity fall back to
+    /// zero-padded decimal.
+    pub fn encode(&self, value: i64, width: u8) -> String {
+        let width = width as usize;
+        if value < 1 || value > self.capacity(width as u8) {
+            return format!("{:0width$}", value);
+        }
+        match self {
+            CounterEncoding::Decimal => format!("{:0width$}", value),
+            CounterEncoding::DecimalUnpadded => value.to_string(),
+            CounterEncoding::Base36 => radix(value, DIGITS36, width),
+            CounterEncoding::Alphabetic => radix(value - 1, &DIGITS36[10..], width),
+            CounterEncoding::DecimalThenAlpha => {
+                let decimal = 10_i64.pow(width as u32);
+                if value < decimal {
+                    return format!("{:0width$}", value);
+                }
+                let block = 10_i64.pow(width as u32 - 1);
+                let overflow = value - decimal;
+                let letter = char::from(b'A' + (overflow / block) as u8);
+                if width == 1 {
+                    return letter.to_string();
+                }
+                format!("{}{:0rest$}", letter, overflow % block, rest = width - 1)
+            }
+        }
+    }
+
+    /// Read back a counter value rendered by `encode`
+    pub fn decode(&self, text: &str, width: u8) -> Option<i64> {
+        let width = width as usize;
+        let bytes = text.as_bytes();
+        match self {
+            CounterEncoding::Decimal => {
+                if bytes.len() < width || !bytes.iter().all(u8::is_ascii_digit) {
+                    return None;
+                }
+                text.parse().ok()
+            }
+            CounterEncoding::DecimalUnpadded => {
+                if bytes.first().is_none_or(|b| *b == b'0') || !bytes.iter().all(u8::is_ascii_digit)
+                {
+                    return None;
+                }
+                text.parse().ok()
+            }
+            CounterEncoding::Base36 => {
+                if bytes.len() != width {
+                    return None;
+                }
+                bytes.iter().try_fold(0_i64, |acc, b| {
+                    let digit = DIGITS36.iter().position(|d| d == b)? as i64;
+                    acc.checked_mul(36)?.checked_add(digit)
+                })
+            }
+            CounterEncoding::Alphabetic => {
+                if bytes.len() != width {
+                    return None;
+                }
+                let index = bytes.iter().try_fold(0_i64, |acc, b| {
+                    if !b.is_ascii_uppercase() {
+                        return None;
+                    }
+                    acc.checked_mul(26)?.checked_add((b - b'A') as i64)
+                })?;
+                index.checked_add(1)
+            }
+            CounterEncoding::DecimalThenAlpha => {
+                let (first, rest) = bytes.split_first()?;
+                if bytes.len() != width || !rest.iter().all(u8::is_ascii_digit) {
+                    return None;
+                }
+                if first.is_ascii_digit() {
+                    return text.parse().ok();
+                }
+                if !first.is_ascii_uppercase() {
+                    return None;
+                }
+                let rest: i64 = if rest.is_empty() {
+                    0
+                } else {
+                    text[1..].parse().ok()?
+                };
+                let block = 10_i64.checked_pow(width as u32 - 1)?;
+                let decimal = block.checked_mul(10)?;
+                decimal
+                    .checked_add(block.checked_mul((first - b'A') as i64)?)?
+                    .checked_add(rest)
+            }
+        }
+    }
+}
+
+/// Non-negative `value` written with `digits` (base = digits.len()),
+/// left-padded with the zero digit to `width`
+fn radix(mut value: i64, digits: &[u8], width: usize) -> String {
+    let base = digits.len() as i64;
+    let mut out = Vec::new();
+    while value > 0 {
+        out.push(digits[(value % base) as usize]);
+        value /= base;
+    }
+    while out.len() < width {
+        out.push(digits[0]);
+    }
+    out.reverse();
+    String::from_utf8(out).unwrap_or_default()
 }
 
 /// Path generation rule
@@ -50,6 +305,23 @@ pub struct PathGenerationRule {
     pub counter_scope: CounterScope,
     /// Number of digits for counter (e.g., 3 → 001, 002, ...)
     pub counter_digits: u8,
+    /// How the counter is rendered within `counter_digits`
+    #[serde(default)]
+    pub counter_encoding: CounterEncoding,
+    /// First counter value of a new scope (e.g., 43 to continue a ledger at 043)
+    #[serde(default = "default_counter_start")]
+    pub counter_start: i64,
+    /// First month of the fiscal year (1-12, e.g., 4 → April-March)
+    #[serde(default = "default_fiscal_year_start_month")]
+    pub fiscal_year_start_month: u8,
+}
+
+fn default_fiscal_year_start_month() -> u8 {
+    1
+}
+
+fn default_counter_start() -> i64 {
+    1
 }
 
 impl PathGenerationRule {
@@ -65,6 +337,9 @@ impl PathGenerationRule {
             separators: Vec::new(),
             counter_scope,
             counter_digits,
+            counter_encoding: CounterEncoding::default(),
+            counter_start: default_counter_start(),
+            fiscal_year_start_month: default_fiscal_year_start_month(),
         }
     }
 
@@ -74,6 +349,68 @@ impl PathGenerationRule {
         self
     }
 
+    /// Set the first month of the fiscal year
+    pub fn with_fiscal_year_start_month(mut self, month: u8) -> Self {
+        self.fiscal_year_start_month = month;
+        self
+    }
+
+    /// Set how the counter is rendered
+    pub fn with_counter_encoding(mut self, encoding: CounterEncoding) -> Self {
+        self.counter_encoding = encoding;
+        self
+    }
+
+    /// Set the first counter value of new scopes
+    pub fn with_counter_start(mut self, value: i64) -> Self {
+        self.counter_start = value;
+        self
+    }
+
+    /// Separator placed after the component at `index`
+    /// (see `separators` for how the list is interpreted)
+    pub fn separator_after(&self, index: usize) -> &str {
+        match self.separators.len() {
+            0 => "",
+            1 => &self.separators[0],
+            len => &self.separators[index.min(len - 1)],
+        }
+    }
+
+    /// Department code fixed by a `FixedDeptCode` component, if any
+    pub fn fixed_dept_code(&self) -> Option<DeptCode> {
+        self.components
+            .iter()
+            .find_map(|component| match component {
+                RuleComponent::FixedDeptCode { code } => Some(DeptCode::new(*code)),
+                _ => None,
+            })
+    }
+
+    /// Section code fixed by a `FixedSectionCode` component, if any
+    pub fn fixed_section_code(&self) -> Option<SectionCode> {
+        self.components
+            .iter()
+            .find_map(|component| match component {
+                RuleComponent::FixedSectionCode { code } => Some(SectionCode::new(*code)),
+                _ => None,
+            })
+    }
+
+    /// Algorithm of the check character ending the number, if any
+    pub fn check_digit(&self) -> Option<CheckDigitAlgorithm> {
+        match self.components.last() {
+            Some(RuleComponent::CheckDigit { algorithm }) => Some(*algorithm),
+            _ => None,
+        }
+    }
+
+    /// Highest counter value that fits in `counter_digits` with the rule's
+    /// encoding (e.g., 3 decimal digits → 999)
+    pub fn counter_capacity(&self) -> i64 {
+        self.counter_encoding.capacity(self.counter_digits)
+    }
+
     /// Example: AGI[YYMM][NNN]
     pub fn example_agi() -> Self {
         Self::new(
@@ -106,6 +443,71 @@ impl PathGenerationRule {
     }
 }
 
+/// One immutable version of a document type's generation rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleVersion {
+    /// Rule version ID (generation_rules.id)
+    pub id: i64,
+    /// Document type the version belongs to
+    pub document_type: TypeCode,
+    /// First business date the version applies to (None = since the type was created)
+    pub effective_from: Option<NaiveDate>,
+    /// The rule itself
+    pub rule: PathGenerationRule,
+}
+
+impl RuleVersion {
+    /// Version in effect on `date` among the versions of a single document type
+    pub fn effective_on(versions: &[RuleVersion], date: NaiveDate) -> Option<&RuleVersion> {
+        versions
+            .iter()
+            .filter(|v| v.effective_from.is_none_or(|from| from <= date))
+            .max_by_key(|v| (v.effective_from, v.id))
+    }
+}
+
+/// When a scheduled rule change takes effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleChangeSchedule {
+    /// First day of the next calendar month
+    NextMonth,
+    /// First day of the next fiscal year
+    NextFiscalYear,
+    /// A specific business date
+    On(NaiveDate),
+}
+
+impl RuleChangeSchedule {
+    /// First business date of the change, as seen on `today`
+    pub fn effective_date(
+        &self,
+        today: NaiveDate,
+        fiscal_year_start_month: u8,
+    ) -> Option<NaiveDate> {
+        match self {
+            RuleChangeSchedule::NextMonth => {
+                let (year, month) = if today.month() == 12 {
+                    (today.year() + 1, 1)
+                } else {
+                    (today.year(), today.month() + 1)
+                };
+                NaiveDate::from_ymd_opt(year, month, 1)
+            }
+            RuleChangeSchedule::NextFiscalYear => {
+                let start_month = fiscal_year_start_month as u32;
+                let year = if today.month() >= start_month {
+                    today.year() + 1
+                } else {
+                    today.year()
+                };
+                NaiveDate::from_ymd_opt(year, start_month, 1)
+            }
+            RuleChangeSchedule::On(date) => Some(*date),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,6 +521,48 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_era_component_serialization() -> Result<(), serde_json::Error> {
+        let component = RuleComponent::EraYear { digits: 2 };
+        let json = serde_json::to_string(&component)?;
+        assert_eq!(json, r#"{"type":"EraYear","digits":2}"#);
+        let parsed: RuleComponent = serde_json::from_str(r#"{"type":"EraLetter"}"#)?;
+        assert_eq!(parsed, RuleComponent::EraLetter);
+        Ok(())
+    }
+
+    #[test]
+    fn test_literal_component_serialization() -> Result<(), serde_json::Error> {
+        let component = RuleComponent::Literal {
+            text: "JP".to_string(),
+        };
+        let json = serde_json::to_string(&component)?;
+        assert_eq!(json, r#"{"type":"Literal","text":"JP"}"#);
+        let parsed: RuleComponent = serde_json::from_str(r#"{"type":"FixedDeptCode","code":"G"}"#)?;
+        assert_eq!(parsed, RuleComponent::FixedDeptCode { code: 'G' });
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixed_codes() {
+        let rule = PathGenerationRule::example_agi();
+        assert!(rule.fixed_dept_code().is_none());
+        assert!(rule.fixed_section_code().is_none());
+
+        let rule = PathGenerationRule::new(
+            vec![
+                RuleComponent::TypeName,
+                RuleComponent::FixedDeptCode { code: 'G' },
+                RuleComponent::FixedSectionCode { code: 'I' },
+                RuleComponent::AutoIncrement,
+            ],
+            CounterScope::TypeDeptSectionYearMonth,
+            3,
+        );
+        assert_eq!(rule.fixed_dept_code(), Some(DeptCode::new('G')));
+        assert_eq!(rule.fixed_section_code(), Some(SectionCode::new('I')));
+    }
+
     #[test]
     fn test_path_generation_rule_example_agi() {
         let rule = PathGenerationRule::example_agi();
@@ -138,6 +582,99 @@ mod tests {
         assert_eq!(rule.separators[0], "-");
     }
 
+    #[test]
+    fn test_fiscal_year_start_month_defaults_to_january() -> Result<(), serde_json::Error> {
+        let rule = PathGenerationRule::example_agi();
+        assert_eq!(rule.fiscal_year_start_month, 1);
+
+        // Rules stored before fiscal year support have no start month
+        let json = r#"{"id":null,"components":[{"type":"AutoIncrement"}],"separators":[],"counter_scope":"TypeOnly","counter_digits":3}"#;
+        let parsed: PathGenerationRule = serde_json::from_str(json)?;
+        assert_eq!(parsed.fiscal_year_start_month, 1);
+
+        let april = rule.with_fiscal_year_start_month(4);
+        assert_eq!(april.fiscal_year_start_month, 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_counter_capacity() {
+        let mut rule = PathGenerationRule::example_agi();
+        assert_eq!(rule.counter_capacity(), 999);
+        rule.counter_digits = 5;
+        assert_eq!(rule.counter_capacity(), 99_999);
+        rule.counter_digits = 30;
+        assert_eq!(rule.counter_capacity(), i64::MAX);
+
+        rule.counter_digits = 3;
+        let capacities = [
+            (CounterEncoding::DecimalUnpadded, 999),
+            (CounterEncoding::Base36, 46_655),
+            (CounterEncoding::Alphabetic, 17_576),
+            (CounterEncoding::DecimalThenAlpha, 3_599),
+        ];
+        for (encoding, capacity) in capacities {
+            assert_eq!(
+                rule.clone()
+                    .with_counter_encoding(encoding)
+                    .counter_capacity(),
+                capacity
+            );
+        }
+    }
+
+    #[test]
+    fn test_counter_encoding_roundtrip() {
+        let cases = [
+            (CounterEncoding::Decimal, 7, "007"),
+            (CounterEncoding::Decimal, 999, "999"),
+            (CounterEncoding::DecimalUnpadded, 7, "7"),
+            (CounterEncoding::DecimalUnpadded, 120, "120"),
+            (CounterEncoding::Base36, 35, "00Z"),
+            (CounterEncoding::Base36, 36, "010"),
+            (CounterEncoding::Base36, 46_655, "ZZZ"),
+            (CounterEncoding::Alphabetic, 1, "AAA"),
+            (CounterEncoding::Alphabetic, 27, "ABA"),
+            (CounterEncoding::Alphabetic, 17_576, "ZZZ"),
+            (CounterEncoding::DecimalThenAlpha, 999, "999"),
+            (CounterEncoding::DecimalThenAlpha, 1000, "A00"),
+            (CounterEncoding::DecimalThenAlpha, 1101, "B01"),
+            (CounterEncoding::DecimalThenAlpha, 3_599, "Z99"),
+        ];
+        for (encoding, value, text) in cases {
+            assert_eq!(encoding.encode(value, 3), text, "{:?} {}", encoding, value);
+            assert_eq!(
+                encoding.decode(text, 3),
+                Some(value),
+                "{:?} {}",
+                encoding,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_counter_encoding_rejects_foreign_text() {
+        assert_eq!(CounterEncoding::Decimal.decode("0A1", 3), None);
+        assert_eq!(CounterEncoding::DecimalUnpadded.decode("007", 3), None);
+        assert_eq!(CounterEncoding::Base36.decode("00z", 3), None);
+        assert_eq!(CounterEncoding::Alphabetic.decode("AB", 3), None);
+        assert_eq!(CounterEncoding::DecimalThenAlpha.decode("AA0", 3), None);
+
+        // Single-character overflow: 1 … 9, then A … Z
+        assert_eq!(CounterEncoding::DecimalThenAlpha.capacity(1), 35);
+        assert_eq!(CounterEncoding::DecimalThenAlpha.encode(10, 1), "A");
+        assert_eq!(CounterEncoding::DecimalThenAlpha.decode("Z", 1), Some(35));
+    }
+
+    #[test]
+    fn test_counter_encoding_defaults_to_decimal() -> Result<(), serde_json::Error> {
+        let json = r#"{"id":null,"components":[{"type":"AutoIncrement"}],"separators":[],"counter_scope":"TypeOnly","counter_digits":3}"#;
+        let parsed: PathGenerationRule = serde_json::from_str(json)?;
+        assert_eq!(parsed.counter_encoding, CounterEncoding::Decimal);
+        Ok(())
+    }
+
     #[test]
     fn test_counter_scope_serialization() -> Result<(), serde_json::Error> {
         let scope = CounterScope::TypeAndYear;
@@ -145,4 +682,98 @@ mod tests {
         assert_eq!(json, "\"TypeAndYear\"");
         Ok(())
     }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap_or(NaiveDate::MIN)
+    }
+
+    #[test]
+    fn test_rule_change_schedule_effective_date() {
+        let schedule = RuleChangeSchedule::NextMonth;
+        assert_eq!(
+            schedule.effective_date(date(2025, 10, 15), 4),
+            Some(date(2025, 11, 1))
+        );
+        assert_eq!(
+            schedule.effective_date(date(2025, 12, 31), 4),
+            Some(date(2026, 1, 1))
+        );
+
+        let schedule = RuleChangeSchedule::NextFiscalYear;
+        assert_eq!(
+            schedule.effective_date(date(2025, 10, 15), 4),
+            Some(date(2026, 4, 1))
+        );
+        assert_eq!(
+            schedule.effective_date(date(2026, 3, 31), 4),
+            Some(date(2026, 4, 1))
+        );
+        assert_eq!(
+            schedule.effective_date(date(2025, 10, 15), 1),
+            Some(date(2026, 1, 1))
+        );
+
+        let schedule = RuleChangeSchedule::On(date(2025, 12, 1));
+        assert_eq!(
+            schedule.effective_date(date(2025, 10, 15), 4),
+            Some(date(2025, 12, 1))
+        );
+    }
+
+    #[test]
+    fn test_rule_version_effective_on() {
+        let version = |id, effective_from| RuleVersion {
+            id,
+            document_type: TypeCode::new("A"),
+            effective_from,
+            rule: PathGenerationRule::example_agi(),
+        };
+        let versions = vec![
+            version(1, None),
+            version(2, Some(date(2025, 11, 1))),
+            version(3, Some(date(2026, 4, 1))),
+        ];
+
+        let id_on = |d| RuleVersion::effective_on(&versions, d).map(|v| v.id);
+        assert_eq!(id_on(date(2025, 10, 31)), Some(1));
+        assert_eq!(id_on(date(2025, 11, 1)), Some(2));
+        assert_eq!(id_on(date(2026, 3, 31)), Some(2));
+        assert_eq!(id_on(date(2026, 4, 1)), Some(3));
+        assert_eq!(RuleVersion::effective_on(&[], date(2025, 1, 1)), None);
+    }
+
+    #[test]
+    fn test_rule_change_schedule_serialization() -> Result<(), serde_json::Error> {
+        let parsed: RuleChangeSchedule = serde_json::from_str(r#""next_fiscal_year""#)?;
+        assert_eq!(parsed, RuleChangeSchedule::NextFiscalYear);
+        let parsed: RuleChangeSchedule = serde_json::from_str(r#"{"on":"2025-12-01"}"#)?;
+        assert_eq!(parsed, RuleChangeSchedule::On(date(2025, 12, 1)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_counter_scope_serialization() -> anyhow::Result<()> {
+        let scope = CounterScope::Custom {
+            dimensions: vec![ScopeDimension::Type, ScopeDimension::Task],
+        };
+        let json = serde_json::to_string(&scope)?;
+        assert_eq!(json, r#"{"Custom":{"dimensions":["Type","Task"]}}"#);
+        assert_eq!(serde_json::from_str::<CounterScope>(&json)?, scope);
+        Ok(())
+    }
+
+    #[test]
+    fn test_counter_scope_validate() {
+        assert!(CounterScope::TypeDeptSectionYearMonth.validate().is_ok());
+        let empty = CounterScope::Custom { dimensions: vec![] };
+        assert!(empty.validate().is_err());
+        let repeated = CounterScope::Custom {
+            dimensions: vec![
+                ScopeDimension::Type,
+                ScopeDimension::Year,
+                ScopeDimension::Type,
+            ],
+        };
+        assert!(repeated.validate().is_err());
+    }
 }
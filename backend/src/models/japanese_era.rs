@@ -0,0 +1,122 @@
+//! Japanese era (和暦) calendar
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Japanese era (元号)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum JapaneseEra {
+    /// 明治 (1868-10-23 〜)
+    Meiji,
+    /// 大正 (1912-07-30 〜)
+    Taisho,
+    /// 昭和 (1926-12-25 〜)
+    Showa,
+    /// 平成 (1989-01-08 〜)
+    Heisei,
+    /// 令和 (2019-05-01 〜)
+    Reiwa,
+}
+
+impl JapaneseEra {
+    /// All eras, newest first
+    pub const ALL: [JapaneseEra; 5] = [
+        JapaneseEra::Reiwa,
+        JapaneseEra::Heisei,
+        JapaneseEra::Showa,
+        JapaneseEra::Taisho,
+        JapaneseEra::Meiji,
+    ];
+
+    /// First day of the era
+    pub fn start_date(&self) -> NaiveDate {
+        let (y, m, d) = match self {
+            JapaneseEra::Meiji => (1868, 10, 23),
+            JapaneseEra::Taisho => (1912, 7, 30),
+            JapaneseEra::Showa => (1926, 12, 25),
+            JapaneseEra::Heisei => (1989, 1, 8),
+            JapaneseEra::Reiwa => (2019, 5, 1),
+        };
+        NaiveDate::from_ymd_opt(y, m, d).unwrap_or(NaiveDate::MIN)
+    }
+
+    /// Single-letter abbreviation (e.g., "R" for 令和)
+    pub fn letter(&self) -> char {
+        match self {
+            JapaneseEra::Meiji => 'M',
+            JapaneseEra::Taisho => 'T',
+            JapaneseEra::Showa => 'S',
+            JapaneseEra::Heisei => 'H',
+            JapaneseEra::Reiwa => 'R',
+        }
+    }
+
+    /// Era name in kanji (e.g., "令和")
+    pub fn kanji(&self) -> &'static str {
+        match self {
+            JapaneseEra::Meiji => "明治",
+            JapaneseEra::Taisho => "大正",
+            JapaneseEra::Showa => "昭和",
+            JapaneseEra::Heisei => "平成",
+            JapaneseEra::Reiwa => "令和",
+        }
+    }
+
+    /// Resolve the era and era year (1-based, 元年 = 1) for a date.
+    /// Returns None for dates before the Meiji era.
+    pub fn from_date(date: NaiveDate) -> Option<(JapaneseEra, u32)> {
+        let era = Self::ALL.into_iter().find(|era| date >= era.start_date())?;
+        let era_year = date.year() - era.start_date().year() + 1;
+        Some((era, era_year as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap_or(NaiveDate::MIN)
+    }
+
+    #[test]
+    fn test_from_date_reiwa() {
+        assert_eq!(
+            JapaneseEra::from_date(date(2025, 10, 1)),
+            Some((JapaneseEra::Reiwa, 7))
+        );
+    }
+
+    #[test]
+    fn test_from_date_era_boundary() {
+        assert_eq!(
+            JapaneseEra::from_date(date(2019, 4, 30)),
+            Some((JapaneseEra::Heisei, 31))
+        );
+        assert_eq!(
+            JapaneseEra::from_date(date(2019, 5, 1)),
+            Some((JapaneseEra::Reiwa, 1))
+        );
+        assert_eq!(
+            JapaneseEra::from_date(date(1989, 1, 7)),
+            Some((JapaneseEra::Showa, 64))
+        );
+        assert_eq!(
+            JapaneseEra::from_date(date(1989, 1, 8)),
+            Some((JapaneseEra::Heisei, 1))
+        );
+    }
+
+    #[test]
+    fn test_from_date_before_meiji() {
+        assert_eq!(JapaneseEra::from_date(date(1868, 10, 22)), None);
+    }
+
+    #[test]
+    fn test_letter_and_kanji() {
+        assert_eq!(JapaneseEra::Reiwa.letter(), 'R');
+        assert_eq!(JapaneseEra::Reiwa.kanji(), "令和");
+        assert_eq!(JapaneseEra::Heisei.letter(), 'H');
+        assert_eq!(JapaneseEra::Heisei.kanji(), "平成");
+    }
+}
@@ -1,21 +1,39 @@
 //! Data models
 
 pub mod business_task;
+pub mod check_digit;
+pub mod counter;
 pub mod department;
 pub mod document_path;
 pub mod document_type;
+pub mod file_check;
 pub mod generation_rule;
+pub mod japanese_era;
 pub mod newtypes;
+pub mod path_template;
 pub mod permissions;
+pub mod reservation;
+pub mod rule_pattern;
 pub mod section;
+pub mod stored_path;
 pub mod user;
+pub mod watch_pattern;
 
 pub use business_task::*;
+pub use check_digit::*;
+pub use counter::*;
 pub use department::*;
 pub use document_path::*;
 pub use document_type::*;
+pub use file_check::*;
 pub use generation_rule::*;
+pub use japanese_era::*;
 pub use newtypes::*;
+pub use path_template::*;
 pub use permissions::*;
+pub use reservation::*;
+pub use rule_pattern::*;
 pub use section::*;
+pub use stored_path::*;
 pub use user::*;
+pub use watch_pattern::*;
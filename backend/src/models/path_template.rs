@@ -0,0 +1,325 @@
+//! File path templates for auto-generated documents
+//!
+//! A template is literal text interleaved with braced placeholders, e.g.
+//! `{root}/{yyyy}/{dept}{section}/{number}.{ext}`. It is rendered after the
+//! number is issued, so every document of a type gets its own file path.
+//! Use `{{` and `}}` for literal braces.
+//!
+//! | Placeholder | Value                                         |
+//! |-------------|-----------------------------------------------|
+//! | `root`      | Root directory of the type (no trailing separator) |
+//! | `number`    | Document number                               |
+//! | `ext`       | File extension supplied by the caller         |
+//! | `type`      | Document type code                            |
+//! | `dept`      | Department code                               |
+//! | `section`   | Section code                                  |
+//! | `yyyy`/`yy` | Year of the business date                     |
+//! | `mm`/`dd`   | Month / day of the business date              |
+//! | `user`      | Creating user                                 |
+//! | `task`      | Business task (required if used)              |
+//!
+//! Every template must contain `{number}`.
+
+use crate::error::{Error, Result};
+use crate::models::{DeptCode, SectionCode, StoredPath, TaskId, TypeCode, UserId};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Template placeholder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathField {
+    Root,
+    Number,
+    Extension,
+    Type,
+    Department,
+    Section,
+    Year4,
+    Year2,
+    Month,
+    Day,
+    User,
+    Task,
+}
+
+impl PathField {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "root" => Some(PathField::Root),
+            "number" => Some(PathField::Number),
+            "ext" => Some(PathField::Extension),
+            "type" => Some(PathField::Type),
+            "dept" => Some(PathField::Department),
+            "section" => Some(PathField::Section),
+            "yyyy" => Some(PathField::Year4),
+            "yy" => Some(PathField::Year2),
+            "mm" => Some(PathField::Month),
+            "dd" => Some(PathField::Day),
+            "user" => Some(PathField::User),
+            "task" => Some(PathField::Task),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(PathField),
+}
+
+/// Values a template is rendered with
+#[derive(Debug, Clone)]
+pub struct PathValues<'a> {
+    pub root: &'a str,
+    pub number: &'a str,
+    /// File extension, with or without the leading dot
+    pub extension: Option<&'a str>,
+    pub document_type: &'a TypeCode,
+    pub department: &'a DeptCode,
+    pub section: &'a SectionCode,
+    /// Business date the number was issued for
+    pub date: NaiveDate,
+    pub user: &'a UserId,
+    pub task: Option<&'a TaskId>,
+}
+
+/// Parsed file path template of a document type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PathTemplate {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl PathTemplate {
+    /// Template text as written
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the template needs a file extension from the caller
+    pub fn uses_extension(&self) -> bool {
+        self.segments
+            .contains(&Segment::Field(PathField::Extension))
+    }
+
+    /// Render the template into an absolute file path
+    pub fn render(&self, values: &PathValues<'_>) -> Result<StoredPath> {
+        let mut path = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => path.push_str(text),
+                Segment::Field(field) => path.push_str(&field_value(*field, values)?),
+            }
+        }
+
+        let path = StoredPath::new(path);
+        if !path.is_absolute() {
+            return Err(Error::Validation(format!(
+                "Path template '{}' rendered a relative path: {}",
+                self.source, path
+            )));
+        }
+        path.validate()?;
+        Ok(path)
+    }
+}
+
+fn field_value(field: PathField, values: &PathValues<'_>) -> Result<String> {
+    let date = values.date;
+    Ok(match field {
+        PathField::Root => match values.root.trim_end_matches(['/', '\\']) {
+            "" => String::new(),
+            root => root.to_string(),
+        },
+        PathField::Number => values.number.to_string(),
+        PathField::Extension => normalize_extension(values.extension)?,
+        PathField::Type => values.document_type.0.clone(),
+        PathField::Department => values.department.0.to_string(),
+        PathField::Section => values.section.0.to_string(),
+        PathField::Year4 => format!("{:04}", date.year()),
+        PathField::Year2 => format!("{:02}", date.year().rem_euclid(100)),
+        PathField::Month => format!("{:02}", date.month()),
+        PathField::Day => format!("{:02}", date.day()),
+        PathField::User => values.user.0.clone(),
+        PathField::Task => values.task.map(|task| task.0.clone()).ok_or_else(|| {
+            Error::Validation("Path template uses {task} but no business task was given".into())
+        })?,
+    })
+}
+
+/// Extension without its leading dot; must be a single plain file name suffix
+fn normalize_extension(extension: Option<&str>) -> Result<String> {
+    let extension = extension
+        .map(|ext| ext.trim().trim_start_matches('.'))
+        .filter(|ext| !ext.is_empty())
+        .ok_or_else(|| Error::Validation("A file extension is required".into()))?;
+    if extension.contains(['/', '\\']) || extension.contains("..") {
+        return Err(Error::Validation(format!(
+            "Invalid file extension: {}",
+            extension
+        )));
+    }
+    Ok(extension.to_string())
+}
+
+impl FromStr for PathTemplate {
+    type Err = Error;
+
+    /// Parse a template. Error positions are 0-based character offsets.
+    fn from_str(source: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().enumerate().peekable();
+
+        while let Some((position, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().map(|(_, next)| *next) == Some('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek().map(|(_, next)| *next) == Some('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '}')) => break,
+                            Some((_, c)) => name.push(c),
+                            None => {
+                                return Err(Error::Validation(format!(
+                                    "Unclosed '{{' at position {} in path template",
+                                    position
+                                )));
+                            }
+                        }
+                    }
+                    let field = PathField::from_name(&name).ok_or_else(|| {
+                        Error::Validation(format!(
+                            "Unknown placeholder '{{{}}}' at position {} in path template",
+                            name, position
+                        ))
+                    })?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(field));
+                }
+                '}' => {
+                    return Err(Error::Validation(format!(
+                        "Unmatched '}}' at position {} in path template",
+                        position
+                    )));
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        if !segments.contains(&Segment::Field(PathField::Number)) {
+            return Err(Error::Validation(
+                "Path template must contain {number}".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            segments,
+        })
+    }
+}
+
+impl TryFrom<String> for PathTemplate {
+    type Error = Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        source.parse()
+    }
+}
+
+impl From<PathTemplate> for String {
+    fn from(template: PathTemplate) -> Self {
+        template.source
+    }
+}
+
+impl fmt::Display for PathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, root: &str, extension: Option<&str>) -> Result<StoredPath> {
+        let template: PathTemplate = template.parse()?;
+        template.render(&PathValues {
+            root,
+            number: "AGI2510001",
+            extension,
+            document_type: &TypeCode::new("A"),
+            department: &DeptCode::new('G'),
+            section: &SectionCode::new('I'),
+            date: NaiveDate::from_ymd_opt(2025, 10, 5).unwrap_or(NaiveDate::MIN),
+            user: &UserId::new("user001"),
+            task: None,
+        })
+    }
+
+    #[test]
+    fn test_render_example_template() -> Result<()> {
+        let path = render(
+            "{root}/{yyyy}/{dept}{section}/{number}.{ext}",
+            "/docs/contracts/",
+            Some(".pdf"),
+        )?;
+        assert_eq!(
+            path,
+            StoredPath::new("/docs/contracts/2025/GI/AGI2510001.pdf")
+        );
+
+        let path = render("{root}/{yy}{mm}{dd}-{type}/{number}", "/", None)?;
+        assert_eq!(path, StoredPath::new("/251005-A/AGI2510001"));
+
+        let path = render("/{{archive}}/{number}.{ext}", "/docs", Some("docx"))?;
+        assert_eq!(path, StoredPath::new("/{archive}/AGI2510001.docx"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_templates() {
+        assert!("{root}/{year}/{number}".parse::<PathTemplate>().is_err());
+        assert!("{root}/{number".parse::<PathTemplate>().is_err());
+        assert!("{root}/number}".parse::<PathTemplate>().is_err());
+        assert!("{root}/{yyyy}/file.pdf".parse::<PathTemplate>().is_err());
+    }
+
+    #[test]
+    fn test_render_requires_extension_and_absolute_path() {
+        assert!(render("{root}/{number}.{ext}", "/docs", None).is_err());
+        assert!(render("{root}/{number}.{ext}", "/docs", Some("../x")).is_err());
+        assert!(render("{root}/{number}", "docs", None).is_err());
+        assert!(render("{root}/{task}/{number}", "/docs", None).is_err());
+    }
+
+    #[test]
+    fn test_path_template_serde_roundtrip() -> anyhow::Result<()> {
+        let template: PathTemplate = serde_json::from_str(r#""{root}/{number}.{ext}""#)?;
+        assert!(template.uses_extension());
+        assert_eq!(
+            serde_json::to_string(&template)?,
+            r#""{root}/{number}.{ext}""#
+        );
+        assert!(serde_json::from_str::<PathTemplate>(r#""{root}/{nope}""#).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+//! Number Reservation entity
+
+use crate::error::{Error, Result};
+use crate::models::{DeptCode, DocumentId, ReservationId, SectionCode, TaskId, TypeCode, UserId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Reservation lifecycle state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReservationStatus {
+    /// Number is held and waiting for a file
+    Reserved,
+    /// Number was turned into a document
+    Confirmed,
+    /// Number was given up by the user (never used)
+    Released,
+    /// Reservation ran out before confirmation (never used)
+    Expired,
+}
+
+impl ReservationStatus {
+    /// Value stored in the `reservations.status` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReservationStatus::Reserved => "reserved",
+            ReservationStatus::Confirmed => "confirmed",
+            ReservationStatus::Released => "released",
+            ReservationStatus::Expired => "expired",
+        }
+    }
+
+    /// Whether the number was reserved but never used
+    pub fn is_unused(&self) -> bool {
+        matches!(
+            self,
+            ReservationStatus::Released | ReservationStatus::Expired
+        )
+    }
+}
+
+impl fmt::Display for ReservationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReservationStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "reserved" => Ok(ReservationStatus::Reserved),
+            "confirmed" => Ok(ReservationStatus::Confirmed),
+            "released" => Ok(ReservationStatus::Released),
+            "expired" => Ok(ReservationStatus::Expired),
+            other => Err(Error::Validation(format!(
+                "Unknown reservation status: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// Number Reservation (番号予約)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reservation {
+    /// 予約識別子
+    pub id: ReservationId,
+    /// 予約された文書番号
+    pub document_number: String,
+    /// 文書種類
+    pub document_type: TypeCode,
+    /// 所属部門
+    pub department: DeptCode,
+    /// 所属課
+    pub section: SectionCode,
+    /// 予約ユーザー
+    pub user: UserId,
+    /// 番号を払い出したカウンタースコープ
+    pub scope_key: String,
+    /// 払い出したカウンター値
+    pub counter_value: i64,
+    /// 状態
+    pub status: ReservationStatus,
+    /// 確定時に作成された文書
+    pub document_id: Option<DocumentId>,
+    /// 予約日時
+    pub reserved_at: DateTime<Utc>,
+    /// 有効期限
+    pub expires_at: DateTime<Utc>,
+    /// 確定・解放・失効日時
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// 番号を生成したルールバージョン
+    #[serde(default)]
+    pub generation_rule_id: Option<i64>,
+    /// 業務タスク (タスク別カウンターの番号)
+    #[serde(default)]
+    pub business_task: Option<TaskId>,
+}
+
+impl Reservation {
+    /// Whether the reservation can still be confirmed at `now`
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.status == ReservationStatus::Reserved && now < self.expires_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reservation_status_roundtrip() -> Result<()> {
+        for status in [
+            ReservationStatus::Reserved,
+            ReservationStatus::Confirmed,
+            ReservationStatus::Released,
+            ReservationStatus::Expired,
+        ] {
+            assert_eq!(status.as_str().parse::<ReservationStatus>()?, status);
+        }
+        assert!("pending".parse::<ReservationStatus>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reservation_status_is_unused() {
+        assert!(!ReservationStatus::Reserved.is_unused());
+        assert!(!ReservationStatus::Confirmed.is_unused());
+        assert!(ReservationStatus::Released.is_unused());
+        assert!(ReservationStatus::Expired.is_unused());
+    }
+}
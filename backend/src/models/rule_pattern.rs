@@ -0,0 +1,491 @@
+//! Human-readable rule pattern DSL
+//!
+//! A pattern is literal text interleaved with bracketed placeholders,
+//! e.g. `[TYPE][DEPT][SECT][YYMM][NNN]` or `JP-[TYPE]-[DEPT=G]-[YYMM]-[NNN]`.
+//! One bracket may hold several placeholders back to back (`[YYMM]`).
+//! Text between placeholders becomes separators; text before the first or
+//! after the last placeholder becomes `Literal` components.
+//! Use `[[` and `]]` for literal brackets.
+//!
+//! | Placeholder     | Component                           |
+//! |-----------------|-------------------------------------|
+//! | `TYPE`          | `TypeName`                          |
+//! | `DEPT`          | `DeptCode`                          |
+//! | `SECT`          | `SectionCode`                       |
+//! | `DEPT=G`        | `FixedDeptCode { code: 'G' }`       |
+//! | `SECT=I`        | `FixedSectionCode { code: 'I' }`    |
+//! | `YYYY` / `YY`   | `Year { digits: 4 / 2 }`            |
+//! | `ERA`           | `EraLetter`                         |
+//! | `ERANAME`       | `EraName`                           |
+//! | `EY`, `EYY`...  | `EraYear { digits: <count of Y> }`  |
+//! | `FYYYY` / `FYY` | `FiscalYear { digits: 4 / 2 }`      |
+//! | `FQ`            | `FiscalQuarter`                     |
+//! | `MM`            | `Month`                             |
+//! | `DD`            | `Day`                               |
+//! | `N`, `NN`...    | `AutoIncrement` (width = count of N)|
+//! | `CHK10`         | `CheckDigit` (Luhn mod 10)          |
+//! | `CHK11`         | `CheckDigit` (mod 11)               |
+//! | `CHK37`         | `CheckDigit` (ISO 7064 MOD 37,36)   |
+//!
+//! A check digit placeholder must be the last element of the pattern.
+
+use crate::error::{Error, Result};
+use crate::models::{CheckDigitAlgorithm, CounterScope, PathGenerationRule, RuleComponent};
+
+/// Fixed-text placeholders, longest first so that greedy matching is unambiguous
+const FIXED_PLACEHOLDERS: &[(&str, RuleComponent)] = &[
+    ("ERANAME", RuleComponent::EraName),
+    ("FYYYY", RuleComponent::FiscalYear { digits: 4 }),
+    (
+        "CHK10",
+        RuleComponent::CheckDigit {
+            algorithm: CheckDigitAlgorithm::Luhn,
+        },
+    ),
+    (
+        "CHK11",
+        RuleComponent::CheckDigit {
+            algorithm: CheckDigitAlgorithm::Mod11,
+        },
+    ),
+    (
+        "CHK37",
+        RuleComponent::CheckDigit {
+            algorithm: CheckDigitAlgorithm::Mod37_36,
+        },
+    ),
+    ("YYYY", RuleComponent::Year { digits: 4 }),
+    ("TYPE", RuleComponent::TypeName),
+    ("DEPT", RuleComponent::DeptCode),
+    ("SECT", RuleComponent::SectionCode),
+    ("ERA", RuleComponent::EraLetter),
+    ("FYY", RuleComponent::FiscalYear { digits: 2 }),
+    ("FQ", RuleComponent::FiscalQuarter),
+    ("YY", RuleComponent::Year { digits: 2 }),
+    ("MM", RuleComponent::Month),
+    ("DD", RuleComponent::Day),
+];
+
+/// Parse a pattern string into a generation rule.
+/// Error positions are 0-based character offsets into `pattern`.
+pub fn parse_rule_pattern(
+    pattern: &str,
+    counter_scope: CounterScope,
+) -> Result<PathGenerationRule> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut parser = PatternParser {
+        pattern,
+        components: Vec::new(),
+        gaps: Vec::new(),
+        literal: String::new(),
+        literal_start: 0,
+        counter_digits: None,
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '[' if chars.get(i + 1) == Some(&'[') => {
+                parser.push_literal('[', i);
+                i += 2;
+            }
+            ']' if chars.get(i + 1) == Some(&']') => {
+                parser.push_literal(']', i);
+                i += 2;
+            }
+            '[' => {
+                let close = chars[i + 1..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .map(|offset| i + 1 + offset)
+                    .ok_or_else(|| parser.error("Unclosed '['", i))?;
+                if close == i + 1 {
+                    return Err(parser.error("Empty placeholder", i));
+                }
+                parser.parse_placeholders(&chars[i + 1..close], i + 1)?;
+                i = close + 1;
+            }
+            ']' => return Err(parser.error("Unmatched ']'", i)),
+            c => {
+                parser.push_literal(c, i);
+                i += 1;
+            }
+        }
+    }
+
+    parser.finish(counter_scope, chars.len())
+}
+
+/// Format a generation rule as a pattern string
+pub fn format_rule_pattern(rule: &PathGenerationRule) -> String {
+    let mut pattern = String::new();
+    for (i, component) in rule.components.iter().enumerate() {
+        if i > 0 {
+            pattern.push_str(&escape_literal(rule.separator_after(i - 1)));
+        }
+        if let RuleComponent::Literal { text } = component {
+            pattern.push_str(&escape_literal(text));
+        } else {
+            pattern.push('[');
+            pattern.push_str(&placeholder(component, rule.counter_digits));
+            pattern.push(']');
+        }
+    }
+    pattern
+}
+
+fn escape_literal(text: &str) -> String {
+    text.replace('[', "[[").replace(']', "]]")
+}
+
+/// Placeholder text for a single component
+fn placeholder(component: &RuleComponent, counter_digits: u8) -> String {
+    match component {
+        RuleComponent::TypeName => "TYPE".to_string(),
+        RuleComponent::DeptCode => "DEPT".to_string(),
+        RuleComponent::SectionCode => "SECT".to_string(),
+        RuleComponent::FixedDeptCode { code } => format!("DEPT={}", code),
+        RuleComponent::FixedSectionCode { code } => format!("SECT={}", code),
+        RuleComponent::Literal { text } => text.clone(),
+        RuleComponent::Year { digits: 2 } => "YY".to_string(),
+        RuleComponent::Year { .. } => "YYYY".to_string(),
+        RuleComponent::EraLetter => "ERA".to_string(),
+        RuleComponent::EraName => "ERANAME".to_string(),
+        RuleComponent::EraYear { digits } => format!("E{}", "Y".repeat((*digits).max(1) as usize)),
+        RuleComponent::FiscalYear { digits: 2 } => "FYY".to_string(),
+        RuleComponent::FiscalYear { .. } => "FYYYY".to_string(),
+        RuleComponent::FiscalQuarter => "FQ".to_string(),
+        RuleComponent::Month => "MM".to_string(),
+        RuleComponent::Day => "DD".to_string(),
+        RuleComponent::AutoIncrement => "N".repeat(counter_digits.max(1) as usize),
+        RuleComponent::CheckDigit { algorithm } => match algorithm {
+            CheckDigitAlgorithm::Luhn => "CHK10".to_string(),
+            CheckDigitAlgorithm::Mod11 => "CHK11".to_string(),
+            CheckDigitAlgorithm::Mod37_36 => "CHK37".to_string(),
+        },
+    }
+}
+
+struct PatternParser<'a> {
+    pattern: &'a str,
+    components: Vec<RuleComponent>,
+    /// Literal text between consecutive components
+    gaps: Vec<String>,
+    /// Literal text collected since the last component
+    literal: String,
+    literal_start: usize,
+    counter_digits: Option<u8>,
+}
+
+impl PatternParser<'_> {
+    fn error(&self, message: &str, position: usize) -> Error {
+        Error::InvalidRuleComponent(format!(
+            "{} at position {} in pattern '{}'",
+            message, position, self.pattern
+        ))
+    }
+
+    fn push_literal(&mut self, c: char, position: usize) {
+        if self.literal.is_empty() {
+            self.literal_start = position;
+        }
+        self.literal.push(c);
+    }
+
+    fn push_component(&mut self, component: RuleComponent) {
+        if self.components.is_empty() {
+            // Leading text is a prefix, not a separator
+            if !self.literal.is_empty() {
+                let text = std::mem::take(&mut self.literal);
+                self.components.push(RuleComponent::Literal { text });
+                self.gaps.push(String::new());
+            }
+        } else {
+            self.gaps.push(std::mem::take(&mut self.literal));
+        }
+        self.components.push(component);
+    }
+
+    /// Parse the contents of one bracket, starting at `offset` in the pattern
+    fn parse_placeholders(&mut self, body: &[char], offset: usize) -> Result<()> {
+        let mut j = 0;
+        while j < body.len() {
+            let position = offset + j;
+            let rest: String = body[j..].iter().collect();
+
+            if let Some(fixed) = ["DEPT=", "SECT="]
+                .into_iter()
+                .find(|prefix| rest.starts_with(prefix))
+            {
+                let code = body
+                    .get(j + fixed.len())
+                    .copied()
+                    .filter(|c| c.is_ascii_alphanumeric())
+                    .ok_or_else(|| {
+                        self.error("Expected a code after '='", position + fixed.len())
+                    })?;
+                self.push_component(if fixed == "DEPT=" {
+                    RuleComponent::FixedDeptCode { code }
+                } else {
+                    RuleComponent::FixedSectionCode { code }
+                });
+                j += fixed.len() + 1;
+                continue;
+            }
+
+            if let Some((text, component)) = FIXED_PLACEHOLDERS
+                .iter()
+                .find(|(text, _)| rest.starts_with(text))
+            {
+                self.push_component(component.clone());
+                j += text.chars().count();
+                continue;
+            }
+
+            if body[j] == 'N' {
+                let width = body[j..].iter().take_while(|c| **c == 'N').count();
+                if self.counter_digits.is_some() {
+                    return Err(self.error("Duplicate counter placeholder", position));
+                }
+                let digits = u8::try_from(width)
+                    .map_err(|_| self.error("Counter placeholder is too wide", position))?;
+                self.counter_digits = Some(digits);
+                self.push_component(RuleComponent::AutoIncrement);
+                j += width;
+                continue;
+            }
+
+            if body[j] == 'E' {
+                let width = body[j + 1..].iter().take_while(|c| **c == 'Y').count();
+                if width > 0 {
+                    let digits = u8::try_from(width)
+                        .map_err(|_| self.error("Era year placeholder is too wide", position))?;
+                    self.push_component(RuleComponent::EraYear { digits });
+                    j += 1 + width;
+                    continue;
+                }
+            }
+
+            return Err(self.error(&format!("Unknown placeholder '{}'", rest), position));
+        }
+        Ok(())
+    }
+
+    fn finish(mut self, counter_scope: CounterScope, end: usize) -> Result<PathGenerationRule> {
+        counter_scope.validate()?;
+
+        // Trailing text is a suffix, not a separator
+        if !self.literal.is_empty() && !self.components.is_empty() {
+            let text = std::mem::take(&mut self.literal);
+            self.gaps.push(String::new());
+            self.components.push(RuleComponent::Literal { text });
+        }
+        let counter_digits = self
+            .counter_digits
+            .ok_or_else(|| self.error("Missing counter placeholder [N...]", end))?;
+        let last = self.components.len() - 1;
+        if self.components[..last]
+            .iter()
+            .any(|c| matches!(c, RuleComponent::CheckDigit { .. }))
+        {
+            return Err(self.error("Check digit must end the pattern", end));
+        }
+
+        let gaps = std::mem::take(&mut self.gaps);
+        let separators = if gaps.iter().all(|gap| gap.is_empty()) {
+            Vec::new()
+        } else if gaps.iter().all(|gap| gap == &gaps[0]) {
+            vec![gaps[0].clone()]
+        } else {
+            gaps
+        };
+
+        Ok(
+            PathGenerationRule::new(self.components, counter_scope, counter_digits)
+                .with_separators(separators),
+        )
+    }
+}
+
+impl PathGenerationRule {
+    /// Build a rule from a pattern string (e.g., `[TYPE][DEPT][SECT][YYMM][NNN]`)
+    pub fn from_pattern(pattern: &str, counter_scope: CounterScope) -> Result<Self> {
+        parse_rule_pattern(pattern, counter_scope)
+    }
+
+    /// Pattern string for this rule
+    pub fn to_pattern(&self) -> String {
+        format_rule_pattern(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_agi_pattern() -> Result<()> {
+        let rule = parse_rule_pattern(
+            "[TYPE][DEPT][SECT][YYMM][NNN]",
+            CounterScope::TypeDeptSectionYearMonth,
+        )?;
+        assert_eq!(rule, PathGenerationRule::example_agi());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_pattern_with_separator() -> Result<()> {
+        let rule = parse_rule_pattern("[TYPE][SECT]-[YY][NNNNN]", CounterScope::TypeSectionYear)?;
+        assert_eq!(rule.components.len(), 4);
+        assert_eq!(rule.separators, vec!["", "-", ""]);
+        assert_eq!(rule.counter_digits, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_pattern_uniform_separator() -> Result<()> {
+        let rule = parse_rule_pattern("[TYPE]-[SECT]-[YY]-[NNN]", CounterScope::TypeSectionYear)?;
+        assert_eq!(rule, PathGenerationRule::example_ringi());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_era_and_fiscal_placeholders() -> Result<()> {
+        let rule = parse_rule_pattern("[TYPE][ERA][EYY]/[FYYYY]Q[FQ][NN]", CounterScope::TypeOnly)?;
+        assert_eq!(
+            rule.components,
+            vec![
+                RuleComponent::TypeName,
+                RuleComponent::EraLetter,
+                RuleComponent::EraYear { digits: 2 },
+                RuleComponent::FiscalYear { digits: 4 },
+                RuleComponent::FiscalQuarter,
+                RuleComponent::AutoIncrement,
+            ]
+        );
+        assert_eq!(rule.separators, vec!["", "", "/", "Q", ""]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_roundtrip() -> Result<()> {
+        for pattern in [
+            "[TYPE][DEPT][SECT][YY][MM][NNN]",
+            "[TYPE][SECT]-[YY][NNN]",
+            "[ERANAME][EY]年[TYPE]第[NNNN]",
+            "[TYPE][[[YYYY]]][NNN]",
+        ] {
+            let rule = parse_rule_pattern(pattern, CounterScope::TypeOnly)?;
+            assert_eq!(format_rule_pattern(&rule), pattern);
+            assert_eq!(
+                parse_rule_pattern(&rule.to_pattern(), CounterScope::TypeOnly)?,
+                rule
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_example_rules() {
+        assert_eq!(
+            PathGenerationRule::example_agi().to_pattern(),
+            "[TYPE][DEPT][SECT][YY][MM][NNN]"
+        );
+        assert_eq!(
+            PathGenerationRule::example_ringi().to_pattern(),
+            "[TYPE]-[SECT]-[YY]-[NNN]"
+        );
+    }
+
+    fn error_message(pattern: &str) -> String {
+        match parse_rule_pattern(pattern, CounterScope::TypeOnly) {
+            Err(Error::InvalidRuleComponent(message)) => message,
+            other => format!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_report_position() {
+        assert!(
+            error_message("[TYPE][YYQ][NNN]").starts_with("Unknown placeholder 'Q' at position 9")
+        );
+        assert!(error_message("[TYPE][YY").starts_with("Unclosed '[' at position 6"));
+        assert!(error_message("[TYPE]][NNN]").starts_with("Unmatched ']' at position 6"));
+        assert!(error_message("[TYPE][][NNN]").starts_with("Empty placeholder at position 6"));
+        assert!(
+            error_message("[TYPE][YY]")
+                .starts_with("Missing counter placeholder [N...] at position 10")
+        );
+        assert!(
+            error_message("[NNN][TYPE][NN]")
+                .starts_with("Duplicate counter placeholder at position 12")
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_multibyte_positions() {
+        // Positions count characters, not bytes
+        assert!(
+            error_message("[TYPE]りん議[X][NNN]")
+                .starts_with("Unknown placeholder 'X' at position 10")
+        );
+        assert!(
+            error_message("りん議[TYPE][DEPT=][NNN]")
+                .starts_with("Expected a code after '=' at position 15")
+        );
+    }
+
+    #[test]
+    fn test_parse_literal_prefix_and_fixed_codes() -> Result<()> {
+        let rule = parse_rule_pattern(
+            "JP-[TYPE]-[DEPT=G]-[YYMM]-[NNN]",
+            CounterScope::TypeDeptSectionYearMonth,
+        )?;
+        assert_eq!(
+            rule.components,
+            vec![
+                RuleComponent::Literal {
+                    text: "JP-".to_string()
+                },
+                RuleComponent::TypeName,
+                RuleComponent::FixedDeptCode { code: 'G' },
+                RuleComponent::Year { digits: 2 },
+                RuleComponent::Month,
+                RuleComponent::AutoIncrement,
+            ]
+        );
+        assert_eq!(rule.separators, vec!["", "-", "-", "", "-"]);
+        assert_eq!(rule.to_pattern(), "JP-[TYPE]-[DEPT=G]-[YY][MM]-[NNN]");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_literal_suffix() -> Result<()> {
+        let rule = parse_rule_pattern("[TYPE][SECT=I][NNN]号", CounterScope::TypeOnly)?;
+        assert_eq!(
+            rule.components.last(),
+            Some(&RuleComponent::Literal {
+                text: "号".to_string()
+            })
+        );
+        assert_eq!(rule.to_pattern(), "[TYPE][SECT=I][NNN]号");
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_check_digit() -> Result<()> {
+        let rule = parse_rule_pattern("[TYPE][YYMM][NNN]-[CHK37]", CounterScope::TypeOnly)?;
+        assert_eq!(rule.check_digit(), Some(CheckDigitAlgorithm::Mod37_36));
+        assert_eq!(rule.to_pattern(), "[TYPE][YY][MM][NNN]-[CHK37]");
+        assert_eq!(
+            parse_rule_pattern("[TYPE][NNN][CHK10]", CounterScope::TypeOnly)?.check_digit(),
+            Some(CheckDigitAlgorithm::Luhn)
+        );
+
+        assert!(
+            error_message("[TYPE][NNN][CHK11]号")
+                .starts_with("Check digit must end the pattern at position 19")
+        );
+        Ok(())
+    }
+}
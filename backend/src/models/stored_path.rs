@@ -0,0 +1,368 @@
+//! Stored file paths
+//!
+//! Document files live on POSIX and Windows file servers alike, so paths are
+//! kept as text and interpreted the same way whatever OS the server runs on.
+//! Three absolute forms are recognised:
+//!
+//! | Flavour | Example                   | Normal form                      |
+//! |---------|---------------------------|----------------------------------|
+//! | POSIX   | `/docs/contracts/a.pdf`   | unchanged                        |
+//! | Drive   | `c:/Users/docs`           | `C:\Users\docs`                  |
+//! | UNC     | `//server/share/docs`     | `\\server\share\docs`            |
+//!
+//! Windows paths use `\` as separator and compare case-insensitively;
+//! POSIX paths compare case-sensitively. A path starting with two separators
+//! is always read as UNC.
+//!
+//! Absolute paths are also canonicalised lexically: Unicode NFC (macOS and
+//! some scanners write decomposed kana), `.` and `..` segments resolved,
+//! duplicate and trailing separators removed. `..` never climbs above the
+//! root (`/`, `C:\` or `\\server\share`). The file system is not consulted.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters Windows does not allow in file and directory names
+const WINDOWS_RESERVED: &[char] = &['<', '>', ':', '"', '|', '?', '*'];
+
+/// Kind of path, decided by its prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathFlavor {
+    /// `/docs/...`
+    Posix,
+    /// `C:\docs\...`
+    Drive,
+    /// `\\server\share\...`
+    Unc,
+    /// Anything else (including `C:docs` and `\docs`)
+    Relative,
+}
+
+impl PathFlavor {
+    fn detect(path: &str) -> Self {
+        let is_separator = |c: Option<char>| matches!(c, Some('/' | '\\'));
+        let mut chars = path.chars();
+        let (first, second, third) = (chars.next(), chars.next(), chars.next());
+
+        if is_separator(first) && is_separator(second) {
+            PathFlavor::Unc
+        } else if first.is_some_and(|c| c.is_ascii_alphabetic())
+            && second == Some(':')
+            && is_separator(third)
+        {
+            PathFlavor::Drive
+        } else if first == Some('/') {
+            PathFlavor::Posix
+        } else {
+            PathFlavor::Relative
+        }
+    }
+
+    /// Whether the path follows Windows rules
+    pub fn is_windows(&self) -> bool {
+        matches!(self, PathFlavor::Drive | PathFlavor::Unc)
+    }
+
+    /// Directory separator of the flavour
+    pub fn separator(&self) -> char {
+        if self.is_windows() { '\\' } else { '/' }
+    }
+}
+
+/// File path as stored for a document or document type (絶対パス)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct StoredPath(String);
+
+impl StoredPath {
+    /// Path in its canonical form. Not validated; see `parse` and `validate`.
+    pub fn new(path: impl Into<String>) -> Self {
+        let path: String = path.into().nfc().collect();
+        let flavor = PathFlavor::detect(&path);
+        let (prefix, rest, fixed) = match flavor {
+            PathFlavor::Posix => ("/".to_string(), &path[1..], 0),
+            PathFlavor::Drive => (path[..1].to_ascii_uppercase() + ":\\", &path[3..], 0),
+            // Server and share name
+            PathFlavor::Unc => ("\\\\".to_string(), &path[2..], 2),
+            PathFlavor::Relative => return Self(path),
+        };
+
+        let separators: &[char] = if flavor.is_windows() {
+            &['/', '\\']
+        } else {
+            &['/']
+        };
+        let mut segments: Vec<&str> = Vec::new();
+        for segment in rest.split(separators) {
+            match segment {
+                "" | "." => {}
+                ".." => {
+                    if segments.len() > fixed {
+                        segments.pop();
+                    }
+                }
+                segment => segments.push(segment),
+            }
+        }
+
+        let separator = flavor.separator().to_string();
+        Self(prefix + &segments.join(&separator))
+    }
+
+    /// Parse an absolute path of any flavour
+    pub fn parse(path: &str) -> Result<Self> {
+        let path = Self::new(path);
+        path.validate()?;
+        Ok(path)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn flavor(&self) -> PathFlavor {
+        PathFlavor::detect(&self.0)
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        self.flavor() != PathFlavor::Relative
+    }
+
+    /// Check that the path is absolute and valid for its flavour
+    pub fn validate(&self) -> Result<()> {
+        let flavor = self.flavor();
+        if flavor == PathFlavor::Relative {
+            return Err(Error::RelativePathNotAllowed);
+        }
+        if self.0.chars().any(char::is_control) {
+            return Err(Error::InvalidPath(format!(
+                "control characters are not allowed: {:?}",
+                self.0
+            )));
+        }
+
+        let rest = match flavor {
+            PathFlavor::Drive => &self.0[3..],
+            PathFlavor::Unc => {
+                let rest = &self.0[2..];
+                let mut parts = rest.split('\\');
+                let server = parts.next().unwrap_or_default();
+                let share = parts.next().unwrap_or_default();
+                if server.is_empty() || share.is_empty() {
+                    return Err(Error::InvalidPath(format!(
+                        "UNC path needs a server and share name: {}",
+                        self.0
+                    )));
+                }
+                rest
+            }
+            PathFlavor::Posix | PathFlavor::Relative => return Ok(()),
+        };
+        if let Some(c) = rest.chars().find(|c| WINDOWS_RESERVED.contains(c)) {
+            return Err(Error::InvalidPath(format!(
+                "'{}' is not allowed in Windows paths: {}",
+                c, self.0
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether the path is `root` itself or lies under it
+    pub fn is_within(&self, root: &StoredPath) -> bool {
+        let flavor = self.flavor();
+        if flavor != root.flavor() || flavor == PathFlavor::Relative {
+            return false;
+        }
+        let (path, root) = (self.comparison_key(), root.comparison_key());
+        match path.strip_prefix(root.as_ref()) {
+            Some(rest) => {
+                rest.is_empty()
+                    || root.ends_with(flavor.separator())
+                    || rest.starts_with(flavor.separator())
+            }
+            None => false,
+        }
+    }
+
+    /// Text the path is compared by (lower-cased for Windows flavours)
+    pub fn comparison_key(&self) -> Cow<'_, str> {
+        if self.flavor().is_windows() {
+            Cow::Owned(self.0.to_lowercase())
+        } else {
+            Cow::Borrowed(&self.0)
+        }
+    }
+}
+
+impl PartialEq for StoredPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.comparison_key() == other.comparison_key()
+    }
+}
+
+impl Eq for StoredPath {}
+
+impl Hash for StoredPath {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.comparison_key().hash(state);
+    }
+}
+
+impl fmt::Display for StoredPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for StoredPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl From<String> for StoredPath {
+    fn from(path: String) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<&str> for StoredPath {
+    fn from(path: &str) -> Self {
+        Self::new(path)
+    }
+}
+
+impl From<StoredPath> for String {
+    fn from(path: StoredPath) -> Self {
+        path.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flavors() {
+        let cases = [
+            ("/docs/contracts/a.pdf", PathFlavor::Posix),
+            ("C:\\Users\\docs", PathFlavor::Drive),
+            ("c:/Users/docs", PathFlavor::Drive),
+            ("\\\\server\\share\\docs", PathFlavor::Unc),
+            ("//server/share/docs", PathFlavor::Unc),
+            ("docs/a.pdf", PathFlavor::Relative),
+            ("C:docs", PathFlavor::Relative),
+            ("\\docs", PathFlavor::Relative),
+            ("", PathFlavor::Relative),
+        ];
+        for (path, flavor) in cases {
+            assert_eq!(StoredPath::new(path).flavor(), flavor, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_normal_form() {
+        assert_eq!(StoredPath::new("c:/Users/docs").as_str(), "C:\\Users\\docs");
+        assert_eq!(
+            StoredPath::new("//server/share/docs").as_str(),
+            "\\\\server\\share\\docs"
+        );
+        // Backslashes are ordinary characters in POSIX names
+        assert_eq!(StoredPath::new("/docs/a\\b").as_str(), "/docs/a\\b");
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let cases = [
+            ("/docs/a/../b/x.pdf", "/docs/b/x.pdf"),
+            ("/docs/b/", "/docs/b"),
+            ("/docs//b/./x.pdf", "/docs/b/x.pdf"),
+            ("/../docs", "/docs"),
+            ("/", "/"),
+            ("C:\\docs\\..\\..\\x", "C:\\x"),
+            ("c:/", "C:\\"),
+            ("\\\\server\\share\\..\\x\\", "\\\\server\\share\\x"),
+            // Decomposed kana (か + combining dakuten) becomes NFC
+            ("/docs/\u{304b}\u{3099}.pdf", "/docs/\u{304c}.pdf"),
+        ];
+        for (path, canonical) in cases {
+            assert_eq!(StoredPath::new(path).as_str(), canonical, "{}", path);
+        }
+        assert_eq!(
+            StoredPath::new("/docs/a/../b/x.pdf"),
+            StoredPath::new("/docs/b/x.pdf")
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(StoredPath::parse("/docs/a.pdf").is_ok());
+        assert!(StoredPath::parse("C:\\Users\\docs").is_ok());
+        assert!(StoredPath::parse("\\\\server\\share\\docs").is_ok());
+        assert!(StoredPath::parse("/docs/a?b").is_ok());
+
+        assert!(matches!(
+            StoredPath::parse("docs/a.pdf"),
+            Err(Error::RelativePathNotAllowed)
+        ));
+        assert!(matches!(
+            StoredPath::parse("\\\\server"),
+            Err(Error::InvalidPath(_))
+        ));
+        assert!(matches!(
+            StoredPath::parse("C:\\docs\\a?.pdf"),
+            Err(Error::InvalidPath(_))
+        ));
+        assert!(matches!(
+            StoredPath::parse("/docs/a\0b"),
+            Err(Error::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn test_case_rules() {
+        assert_eq!(
+            StoredPath::new("C:\\Docs\\A.pdf"),
+            StoredPath::new("c:/docs/a.PDF")
+        );
+        assert_eq!(
+            StoredPath::new("\\\\Server\\Share"),
+            StoredPath::new("\\\\server\\share")
+        );
+        assert_ne!(
+            StoredPath::new("/Docs/a.pdf"),
+            StoredPath::new("/docs/a.pdf")
+        );
+    }
+
+    #[test]
+    fn test_is_within() {
+        let root = StoredPath::new("/docs/contracts/");
+        assert!(StoredPath::new("/docs/contracts/a.pdf").is_within(&root));
+        assert!(StoredPath::new("/docs/contracts").is_within(&root));
+        assert!(!StoredPath::new("/docs/contracts-old/a.pdf").is_within(&root));
+        assert!(!StoredPath::new("/docs/contracts/../invoices/a.pdf").is_within(&root));
+        assert!(StoredPath::new("/any/where").is_within(&StoredPath::new("/")));
+
+        let root = StoredPath::new("\\\\Server\\Share\\Contracts");
+        assert!(StoredPath::new("//server/share/contracts/a.pdf").is_within(&root));
+        assert!(!StoredPath::new("\\\\server\\share\\a.pdf").is_within(&root));
+        assert!(StoredPath::new("c:/docs/a.pdf").is_within(&StoredPath::new("C:\\")));
+        assert!(!StoredPath::new("/docs/contracts/a.pdf").is_within(&StoredPath::new("C:\\")));
+    }
+
+    #[test]
+    fn test_serde_normalizes() -> anyhow::Result<()> {
+        let path: StoredPath = serde_json::from_str(r#""d:/shared/docs""#)?;
+        assert_eq!(serde_json::to_string(&path)?, r#""D:\\shared\\docs""#);
+        Ok(())
+    }
+}
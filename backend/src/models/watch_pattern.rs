@@ -0,0 +1,239 @@
+//! File name patterns for the filesystem watcher
+//!
+//! A document type with a watch pattern has new files under its root
+//! directory registered automatically when their name matches:
+//!
+//! | Syntax     | Matches                                            |
+//! |------------|----------------------------------------------------|
+//! | `*`        | Any run of characters (possibly empty)             |
+//! | `?`        | Exactly one character                              |
+//! | `{number}` | The document number (one or more characters)       |
+//! | `{{`/`}}`  | Literal braces                                     |
+//!
+//! With `{number}` the file is registered as a manual document under the
+//! captured number (`{number}.pdf`); without it the number is generated
+//! (`*.pdf`). Matching is case-sensitive and covers the file name only.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    AnyRun,
+    AnyChar,
+    Number,
+}
+
+/// How a matching file is numbered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchMatch {
+    /// Number taken from the file name
+    Manual(String),
+    /// Number generated by the type's rule
+    Auto,
+}
+
+/// Parsed watch pattern of a document type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct WatchPattern {
+    source: String,
+    tokens: Vec<Token>,
+}
+
+impl WatchPattern {
+    /// Pattern text as written
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Match a file name; `None` if it does not match
+    pub fn matches(&self, file_name: &str) -> Option<WatchMatch> {
+        let chars: Vec<char> = file_name.chars().collect();
+        let mut capture = None;
+        if !match_tokens(&self.tokens, &chars, 0, &mut capture) {
+            return None;
+        }
+        Some(match capture {
+            Some((start, end)) => WatchMatch::Manual(chars[start..end].iter().collect()),
+            None => WatchMatch::Auto,
+        })
+    }
+}
+
+/// Backtracking match of `tokens` against `chars[offset..]`, recording the
+/// character range of `{number}` in `capture`
+fn match_tokens(
+    tokens: &[Token],
+    chars: &[char],
+    offset: usize,
+    capture: &mut Option<(usize, usize)>,
+) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return offset == chars.len();
+    };
+    match token {
+        Token::Literal(c) => {
+            chars.get(offset) == Some(c) && match_tokens(rest, chars, offset + 1, capture)
+        }
+        Token::AnyChar => offset < chars.len() && match_tokens(rest, chars, offset + 1, capture),
+        Token::AnyRun => {
+            (offset..=chars.len()).any(|next| match_tokens(rest, chars, next, capture))
+        }
+        Token::Number => (offset + 1..=chars.len()).any(|end| {
+            let matched = match_tokens(rest, chars, end, capture);
+            if matched {
+                *capture = Some((offset, end));
+            }
+            matched
+        }),
+    }
+}
+
+impl FromStr for WatchPattern {
+    type Err = Error;
+
+    /// Parse a pattern. Error positions are 0-based character offsets.
+    fn from_str(source: &str) -> Result<Self> {
+        if source.is_empty() {
+            return Err(Error::Validation("Watch pattern must not be empty".into()));
+        }
+        if source.contains(['/', '\\']) {
+            return Err(Error::Validation(format!(
+                "Watch pattern '{}' must match file names only",
+                source
+            )));
+        }
+
+        let mut tokens = Vec::new();
+        let mut chars = source.chars().enumerate().peekable();
+        while let Some((position, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().map(|(_, next)| *next) == Some('{') => {
+                    chars.next();
+                    tokens.push(Token::Literal('{'));
+                }
+                '}' if chars.peek().map(|(_, next)| *next) == Some('}') => {
+                    chars.next();
+                    tokens.push(Token::Literal('}'));
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '}')) => break,
+                            Some((_, c)) => name.push(c),
+                            None => {
+                                return Err(Error::Validation(format!(
+                                    "Unclosed '{{' at position {} in watch pattern",
+                                    position
+                                )));
+                            }
+                        }
+                    }
+                    if name != "number" {
+                        return Err(Error::Validation(format!(
+                            "Unknown placeholder '{{{}}}' at position {} in watch pattern",
+                            name, position
+                        )));
+                    }
+                    if tokens.contains(&Token::Number) {
+                        return Err(Error::Validation(
+                            "Watch pattern may contain {number} only once".to_string(),
+                        ));
+                    }
+                    tokens.push(Token::Number);
+                }
+                '}' => {
+                    return Err(Error::Validation(format!(
+                        "Unmatched '}}' at position {} in watch pattern",
+                        position
+                    )));
+                }
+                '*' => tokens.push(Token::AnyRun),
+                '?' => tokens.push(Token::AnyChar),
+                c => tokens.push(Token::Literal(c)),
+            }
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            tokens,
+        })
+    }
+}
+
+impl TryFrom<String> for WatchPattern {
+    type Error = Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        source.parse()
+    }
+}
+
+impl From<WatchPattern> for String {
+    fn from(pattern: WatchPattern) -> Self {
+        pattern.source
+    }
+}
+
+impl fmt::Display for WatchPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_patterns() -> Result<()> {
+        let pdf: WatchPattern = "*.pdf".parse()?;
+        assert_eq!(pdf.matches("見積書.pdf"), Some(WatchMatch::Auto));
+        assert_eq!(pdf.matches(".pdf"), Some(WatchMatch::Auto));
+        assert_eq!(pdf.matches("a.PDF"), None);
+        assert_eq!(pdf.matches("a.pdf.tmp"), None);
+
+        let scan: WatchPattern = "scan_????.tif".parse()?;
+        assert_eq!(scan.matches("scan_0001.tif"), Some(WatchMatch::Auto));
+        assert_eq!(scan.matches("scan_001.tif"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_number_capture() -> Result<()> {
+        let manual: WatchPattern = "{number}.pdf".parse()?;
+        assert_eq!(
+            manual.matches("AGI2510001.pdf"),
+            Some(WatchMatch::Manual("AGI2510001".to_string()))
+        );
+        assert_eq!(manual.matches(".pdf"), None);
+
+        let titled: WatchPattern = "{number}_*.docx".parse()?;
+        assert_eq!(
+            titled.matches("CTR-0007_draft_v2.docx"),
+            Some(WatchMatch::Manual("CTR-0007".to_string()))
+        );
+
+        let braces: WatchPattern = "{{{number}}}.txt".parse()?;
+        assert_eq!(
+            braces.matches("{X-1}.txt"),
+            Some(WatchMatch::Manual("X-1".to_string()))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_patterns() {
+        assert!("".parse::<WatchPattern>().is_err());
+        assert!("in/*.pdf".parse::<WatchPattern>().is_err());
+        assert!("{num}.pdf".parse::<WatchPattern>().is_err());
+        assert!("{number}-{number}.pdf".parse::<WatchPattern>().is_err());
+        assert!("{number.pdf".parse::<WatchPattern>().is_err());
+        assert!("number}.pdf".parse::<WatchPattern>().is_err());
+    }
+}
@@ -0,0 +1,577 @@
+//! Numbering audit service
+//!
+//! Auditors need to show that no number in a scope went missing. The audit
+//! maps every document and reservation back to its counter scope and reports,
+//! per scope, which counter values were issued, deleted, reserved, taken by
+//! manual numbers, or never used at all (e.g., counters advanced by hand or
+//! numbers burned before generation and insert shared a transaction).
+
+use crate::error::Result;
+use crate::models::{
+    DocumentPath, DocumentType, PathGenerationRule, ReservationStatus, RuleVersion, ScopeDimension,
+};
+use crate::services::clock::GenerationClock;
+use crate::services::generation_service::{self, ScopeOwner};
+use crate::storage::{counter, document_path, document_type, reservation};
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// A document number tied to a counter value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditedNumber {
+    pub counter_value: i64,
+    pub document_number: String,
+}
+
+/// Audit result for a single counter scope
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct ScopeAudit {
+    /// Counter scope key (e.g., "A_G_I_2025_10")
+    pub scope_key: String,
+    /// Last value handed out by the counter; None when the scope has
+    /// numbers but no counter row (e.g., imported data or a lost counter)
+    pub current_value: Option<i64>,
+    /// Values up to this one were issued outside the system (not missing)
+    pub seeded_value: i64,
+    /// Live auto-generated documents
+    pub issued: Vec<AuditedNumber>,
+    /// Logically deleted auto-generated documents
+    pub deleted: Vec<AuditedNumber>,
+    /// Reserved numbers without a document (pending, released or expired)
+    pub reserved: Vec<AuditedNumber>,
+    /// Counter values with no document, reservation or manual number
+    pub missing: Vec<i64>,
+    /// Manually entered numbers that fall on the auto sequence
+    pub manual_collisions: Vec<AuditedNumber>,
+}
+
+impl ScopeAudit {
+    /// True when the scope has a counter, every counter value is accounted
+    /// for and no manual number collides with the sequence
+    pub fn is_clean(&self) -> bool {
+        self.current_value.is_some() && self.missing.is_empty() && self.manual_collisions.is_empty()
+    }
+
+    fn without_counter(scope_key: &str) -> Self {
+        Self {
+            scope_key: scope_key.to_string(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Numbering audit report
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct AuditReport {
+    /// One entry per counter scope, ordered by scope key
+    pub scopes: Vec<ScopeAudit>,
+    /// Auto-generated numbers that could not be traced back to a counter
+    /// (e.g., the type's rule was edited in place before rules were versioned)
+    pub unmatched: Vec<String>,
+}
+
+/// Build the numbering audit report for every counter scope, including
+/// scopes that have numbers but no counter row, or for a single scope when
+/// `scope_key` is given.
+/// Document dates are evaluated in the type's timezone, falling back to the
+/// clock's business timezone, as they were when the numbers were generated,
+/// and each document is read with the rule version that numbered it.
+pub async fn audit_counters(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    scope_key: Option<&str>,
+) -> Result<AuditReport> {
+    let wanted = |key: &str| scope_key.is_none_or(|wanted| wanted == key);
+    let mut scopes: BTreeMap<String, ScopeAudit> = counter::list_counters(pool)
+        .await?
+        .into_iter()
+        .filter(|c| wanted(&c.scope_key))
+        .map(|c| {
+            let key = c.scope_key;
+            let audit = ScopeAudit {
+                scope_key: key.clone(),
+                current_value: Some(c.current_value),
+                seeded_value: c.seeded_value,
+                ..ScopeAudit::default()
+            };
+            (key, audit)
+        })
+        .collect();
+
+    let (doc_types, versions) = load_rules(pool).await?;
+    let documents = document_path::list_document_paths(pool, true).await?;
+
+    // Counter affixes seen per scope, used to recognise back-dated manual numbers
+    let mut affixes_by_scope: BTreeMap<String, BTreeSet<(String, String)>> = BTreeMap::new();
+    let mut placed = Vec::new();
+    let mut unmatched = Vec::new();
+
+    for doc in &documents {
+        let location = doc_types
+            .get(&doc.document_type.0)
+            .map(|t| locate(clock, t, rule_versions(&versions, t), doc))
+            .transpose()?
+            .flatten();
+
+        match location {
+            Some(location) => {
+                affixes_by_scope
+                    .entry(location.scope_key.clone())
+                    .or_default()
+                    .insert(location.affixes);
+                placed.push((doc, location.scope_key, location.counter_value));
+            }
+            None if doc.generated => unmatched.push(doc.document_number.clone()),
+            None => {}
+        }
+    }
+
+    for (doc, key, value) in &placed {
+        if !wanted(key) {
+            continue;
+        }
+        let scope = scopes
+            .entry(key.clone())
+            .or_insert_with(|| ScopeAudit::without_counter(key));
+        let number = AuditedNumber {
+            counter_value: *value,
+            document_number: doc.document_number.clone(),
+        };
+        match (doc.generated, doc.deleted) {
+            (true, false) => scope.issued.push(number),
+            (true, true) => scope.deleted.push(number),
+            (false, _) => scope.manual_collisions.push(number),
+        }
+    }
+
+    // Manual numbers dated outside their scope's period are still collisions
+    // when they match the affixes of numbers issued in that scope
+    let placed_ids: HashSet<&str> = placed.iter().map(|(doc, _, _)| doc.id.0.as_str()).collect();
+    for doc in documents.iter().filter(|d| !d.generated) {
+        if placed_ids.contains(doc.id.0.as_str()) {
+            continue;
+        }
+        let Some(doc_type) = doc_types.get(&doc.document_type.0) else {
+            continue;
+        };
+        let (rule, _) = rule_for(clock, doc_type, rule_versions(&versions, doc_type), doc);
+        for (key, affixes) in &affixes_by_scope {
+            let value = affixes.iter().find_map(|(prefix, suffix)| {
+                generation_service::counter_value_in(rule, (prefix, suffix), &doc.document_number)
+            });
+            if let (Some(value), Some(scope)) = (value, scopes.get_mut(key)) {
+                scope.manual_collisions.push(AuditedNumber {
+                    counter_value: value,
+                    document_number: doc.document_number.clone(),
+                });
+                break;
+            }
+        }
+    }
+
+    for reserved in reservation::list_reservations(pool, None).await? {
+        if reserved.status == ReservationStatus::Confirmed {
+            continue;
+        }
+        if !wanted(&reserved.scope_key) {
+            continue;
+        }
+        let scope = scopes
+            .entry(reserved.scope_key.clone())
+            .or_insert_with(|| ScopeAudit::without_counter(&reserved.scope_key));
+        scope.reserved.push(AuditedNumber {
+            counter_value: reserved.counter_value,
+            document_number: reserved.document_number,
+        });
+    }
+
+    for scope in scopes.values_mut() {
+        for numbers in [
+            &mut scope.issued,
+            &mut scope.deleted,
+            &mut scope.reserved,
+            &mut scope.manual_collisions,
+        ] {
+            numbers.sort_by_key(|n| n.counter_value);
+        }
+
+        let accounted: BTreeSet<i64> = scope
+            .issued
+            .iter()
+            .chain(&scope.deleted)
+            .chain(&scope.reserved)
+            .chain(&scope.manual_collisions)
+            .map(|n| n.counter_value)
+            .collect();
+        let current_value = scope.current_value.unwrap_or_default();
+        scope.missing = (scope.seeded_value + 1..=current_value)
+            .filter(|value| !accounted.contains(value))
+            .collect();
+    }
+
+    unmatched.sort();
+    Ok(AuditReport {
+        scopes: scopes.into_values().collect(),
+        unmatched,
+    })
+}
+
+/// Counter values in use per scope, whether or not the scope has a counter
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct CounterUsage {
+    /// Highest counter value taken per scope key by a document (live,
+    /// deleted or manual) or a reservation
+    pub highest: BTreeMap<String, i64>,
+    /// Auto-generated numbers that could not be traced back to a counter
+    pub unmatched: Vec<String>,
+}
+
+/// Parse every document number back through the rule that numbered it and
+/// collect the highest counter value per scope, together with reservations.
+/// Scopes without a counter row are included, so counters lost in a restore
+/// show up.
+pub async fn counter_usage(pool: &SqlitePool, clock: &GenerationClock) -> Result<CounterUsage> {
+    let (doc_types, versions) = load_rules(pool).await?;
+    let mut usage = CounterUsage::default();
+
+    for doc in document_path::list_document_paths(pool, true).await? {
+        let location = doc_types
+            .get(&doc.document_type.0)
+            .map(|t| locate(clock, t, rule_versions(&versions, t), &doc))
+            .transpose()?
+            .flatten();
+
+        match location {
+            Some(location) => usage.record(location.scope_key, location.counter_value),
+            None if doc.generated => usage.unmatched.push(doc.document_number),
+            None => {}
+        }
+    }
+    for reserved in reservation::list_reservations(pool, None).await? {
+        usage.record(reserved.scope_key, reserved.counter_value);
+    }
+
+    usage.unmatched.sort();
+    Ok(usage)
+}
+
+impl CounterUsage {
+    fn record(&mut self, scope_key: String, counter_value: i64) {
+        let highest = self.highest.entry(scope_key).or_insert(counter_value);
+        *highest = (*highest).max(counter_value);
+    }
+}
+
+/// Document types by code, with their rule versions
+async fn load_rules(
+    pool: &SqlitePool,
+) -> Result<(
+    HashMap<String, DocumentType>,
+    HashMap<String, Vec<RuleVersion>>,
+)> {
+    let doc_types: HashMap<String, DocumentType> = document_type::list_document_types(pool)
+        .await?
+        .into_iter()
+        .map(|t| (t.code.0.clone(), t))
+        .collect();
+    let mut versions: HashMap<String, Vec<RuleVersion>> = HashMap::new();
+    for code in doc_types.values().map(|t| &t.code) {
+        versions.insert(
+            code.0.clone(),
+            document_type::list_rule_versions(pool, code).await?,
+        );
+    }
+    Ok((doc_types, versions))
+}
+
+/// Where a document number sits in the auto sequence
+struct Location {
+    scope_key: String,
+    affixes: (String, String),
+    counter_value: i64,
+}
+
+fn rule_versions<'a>(
+    versions: &'a HashMap<String, Vec<RuleVersion>>,
+    doc_type: &DocumentType,
+) -> &'a [RuleVersion] {
+    versions
+        .get(&doc_type.code.0)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Rule that numbered a document and the business date it was created:
+/// the recorded rule version, else the version in effect on that date
+fn rule_for<'a>(
+    clock: &GenerationClock,
+    doc_type: &'a DocumentType,
+    versions: &'a [RuleVersion],
+    doc: &DocumentPath,
+) -> (&'a PathGenerationRule, NaiveDate) {
+    let timezone = doc_type.timezone.unwrap_or(clock.timezone());
+    let date = doc.created_at.with_timezone(&timezone).date_naive();
+    let recorded = doc
+        .generation_rule_id
+        .and_then(|id| versions.iter().find(|v| v.id == id));
+    let rule = recorded
+        .or_else(|| RuleVersion::effective_on(versions, date))
+        .map(|version| &version.rule)
+        .unwrap_or(&doc_type.generation_rule);
+    (rule, date)
+}
+
+/// Locate a document in its counter scope,
+/// evaluated on the business date it was created
+fn locate(
+    clock: &GenerationClock,
+    doc_type: &DocumentType,
+    versions: &[RuleVersion],
+    doc: &DocumentPath,
+) -> Result<Option<Location>> {
+    let (rule, date) = rule_for(clock, doc_type, versions, doc);
+    if doc.business_task.is_none() && rule.counter_scope.uses(ScopeDimension::Task) {
+        return Ok(None);
+    }
+
+    let Some(affixes) = generation_service::counter_affixes(
+        rule,
+        &doc.document_type,
+        &doc.department,
+        &doc.section,
+        date,
+    )?
+    else {
+        return Ok(None);
+    };
+    let Some(value) =
+        generation_service::counter_value_in(rule, (&affixes.0, &affixes.1), &doc.document_number)
+    else {
+        return Ok(None);
+    };
+    let scope_key = generation_service::build_scope_key(
+        rule,
+        &doc.document_type,
+        &doc.department,
+        &doc.section,
+        ScopeOwner::new(&doc.user, doc.business_task.as_ref()),
+        date,
+    )?;
+
+    Ok(Some(Location {
+        scope_key,
+        affixes,
+        counter_value: value,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        Department, DeptCode, PathGenerationRule, Section, SectionCode, StoredPath, TypeCode, User,
+        UserId,
+    };
+    use crate::services::document_service::{self, AutoDocumentRequest, ManualDocumentRequest};
+    use crate::storage::db::init_db_pool;
+    use crate::storage::{department, section, user};
+    use chrono::{TimeZone, Utc};
+
+    async fn setup_test_data(pool: &SqlitePool) -> anyhow::Result<()> {
+        department::create_department(pool, &Department::new('G', "総務")).await?;
+        let sec = Section {
+            code: SectionCode::new('I'),
+            name: "インフラ".to_string(),
+            department: DeptCode::new('G'),
+        };
+        section::create_section(pool, &sec).await?;
+        user::create_user(pool, &User::new("user001", "田川太郎", 'G', 'I')).await?;
+        let doc_type = DocumentType::new(
+            "A",
+            "契約書",
+            "/docs/contracts/",
+            PathGenerationRule::example_agi(),
+        );
+        document_type::create_document_type(pool, &doc_type).await?;
+        Ok(())
+    }
+
+    fn october_clock() -> anyhow::Result<GenerationClock> {
+        let now = Utc
+            .with_ymd_and_hms(2025, 10, 1, 3, 0, 0)
+            .single()
+            .ok_or_else(|| anyhow::anyhow!("invalid datetime"))?;
+        Ok(GenerationClock::fixed(now, chrono_tz::Asia::Tokyo))
+    }
+
+    async fn create_auto(
+        pool: &SqlitePool,
+        clock: &GenerationClock,
+    ) -> anyhow::Result<DocumentPath> {
+        Ok(document_service::create_document_auto(
+            pool,
+            clock,
+            AutoDocumentRequest {
+                type_code: TypeCode::new("A"),
+                dept_code: DeptCode::new('G'),
+                section_code: SectionCode::new('I'),
+                user_id: UserId::new("user001"),
+                file_path: StoredPath::new("/docs/contracts/test.pdf"),
+                business_task: None,
+                extension: None,
+            },
+        )
+        .await?
+        .document)
+    }
+
+    async fn create_manual(pool: &SqlitePool, number: &str) -> anyhow::Result<DocumentPath> {
+        Ok(document_service::create_document_manual(
+            pool,
+            &GenerationClock::default(),
+            ManualDocumentRequest {
+                document_number: number.to_string(),
+                type_code: TypeCode::new("A"),
+                dept_code: DeptCode::new('G'),
+                section_code: SectionCode::new('I'),
+                user_id: UserId::new("user001"),
+                file_path: StoredPath::new("/docs/contracts/manual.pdf"),
+                business_task: None,
+            },
+        )
+        .await?
+        .document)
+    }
+
+    #[tokio::test]
+    async fn test_audit_reports_issued_deleted_and_missing() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = october_clock()?;
+
+        create_auto(&pool, &clock).await?; // 001
+        let second = create_auto(&pool, &clock).await?; // 002
+        document_service::delete_document(&pool, &second.id).await?;
+        // 003 is consumed by a failed insert
+        counter::get_next_counter(&pool, "A_G_I_2025_10").await?;
+        create_auto(&pool, &clock).await?; // 004
+
+        let report = audit_counters(&pool, &clock, None).await?;
+        assert_eq!(report.scopes.len(), 1);
+        assert!(report.unmatched.is_empty());
+
+        let scope = &report.scopes[0];
+        assert_eq!(scope.scope_key, "A_G_I_2025_10");
+        assert_eq!(scope.current_value, Some(4));
+        assert_eq!(
+            scope
+                .issued
+                .iter()
+                .map(|n| n.document_number.as_str())
+                .collect::<Vec<_>>(),
+            vec!["AGI2510001", "AGI2510004"]
+        );
+        assert_eq!(scope.deleted.len(), 1);
+        assert_eq!(scope.deleted[0].counter_value, 2);
+        assert_eq!(scope.missing, vec![3]);
+        assert!(!scope.is_clean());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_audit_reports_manual_collisions() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = october_clock()?;
+
+        create_auto(&pool, &clock).await?; // 001
+        // Manual number on the auto sequence (created "today" in real time,
+        // so it is matched through the scope's affixes)
+        create_manual(&pool, "AGI2510002").await?;
+        counter::get_next_counter(&pool, "A_G_I_2025_10").await?; // 002 fails to insert
+        // Manual number unrelated to the sequence
+        create_manual(&pool, "LEGACY-17").await?;
+
+        let report = audit_counters(&pool, &clock, Some("A_G_I_2025_10")).await?;
+        let scope = &report.scopes[0];
+        assert_eq!(
+            scope.manual_collisions,
+            vec![AuditedNumber {
+                counter_value: 2,
+                document_number: "AGI2510002".to_string(),
+            }]
+        );
+        assert!(scope.missing.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_audit_accounts_for_reservations_and_filters_scope() -> anyhow::Result<()> {
+        use crate::services::reservation_service::{self, ReserveNumberRequest};
+
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = october_clock()?;
+
+        let reserved = reservation_service::reserve_number(
+            &pool,
+            &clock,
+            ReserveNumberRequest {
+                type_code: TypeCode::new("A"),
+                dept_code: DeptCode::new('G'),
+                section_code: SectionCode::new('I'),
+                user_id: UserId::new("user001"),
+                business_task: None,
+                ttl: chrono::Duration::hours(1),
+            },
+        )
+        .await?;
+        reservation_service::release_reservation(&pool, &clock, &reserved.id).await?;
+        counter::get_next_counter(&pool, "other_scope").await?;
+
+        let report = audit_counters(&pool, &clock, Some("A_G_I_2025_10")).await?;
+        assert_eq!(report.scopes.len(), 1);
+        let scope = &report.scopes[0];
+        assert_eq!(scope.reserved.len(), 1);
+        assert_eq!(scope.reserved[0].document_number, "AGI2510001");
+        assert!(scope.is_clean());
+
+        let report = audit_counters(&pool, &clock, None).await?;
+        assert_eq!(report.scopes.len(), 2);
+        assert_eq!(report.scopes[1].scope_key, "other_scope");
+        assert_eq!(report.scopes[1].missing, vec![1]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_audit_includes_scopes_without_counter() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = october_clock()?;
+
+        create_auto(&pool, &clock).await?; // 001
+        create_auto(&pool, &clock).await?; // 002
+        // Counter lost, e.g. in a partial restore
+        counter::delete_counter(&pool, "A_G_I_2025_10").await?;
+
+        for filter in [None, Some("A_G_I_2025_10")] {
+            let report = audit_counters(&pool, &clock, filter).await?;
+            assert_eq!(report.scopes.len(), 1);
+            let scope = &report.scopes[0];
+            assert_eq!(scope.scope_key, "A_G_I_2025_10");
+            assert_eq!(scope.current_value, None);
+            assert_eq!(scope.issued.len(), 2);
+            assert!(scope.missing.is_empty());
+            assert!(!scope.is_clean());
+        }
+        assert!(
+            audit_counters(&pool, &clock, Some("other_scope"))
+                .await?
+                .scopes
+                .is_empty()
+        );
+        Ok(())
+    }
+}
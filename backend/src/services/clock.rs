@@ -0,0 +1,148 @@
+//! Business clock for date-dependent number generation
+
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use std::sync::Arc;
+
+/// Default business timezone (JST)
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::Asia::Tokyo;
+
+/// Source of the current instant
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock backed by the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock pinned to a fixed instant (for tests and backfills)
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// Clock used for document number generation.
+/// Combines a time source with the deployment's business timezone so that
+/// year/month/day components follow the local calendar rather than UTC.
+#[derive(Clone)]
+pub struct GenerationClock {
+    clock: Arc<dyn Clock>,
+    timezone: Tz,
+}
+
+impl GenerationClock {
+    /// Create a clock from a time source and business timezone
+    pub fn new(clock: Arc<dyn Clock>, timezone: Tz) -> Self {
+        Self { clock, timezone }
+    }
+
+    /// System time in the given business timezone
+    pub fn system(timezone: Tz) -> Self {
+        Self::new(Arc::new(SystemClock), timezone)
+    }
+
+    /// Fixed instant in the given business timezone
+    pub fn fixed(now: DateTime<Utc>, timezone: Tz) -> Self {
+        Self::new(Arc::new(FixedClock(now)), timezone)
+    }
+
+    /// Fixed local time, with its timezone as the business timezone
+    pub fn fixed_local(local: DateTime<Tz>) -> Self {
+        Self::fixed(local.with_timezone(&Utc), local.timezone())
+    }
+
+    /// Deployment business timezone
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// Current instant (UTC)
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Current business date, optionally in a per-document-type timezone
+    pub fn today(&self, timezone_override: Option<Tz>) -> NaiveDate {
+        let timezone = timezone_override.unwrap_or(self.timezone);
+        self.now().with_timezone(&timezone).date_naive()
+    }
+}
+
+impl Default for GenerationClock {
+    fn default() -> Self {
+        Self::system(DEFAULT_TIMEZONE)
+    }
+}
+
+impl std::fmt::Debug for GenerationClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GenerationClock")
+            .field("now", &self.now())
+            .field("timezone", &self.timezone)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_today_uses_business_timezone() -> anyhow::Result<()> {
+        // 2025-09-30 16:00 UTC = 2025-10-01 01:00 JST
+        let now = Utc
+            .with_ymd_and_hms(2025, 9, 30, 16, 0, 0)
+            .single()
+            .ok_or_else(|| anyhow::anyhow!("invalid datetime"))?;
+        let clock = GenerationClock::fixed(now, DEFAULT_TIMEZONE);
+
+        assert_eq!(
+            clock.today(None),
+            NaiveDate::from_ymd_opt(2025, 10, 1).ok_or_else(|| anyhow::anyhow!("invalid date"))?
+        );
+        assert_eq!(
+            clock.today(Some(chrono_tz::UTC)),
+            NaiveDate::from_ymd_opt(2025, 9, 30).ok_or_else(|| anyhow::anyhow!("invalid date"))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixed_local_keeps_local_date() -> anyhow::Result<()> {
+        // 2025-10-01 01:00 JST = 2025-09-30 16:00 UTC
+        let local = chrono_tz::Asia::Tokyo
+            .with_ymd_and_hms(2025, 10, 1, 1, 0, 0)
+            .single()
+            .ok_or_else(|| anyhow::anyhow!("invalid datetime"))?;
+        let clock = GenerationClock::fixed_local(local);
+
+        assert_eq!(clock.timezone(), chrono_tz::Asia::Tokyo);
+        assert_eq!(clock.now(), local.with_timezone(&Utc));
+        assert_eq!(
+            clock.today(None),
+            NaiveDate::from_ymd_opt(2025, 10, 1).ok_or_else(|| anyhow::anyhow!("invalid date"))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_system_clock_advances() {
+        let clock = GenerationClock::default();
+        let first = clock.now();
+        let second = clock.now();
+        assert!(second >= first);
+        assert_eq!(clock.timezone(), chrono_tz::Asia::Tokyo);
+    }
+}
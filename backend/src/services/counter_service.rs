@@ -0,0 +1,436 @@
+//! Counter administration service
+//!
+//! When numbering moves over from an external ledger mid-period, the
+//! counters are seeded so that the sequence continues where the ledger left
+//! off. Seeding never moves a counter below a number already issued in the
+//! scope, so it cannot cause the same number to be handed out twice.
+//!
+//! After a restore or a manual import the counters can instead fall behind
+//! the documents; reconciliation parses the existing numbers back into their
+//! scopes and raises every counter that is behind.
+
+use crate::error::{Error, Result};
+use crate::models::{Counter, PathGenerationRule};
+use crate::services::clock::GenerationClock;
+use crate::services::{audit_service, generation_service, rule_service};
+use crate::storage::{counter, document_type};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+/// Outcome of seeding a counter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CounterSeed {
+    /// Counter scope key (e.g., "A_G_I_2025_10")
+    pub scope_key: String,
+    /// Counter value before seeding (None when the scope had no counter)
+    pub previous_value: Option<i64>,
+    /// Highest number found in the scope (documents, reservations, manual collisions)
+    pub highest_issued: Option<i64>,
+    /// Next value the counter will hand out
+    pub next_value: i64,
+}
+
+/// A counter that is behind the numbers already in use in its scope
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CounterRepair {
+    /// Counter scope key (e.g., "A_G_I_2025_10")
+    pub scope_key: String,
+    /// Counter value found (None when the scope had no counter)
+    pub current_value: Option<i64>,
+    /// Highest counter value in use by a document or reservation
+    pub highest_in_use: i64,
+}
+
+/// Counter reconciliation result
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct ReconcileReport {
+    /// Whether the repairs were written (false in report-only mode)
+    pub applied: bool,
+    /// Counters behind their documents, ordered by scope key
+    pub repairs: Vec<CounterRepair>,
+    /// Auto-generated numbers that could not be traced back to a counter
+    pub unmatched: Vec<String>,
+}
+
+/// List all counters
+pub async fn list_counters(pool: &SqlitePool) -> Result<Vec<Counter>> {
+    counter::list_counters(pool).await
+}
+
+/// Seed the counter of `scope_key` so that the next number drawn is `next_value`.
+/// The key must have the shape of a document type's current counter scope and
+/// `next_value` must fit its counter. Refuses to go at or below the highest
+/// counter value already used in the scope by a document (live or deleted),
+/// a reservation or a manual number.
+pub async fn seed_counter(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    scope_key: &str,
+    next_value: i64,
+) -> Result<CounterSeed> {
+    if scope_key.trim().is_empty() {
+        return Err(Error::Validation("Scope key cannot be empty".to_string()));
+    }
+    if next_value < 1 {
+        return Err(Error::Validation(format!(
+            "Next counter value must be at least 1, got {}",
+            next_value
+        )));
+    }
+
+    let rules = scope_rules(pool, clock, scope_key).await?;
+    let Some(capacity) = rules.iter().map(|rule| rule.counter_capacity()).min() else {
+        return Err(Error::Validation(format!(
+            "Scope key '{}' does not match the counter scope of any document type",
+            scope_key
+        )));
+    };
+    if next_value > capacity {
+        return Err(Error::Validation(format!(
+            "Cannot seed counter '{}' to continue at {}: the counter only goes up to {}",
+            scope_key, next_value, capacity
+        )));
+    }
+
+    let previous_value = counter::get_current_counter(pool, scope_key).await?;
+    let highest_issued = highest_issued(pool, clock, scope_key).await?;
+    if let Some(highest) = highest_issued
+        && next_value <= highest
+    {
+        return Err(Error::Validation(format!(
+            "Cannot seed counter '{}' to continue at {}: number {} has already been issued",
+            scope_key, next_value, highest
+        )));
+    }
+
+    // Only apply if nothing was issued since the check above
+    if !counter::seed_counter(pool, scope_key, previous_value, next_value - 1).await? {
+        return Err(Error::ConcurrentModification);
+    }
+
+    Ok(CounterSeed {
+        scope_key: scope_key.to_string(),
+        previous_value,
+        highest_issued,
+        next_value,
+    })
+}
+
+/// Compare every counter with the highest value in use in its scope and,
+/// when `apply` is set, raise the counters that are behind (creating missing
+/// ones). Counters ahead of their documents are left alone.
+pub async fn reconcile_counters(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    apply: bool,
+) -> Result<ReconcileReport> {
+    let usage = audit_service::counter_usage(pool, clock).await?;
+    let counters: HashMap<String, i64> = counter::list_counters(pool)
+        .await?
+        .into_iter()
+        .map(|c| (c.scope_key, c.current_value))
+        .collect();
+
+    let repairs: Vec<CounterRepair> = usage
+        .highest
+        .into_iter()
+        .filter_map(|(scope_key, highest_in_use)| {
+            let current_value = counters.get(&scope_key).copied();
+            (current_value.is_none_or(|value| value < highest_in_use)).then_some(CounterRepair {
+                scope_key,
+                current_value,
+                highest_in_use,
+            })
+        })
+        .collect();
+
+    if apply {
+        // Raising is monotonic, so a number issued meanwhile cannot be undone
+        for repair in &repairs {
+            counter::raise_counter(pool, &repair.scope_key, repair.highest_in_use).await?;
+        }
+    }
+
+    Ok(ReconcileReport {
+        applied: apply,
+        repairs,
+        unmatched: usage.unmatched,
+    })
+}
+
+/// Current rules of the document types whose counter scope keys have the
+/// shape of `scope_key` (several only when the scope is not keyed on the type)
+async fn scope_rules(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    scope_key: &str,
+) -> Result<Vec<PathGenerationRule>> {
+    let mut rules = Vec::new();
+    for doc_type in document_type::list_document_types(pool).await? {
+        let today = clock.today(doc_type.timezone);
+        let rule = rule_service::effective_rule(pool, &doc_type, today).await?;
+        if generation_service::scope_key_matches(&rule, &doc_type.code, scope_key) {
+            rules.push(rule);
+        }
+    }
+    Ok(rules)
+}
+
+/// Highest counter value used in the scope, including scopes that have no
+/// counter row yet (e.g., only imported or manual numbers)
+async fn highest_issued(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    scope_key: &str,
+) -> Result<Option<i64>> {
+    let usage = audit_service::counter_usage(pool, clock).await?;
+    Ok(usage.highest.get(scope_key).copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        Department, DeptCode, DocumentType, PathGenerationRule, Section, SectionCode, StoredPath,
+        TypeCode, User, UserId,
+    };
+    use crate::services::document_service::{self, AutoDocumentRequest};
+    use crate::storage::db::init_db_pool;
+    use crate::storage::{department, document_type, section, user};
+    use chrono::TimeZone;
+
+    async fn setup_test_data(pool: &SqlitePool) -> anyhow::Result<()> {
+        department::create_department(pool, &Department::new('G', "総務")).await?;
+        section::create_section(pool, &Section::new('I', "インフラ", 'G')).await?;
+        user::create_user(pool, &User::new("user001", "田川太郎", 'G', 'I')).await?;
+        let doc_type = DocumentType::new(
+            "A",
+            "契約書",
+            "/docs/contracts/",
+            PathGenerationRule::example_agi(),
+        );
+        document_type::create_document_type(pool, &doc_type).await?;
+        Ok(())
+    }
+
+    fn clock() -> anyhow::Result<GenerationClock> {
+        chrono_tz::Asia::Tokyo
+            .with_ymd_and_hms(2025, 10, 15, 9, 0, 0)
+            .single()
+            .map(GenerationClock::fixed_local)
+            .ok_or_else(|| anyhow::anyhow!("invalid datetime"))
+    }
+
+    async fn create_document(pool: &SqlitePool, clock: &GenerationClock) -> anyhow::Result<String> {
+        let request = AutoDocumentRequest {
+            type_code: TypeCode::new("A"),
+            dept_code: DeptCode::new('G'),
+            section_code: SectionCode::new('I'),
+            user_id: UserId::new("user001"),
+            file_path: StoredPath::new("/docs/contracts/a.pdf"),
+            business_task: None,
+            extension: None,
+        };
+        Ok(document_service::create_document_auto(pool, clock, request)
+            .await?
+            .document
+            .document_number)
+    }
+
+    #[tokio::test]
+    async fn test_seed_counter_continues_ledger() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = clock()?;
+
+        let seed = seed_counter(&pool, &clock, "A_G_I_2025_10", 43).await?;
+        assert_eq!(seed.previous_value, None);
+        assert_eq!(seed.highest_issued, None);
+        assert_eq!(create_document(&pool, &clock).await?, "AGI2510043");
+
+        // Ledger numbers are not reported as missing
+        let report = audit_service::audit_counters(&pool, &clock, None).await?;
+        assert!(report.scopes[0].is_clean());
+        assert_eq!(report.scopes[0].seeded_value, 42);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seed_counter_refuses_to_go_below_issued_numbers() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = clock()?;
+
+        for _ in 0..3 {
+            create_document(&pool, &clock).await?;
+        }
+
+        let result = seed_counter(&pool, &clock, "A_G_I_2025_10", 3).await;
+        assert!(matches!(result, Err(Error::Validation(_))));
+        assert_eq!(
+            counter::get_current_counter(&pool, "A_G_I_2025_10").await?,
+            Some(3)
+        );
+
+        // Moving forward is allowed
+        let seed = seed_counter(&pool, &clock, "A_G_I_2025_10", 100).await?;
+        assert_eq!(seed.previous_value, Some(3));
+        assert_eq!(seed.highest_issued, Some(3));
+        assert_eq!(create_document(&pool, &clock).await?, "AGI2510100");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seed_counter_without_counter_row_checks_manual_numbers() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = clock()?;
+
+        // Ledger numbers imported by hand before the cutover; no counter yet
+        let manual = document_service::ManualDocumentRequest {
+            document_number: "AGI2510005".to_string(),
+            type_code: TypeCode::new("A"),
+            dept_code: DeptCode::new('G'),
+            section_code: SectionCode::new('I'),
+            user_id: UserId::new("user001"),
+            file_path: StoredPath::new("/docs/contracts/imported.pdf"),
+            business_task: None,
+        };
+        document_service::create_document_manual(&pool, &clock, manual).await?;
+        assert_eq!(
+            counter::get_current_counter(&pool, "A_G_I_2025_10").await?,
+            None
+        );
+
+        let result = seed_counter(&pool, &clock, "A_G_I_2025_10", 3).await;
+        assert!(matches!(result, Err(Error::Validation(_))));
+        assert_eq!(
+            counter::get_current_counter(&pool, "A_G_I_2025_10").await?,
+            None
+        );
+
+        let seed = seed_counter(&pool, &clock, "A_G_I_2025_10", 6).await?;
+        assert_eq!(seed.highest_issued, Some(5));
+        assert_eq!(create_document(&pool, &clock).await?, "AGI2510006");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seed_counter_rejects_unknown_scope_and_overflow() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = clock()?;
+
+        // Typo in the month, wrong dimension count, unknown type
+        for scope_key in ["A_G_I_2025_1", "A_G_I_2025", "Z_G_I_2025_10"] {
+            let result = seed_counter(&pool, &clock, scope_key, 43).await;
+            assert!(matches!(result, Err(Error::Validation(_))), "{}", scope_key);
+            assert_eq!(counter::get_current_counter(&pool, scope_key).await?, None);
+        }
+
+        // Three digits cannot continue past 999
+        let result = seed_counter(&pool, &clock, "A_G_I_2025_10", 1000).await;
+        assert!(matches!(result, Err(Error::Validation(_))));
+        let seed = seed_counter(&pool, &clock, "A_G_I_2025_10", 999).await?;
+        assert_eq!(seed.next_value, 999);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rule_counter_start() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = clock()?;
+
+        let rule = PathGenerationRule::example_agi().with_counter_start(43);
+        let doc_type = DocumentType::new("B", "請求書", "/docs/invoices/", rule);
+        document_type::create_document_type(&pool, &doc_type).await?;
+
+        let request = AutoDocumentRequest {
+            type_code: TypeCode::new("B"),
+            dept_code: DeptCode::new('G'),
+            section_code: SectionCode::new('I'),
+            user_id: UserId::new("user001"),
+            file_path: StoredPath::new("/docs/invoices/a.pdf"),
+            business_task: None,
+            extension: None,
+        };
+        let doc = document_service::create_document_auto(&pool, &clock, request)
+            .await?
+            .document;
+        assert_eq!(doc.document_number, "BGI2510043");
+
+        let report = audit_service::audit_counters(&pool, &clock, Some("B_G_I_2025_10")).await?;
+        assert!(report.scopes[0].missing.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_counters_after_restore() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = clock()?;
+
+        for _ in 0..3 {
+            create_document(&pool, &clock).await?;
+        }
+        // Restored from a backup taken after the first document
+        counter::reset_counter(&pool, "A_G_I_2025_10", 1).await?;
+
+        let report = reconcile_counters(&pool, &clock, false).await?;
+        assert!(!report.applied);
+        assert_eq!(
+            report.repairs,
+            vec![CounterRepair {
+                scope_key: "A_G_I_2025_10".to_string(),
+                current_value: Some(1),
+                highest_in_use: 3,
+            }]
+        );
+        assert_eq!(
+            counter::get_current_counter(&pool, "A_G_I_2025_10").await?,
+            Some(1)
+        );
+
+        let report = reconcile_counters(&pool, &clock, true).await?;
+        assert!(report.applied);
+        assert_eq!(report.repairs.len(), 1);
+        assert_eq!(create_document(&pool, &clock).await?, "AGI2510004");
+
+        // Nothing left to repair
+        assert!(
+            reconcile_counters(&pool, &clock, true)
+                .await?
+                .repairs
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_counters_recreates_lost_counter() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = clock()?;
+
+        create_document(&pool, &clock).await?;
+        let manual = document_service::ManualDocumentRequest {
+            document_number: "AGI2510007".to_string(),
+            type_code: TypeCode::new("A"),
+            dept_code: DeptCode::new('G'),
+            section_code: SectionCode::new('I'),
+            user_id: UserId::new("user001"),
+            file_path: StoredPath::new("/docs/contracts/imported.pdf"),
+            business_task: None,
+        };
+        document_service::create_document_manual(&pool, &clock, manual).await?;
+        counter::delete_counter(&pool, "A_G_I_2025_10").await?;
+
+        let report = reconcile_counters(&pool, &clock, true).await?;
+        assert_eq!(report.repairs[0].current_value, None);
+        assert_eq!(report.repairs[0].highest_in_use, 7);
+        assert_eq!(create_document(&pool, &clock).await?, "AGI2510008");
+        Ok(())
+    }
+}
@@ -1,12 +1,30 @@
 //! Document creation and management service
 
-use crate::error::Result;
-use crate::models::{DeptCode, DocumentId, DocumentPath, SectionCode, TaskId, TypeCode, UserId};
-use crate::services::generation_service;
-use crate::storage::{document_path, document_type};
-use chrono::Utc;
+use crate::error::{Error, Result};
+use crate::models::{
+    CheckDigitAlgorithm, DeptCode, DocumentId, DocumentPath, ManualNumberMode, PathGenerationRule,
+    PathValues, ScopeDimension, SectionCode, StoredPath, TaskId, TypeCode, UserId,
+};
+use crate::services::clock::GenerationClock;
+use crate::services::generation_service::{self, ParsedNumber, ScopeOwner};
+use crate::services::{path_service, rule_service};
+use crate::storage::{counter, db, document_path, document_type, reservation};
+use crate::validation;
+use chrono::NaiveDate;
 use sqlx::SqlitePool;
-use std::path::PathBuf;
+
+/// Request parameters for auto-generated document creation
+pub struct AutoDocumentRequest {
+    pub type_code: TypeCode,
+    pub dept_code: DeptCode,
+    pub section_code: SectionCode,
+    pub user_id: UserId,
+    /// File path used when the document type has no path template
+    pub file_path: StoredPath,
+    pub business_task: Option<TaskId>,
+    /// File extension for the type's path template
+    pub extension: Option<String>,
+}
 
 /// Request parameters for manual document creation
 pub struct ManualDocumentRequest {
@@ -15,26 +33,58 @@ pub struct ManualDocumentRequest {
     pub dept_code: DeptCode,
     pub section_code: SectionCode,
     pub user_id: UserId,
-    pub file_path: PathBuf,
+    pub file_path: StoredPath,
     pub business_task: Option<TaskId>,
 }
 
-/// Create a document with auto-generated number
+/// Document written, with the warnings raised by its type's manual number,
+/// path containment and path uniqueness policies in warn mode
+#[derive(Debug, Clone)]
+pub struct SavedDocument {
+    pub document: DocumentPath,
+    pub warnings: Vec<String>,
+}
+
+/// Create a document with auto-generated number.
+/// Date components are taken from `clock` in the document type's timezone
+/// (falling back to the deployment business timezone).
+/// If the type has a path template, the file path is rendered from it once
+/// the number is issued. The type's path containment and uniqueness
+/// policies are enforced in strict/reject mode; warn mode returns warnings.
 pub async fn create_document_auto(
     pool: &SqlitePool,
-    type_code: TypeCode,
-    dept_code: DeptCode,
-    section_code: SectionCode,
-    user_id: UserId,
-    file_path: PathBuf,
-    business_task: Option<TaskId>,
-) -> Result<DocumentPath> {
-    // Validate file path is absolute
-    if !file_path.is_absolute() {
-        return Err(crate::error::Error::Validation(
-            "File path must be absolute".to_string(),
-        ));
-    }
+    clock: &GenerationClock,
+    request: AutoDocumentRequest,
+) -> Result<SavedDocument> {
+    create_auto(pool, clock, request, true).await
+}
+
+/// Create a document with auto-generated number for a file that already
+/// exists (e.g. one picked up by the filesystem watcher). The file path is
+/// kept as given even if the type has a path template.
+pub async fn register_existing_file(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    request: AutoDocumentRequest,
+) -> Result<SavedDocument> {
+    create_auto(pool, clock, request, false).await
+}
+
+async fn create_auto(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    request: AutoDocumentRequest,
+    use_template: bool,
+) -> Result<SavedDocument> {
+    let AutoDocumentRequest {
+        type_code,
+        dept_code,
+        section_code,
+        user_id,
+        file_path,
+        business_task,
+        extension,
+    } = request;
 
     // Get document type and its generation rule
     let doc_type = document_type::get_document_type(pool, &type_code)
@@ -43,6 +93,12 @@ pub async fn create_document_auto(
             crate::error::Error::NotFound(format!("Document type '{}' not found", type_code.0))
         })?;
 
+    // Validate file path is absolute (a templated path is checked when rendered)
+    let path_template = doc_type.path_template.as_ref().filter(|_| use_template);
+    if path_template.is_none() {
+        validation::validate_absolute_path(&file_path)?;
+    }
+
     // Check if document type is active
     if !doc_type.active {
         return Err(crate::error::Error::Validation(format!(
@@ -51,21 +107,15 @@ pub async fn create_document_auto(
         )));
     }
 
-    // Generate document number
-    let document_number = generation_service::generate_document_number(
-        pool,
-        &doc_type.generation_rule,
-        &type_code,
-        &dept_code,
-        &section_code,
-    )
-    .await?;
+    // Rule version in effect on the business date
+    let date = clock.today(doc_type.timezone);
+    let rule = rule_service::effective_rule(pool, &doc_type, date).await?;
 
-    // Create document path
-    let now = Utc::now();
-    let doc = DocumentPath {
+    // Create document path (number assigned below)
+    let now = clock.now();
+    let draft = DocumentPath {
         id: DocumentId::new(uuid::Uuid::new_v4().to_string()),
-        document_number: document_number.clone(),
+        document_number: String::new(),
         document_type: type_code,
         department: dept_code,
         section: section_code,
@@ -76,25 +126,77 @@ pub async fn create_document_auto(
         updated_at: now,
         generated: true,
         deleted: false,
+        generation_rule_id: rule.id,
     };
 
-    // Save to database
-    document_path::create_document_path(pool, &doc).await?;
+    // Generate the number and save in one transaction, so that a failed
+    // insert rolls the counter back instead of leaving a gap
+    db::retry_on_busy(|| async {
+        let mut tx = db::begin_write(pool).await?;
+        let mut doc = draft.clone();
+        loop {
+            doc.document_number = generation_service::issue_document_number(
+                &mut tx,
+                &rule,
+                &doc.document_type,
+                &doc.department,
+                &doc.section,
+                ScopeOwner::new(&doc.user, doc.business_task.as_ref()),
+                date,
+            )
+            .await?
+            .document_number;
 
-    Ok(doc)
+            // Skip numbers already taken by manually created documents
+            if !document_path::document_number_exists(&mut *tx, &doc.document_number).await? {
+                break;
+            }
+        }
+        if let Some(template) = path_template {
+            doc.file_path = template.render(&PathValues {
+                root: doc_type.root_directory.as_str(),
+                number: &doc.document_number,
+                extension: extension.as_deref(),
+                document_type: &doc.document_type,
+                department: &doc.department,
+                section: &doc.section,
+                date,
+                user: &doc.user,
+                task: doc.business_task.as_ref(),
+            })?;
+        }
+        let mut warnings = path_service::check_containment(&doc_type, &doc.file_path)?;
+        warnings.extend(
+            path_service::check_path_uniqueness(
+                &mut *tx,
+                doc_type.path_uniqueness,
+                &doc.file_path,
+                None,
+            )
+            .await?,
+        );
+        document_path::create_document_path(&mut *tx, &doc).await?;
+        tx.commit().await?;
+        Ok(SavedDocument {
+            document: doc,
+            warnings,
+        })
+    })
+    .await
 }
 
-/// Create a document with manual number
+/// Create a document with manual number.
+/// In strict mode (see `ManualNumberPolicy`) the number must match the type's
+/// rule, and a number on today's auto-increment sequence of the scope it
+/// belongs to is rejected or accepted with a warning (optionally advancing the
+/// counter past it).
 pub async fn create_document_manual(
     pool: &SqlitePool,
+    clock: &GenerationClock,
     request: ManualDocumentRequest,
-) -> Result<DocumentPath> {
+) -> Result<SavedDocument> {
     // Validate file path is absolute
-    if !request.file_path.is_absolute() {
-        return Err(crate::error::Error::Validation(
-            "File path must be absolute".to_string(),
-        ));
-    }
+    validation::validate_absolute_path(&request.file_path)?;
 
     // Validate document number is not empty
     if request.document_number.trim().is_empty() {
@@ -121,18 +223,55 @@ pub async fn create_document_manual(
         )));
     }
 
-    // Check if document number already exists
-    if let Some(_existing) =
-        document_path::get_document_path_by_number(pool, &request.document_number).await?
+    // Numbers protected by a check digit must carry the right one
+    let today = clock.today(doc_type.timezone);
+    let rule = rule_service::effective_rule(pool, &doc_type, today).await?;
+    if let Some(algorithm) = rule.check_digit()
+        && !algorithm.is_valid(&request.document_number)
     {
-        return Err(crate::error::Error::Validation(format!(
-            "Document number '{}' already exists",
-            request.document_number
-        )));
+        return Err(Error::InvalidCheckDigit {
+            suggestions: algorithm.corrections(&request.document_number),
+            number: request.document_number,
+        });
+    }
+
+    // The path must lie under the type's root directory (containment policy)
+    let mut warnings = path_service::check_containment(&doc_type, &request.file_path)?;
+
+    // Strict mode: the number must fit the rule and stay off the live sequence
+    let policy = doc_type.manual_numbers;
+    let mut bump = None;
+    if policy.mode != ManualNumberMode::Off {
+        let Some(parsed) = generation_service::parse_document_number(
+            &rule,
+            &doc_type.code,
+            &request.document_number,
+        ) else {
+            return Err(Error::Validation(format!(
+                "Document number '{}' does not match the rule {} of document type '{}'",
+                request.document_number,
+                rule.to_pattern(),
+                doc_type.code.0
+            )));
+        };
+
+        if let Some((scope_key, value)) = live_sequence_position(&rule, &request, &parsed, today)? {
+            let message = format!(
+                "Document number '{}' falls on the auto-increment sequence of scope '{}' (counter value {})",
+                request.document_number, scope_key, value
+            );
+            if policy.mode == ManualNumberMode::Reject {
+                return Err(Error::Validation(message));
+            }
+            warnings.push(message);
+            if policy.bump_counter {
+                bump = Some((scope_key, value));
+            }
+        }
     }
 
     // Create document path
-    let now = Utc::now();
+    let now = clock.now();
     let doc = DocumentPath {
         id: DocumentId::new(uuid::Uuid::new_v4().to_string()),
         document_number: request.document_number,
@@ -146,26 +285,94 @@ pub async fn create_document_manual(
         updated_at: now,
         generated: false,
         deleted: false,
+        generation_rule_id: None,
     };
 
-    // Save to database
-    document_path::create_document_path(pool, &doc).await?;
+    // Save to database, checking the number and path and advancing the
+    // counter in the same transaction, so that a concurrent reservation
+    // cannot draw the number in between
+    let (path_warnings, bumped) = db::retry_on_busy(|| async {
+        let mut tx = db::begin_write(pool).await?;
+        if document_path::document_number_exists(&mut *tx, &doc.document_number).await? {
+            return Err(Error::DuplicateDocumentNumber(doc.document_number.clone()));
+        }
+        if reservation::get_reservation_by_number(&mut *tx, &doc.document_number)
+            .await?
+            .is_some()
+        {
+            return Err(Error::ReservedDocumentNumber(doc.document_number.clone()));
+        }
+        let path_warnings = path_service::check_path_uniqueness(
+            &mut *tx,
+            doc_type.path_uniqueness,
+            &doc.file_path,
+            None,
+        )
+        .await?;
+        document_path::create_document_path(&mut *tx, &doc).await?;
+        let bumped = match &bump {
+            Some((scope_key, value)) => counter::raise_counter(&mut *tx, scope_key, *value).await?,
+            None => false,
+        };
+        tx.commit().await?;
+        Ok((path_warnings, bumped))
+    })
+    .await?;
+    warnings.extend(path_warnings);
+    if let (true, Some((scope_key, value))) = (bumped, &bump) {
+        warnings.push(format!(
+            "Counter of scope '{}' advanced to {}",
+            scope_key, value
+        ));
+    }
 
-    Ok(doc)
+    Ok(SavedDocument {
+        document: doc,
+        warnings,
+    })
 }
 
-/// Update document path (file location)
+/// Scope key and counter value of a manual number that falls on the
+/// auto-increment sequence of `date`. The scope is the one the number was
+/// read as belonging to: its department and section, or the creator's
+/// where the rule does not show them.
+fn live_sequence_position(
+    rule: &PathGenerationRule,
+    request: &ManualDocumentRequest,
+    parsed: &ParsedNumber,
+    date: NaiveDate,
+) -> Result<Option<(String, i64)>> {
+    if request.business_task.is_none() && rule.counter_scope.uses(ScopeDimension::Task) {
+        return Ok(None);
+    }
+    let Some(value) = parsed.counter_value else {
+        return Ok(None);
+    };
+    // Numbers dated otherwise belong to a past (or future) sequence
+    if !parsed.is_dated(rule, date)? {
+        return Ok(None);
+    }
+    let scope_key = generation_service::build_scope_key(
+        rule,
+        &parsed.document_type,
+        &parsed.department.unwrap_or(request.dept_code),
+        &parsed.section.unwrap_or(request.section_code),
+        ScopeOwner::new(&request.user_id, request.business_task.as_ref()),
+        date,
+    )?;
+    Ok(Some((scope_key, value)))
+}
+
+/// Update document path (file location), applying the type's path
+/// containment and uniqueness policies
 pub async fn update_document_path(
     pool: &SqlitePool,
+    clock: &GenerationClock,
     id: &DocumentId,
-    new_file_path: PathBuf,
-) -> Result<DocumentPath> {
+    new_file_path: StoredPath,
+) -> Result<SavedDocument> {
     // Validate file path is absolute
-    if !new_file_path.is_absolute() {
-        return Err(crate::error::Error::Validation(
-            "File path must be absolute".to_string(),
-        ));
-    }
+    validation::validate_absolute_path(&new_file_path)?;
 
     // Get existing document
     let mut doc = document_path::get_document_path(pool, id)
@@ -179,14 +386,42 @@ pub async fn update_document_path(
         ));
     }
 
+    let doc_type = document_type::get_document_type(pool, &doc.document_type).await?;
+    let mut warnings = match &doc_type {
+        Some(doc_type) => path_service::check_containment(doc_type, &new_file_path)?,
+        None => Vec::new(),
+    };
+    let policy = doc_type
+        .map(|doc_type| doc_type.path_uniqueness)
+        .unwrap_or_default();
+
     // Update path and timestamp
     doc.file_path = new_file_path.clone();
-    doc.updated_at = Utc::now();
+    doc.updated_at = clock.now();
 
     // Save to database
-    document_path::update_document_path(pool, &doc.id, new_file_path).await?;
+    let path_warnings = db::retry_on_busy(|| async {
+        let mut tx = db::begin_write(pool).await?;
+        let warnings =
+            path_service::check_path_uniqueness(&mut *tx, policy, &new_file_path, Some(&doc.id))
+                .await?;
+        document_path::update_document_path(
+            &mut *tx,
+            &doc.id,
+            new_file_path.clone(),
+            doc.updated_at,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(warnings)
+    })
+    .await?;
+    warnings.extend(path_warnings);
 
-    Ok(doc)
+    Ok(SavedDocument {
+        document: doc,
+        warnings,
+    })
 }
 
 /// Logically delete a document
@@ -222,15 +457,78 @@ pub async fn get_document_by_number(
     pool: &SqlitePool,
     document_number: &str,
 ) -> Result<Option<DocumentPath>> {
-    document_path::get_document_path_by_number(pool, document_number).await
+    if let Some(doc) = document_path::get_document_path_by_number(pool, document_number).await? {
+        return Ok(Some(doc));
+    }
+
+    // Not found: tell a mistyped number apart from an unknown one
+    let algorithms = check_digit_algorithms(pool, document_number).await?;
+    if algorithms.is_empty() || algorithms.iter().any(|a| a.is_valid(document_number)) {
+        return Ok(None);
+    }
+
+    let mut suggestions: Vec<String> = Vec::new();
+    for algorithm in &algorithms {
+        for candidate in algorithm.corrections(document_number) {
+            if !suggestions.contains(&candidate)
+                && document_path::document_number_exists(pool, &candidate).await?
+            {
+                suggestions.push(candidate);
+            }
+        }
+    }
+    Err(Error::InvalidCheckDigit {
+        number: document_number.to_string(),
+        suggestions,
+    })
+}
+
+/// Check digit algorithms of the rule versions that could have produced
+/// `document_number`: the number, or one of its corrections, reads as a
+/// number of the rule
+async fn check_digit_algorithms(
+    pool: &SqlitePool,
+    document_number: &str,
+) -> Result<Vec<CheckDigitAlgorithm>> {
+    let mut algorithms = Vec::new();
+    for doc_type in document_type::list_document_types(pool).await? {
+        let versions = document_type::list_rule_versions(pool, &doc_type.code).await?;
+        let rules = versions
+            .iter()
+            .map(|version| &version.rule)
+            .chain(std::iter::once(&doc_type.generation_rule));
+        for rule in rules {
+            let Some(algorithm) = rule.check_digit() else {
+                continue;
+            };
+            if algorithms.contains(&algorithm) {
+                continue;
+            }
+            let fits = |number: &str| {
+                generation_service::parse_document_number(rule, &doc_type.code, number).is_some()
+            };
+            if fits(document_number)
+                || algorithm
+                    .corrections(document_number)
+                    .iter()
+                    .any(|c| fits(c))
+            {
+                algorithms.push(algorithm);
+            }
+        }
+    }
+    Ok(algorithms)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{Department, DocumentType, PathGenerationRule, Section, User};
+    use crate::models::{
+        Department, DocumentType, ManualNumberPolicy, PathGenerationRule, Section, User,
+    };
     use crate::storage::db::init_db_pool;
     use crate::storage::{department, section, user};
+    use chrono::TimeZone;
 
     async fn setup_test_data(pool: &SqlitePool) -> anyhow::Result<()> {
         // Create department
@@ -256,6 +554,51 @@ mod tests {
         Ok(())
     }
 
+    fn auto_request(file_path: &str) -> AutoDocumentRequest {
+        AutoDocumentRequest {
+            type_code: TypeCode::new("A"),
+            dept_code: DeptCode::new('G'),
+            section_code: SectionCode::new('I'),
+            user_id: UserId::new("user001"),
+            file_path: StoredPath::new(file_path),
+            business_task: None,
+            extension: None,
+        }
+    }
+
+    fn manual_request(document_number: &str) -> ManualDocumentRequest {
+        ManualDocumentRequest {
+            document_number: document_number.to_string(),
+            type_code: TypeCode::new("A"),
+            dept_code: DeptCode::new('G'),
+            section_code: SectionCode::new('I'),
+            user_id: UserId::new("user001"),
+            file_path: StoredPath::new("/docs/contracts/manual.pdf"),
+            business_task: None,
+        }
+    }
+
+    async fn set_manual_numbers(
+        pool: &SqlitePool,
+        mode: ManualNumberMode,
+        bump_counter: bool,
+    ) -> anyhow::Result<()> {
+        let doc_type = document_type::get_document_type(pool, &TypeCode::new("A"))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("document type missing"))?
+            .with_manual_numbers(ManualNumberPolicy { mode, bump_counter });
+        document_type::update_document_type(pool, &doc_type).await?;
+        Ok(())
+    }
+
+    fn jst_clock(y: i32, m: u32, d: u32, h: u32) -> anyhow::Result<GenerationClock> {
+        chrono_tz::Asia::Tokyo
+            .with_ymd_and_hms(y, m, d, h, 0, 0)
+            .single()
+            .map(GenerationClock::fixed_local)
+            .ok_or_else(|| anyhow::anyhow!("invalid datetime"))
+    }
+
     #[tokio::test]
     async fn test_create_document_auto() -> anyhow::Result<()> {
         let pool = init_db_pool("sqlite::memory:").await?;
@@ -263,14 +606,11 @@ mod tests {
 
         let doc = create_document_auto(
             &pool,
-            TypeCode::new("A"),
-            DeptCode::new('G'),
-            SectionCode::new('I'),
-            UserId::new("user001"),
-            PathBuf::from("/docs/contracts/test.pdf"),
-            None,
+            &GenerationClock::default(),
+            auto_request("/docs/contracts/test.pdf"),
         )
-        .await?;
+        .await?
+        .document;
 
         assert!(doc.generated);
         assert!(!doc.document_number.is_empty());
@@ -285,17 +625,19 @@ mod tests {
 
         let doc = create_document_manual(
             &pool,
+            &jst_clock(2025, 10, 1, 9)?,
             ManualDocumentRequest {
                 document_number: "MANUAL-001".to_string(),
                 type_code: TypeCode::new("A"),
                 dept_code: DeptCode::new('G'),
                 section_code: SectionCode::new('I'),
                 user_id: UserId::new("user001"),
-                file_path: PathBuf::from("/docs/contracts/manual.pdf"),
+                file_path: StoredPath::new("/docs/contracts/manual.pdf"),
                 business_task: None,
             },
         )
-        .await?;
+        .await?
+        .document;
 
         assert!(!doc.generated);
         assert_eq!(doc.document_number, "MANUAL-001");
@@ -309,20 +651,64 @@ mod tests {
 
         let doc = create_document_auto(
             &pool,
-            TypeCode::new("A"),
-            DeptCode::new('G'),
-            SectionCode::new('I'),
-            UserId::new("user001"),
-            PathBuf::from("/docs/contracts/old.pdf"),
-            None,
+            &jst_clock(2025, 10, 1, 9)?,
+            auto_request("/docs/contracts/old.pdf"),
         )
-        .await?;
+        .await?
+        .document;
+
+        let clock = jst_clock(2025, 10, 2, 9)?;
+        let updated = update_document_path(
+            &pool,
+            &clock,
+            &doc.id,
+            StoredPath::new("/docs/contracts/new.pdf"),
+        )
+        .await?
+        .document;
+
+        assert_eq!(
+            updated.file_path,
+            StoredPath::new("/docs/contracts/new.pdf")
+        );
+        assert_eq!(updated.updated_at, clock.now());
+        let stored = get_document_by_id(&pool, &doc.id).await?;
+        assert_eq!(stored.map(|d| d.updated_at), Some(clock.now()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_windows_paths_on_any_host() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = GenerationClock::default();
+
+        let doc = create_document_auto(&pool, &clock, auto_request("c:/Users/docs/a.pdf"))
+            .await?
+            .document;
+        let stored = document_path::get_document_path(&pool, &doc.id).await?;
+        assert_eq!(
+            stored.map(|d| d.file_path.to_string()),
+            Some("C:\\Users\\docs\\a.pdf".to_string())
+        );
 
-        let updated =
-            update_document_path(&pool, &doc.id, PathBuf::from("/docs/contracts/new.pdf")).await?;
+        let updated = update_document_path(
+            &pool,
+            &clock,
+            &doc.id,
+            StoredPath::new("\\\\server\\share\\docs\\a.pdf"),
+        )
+        .await?
+        .document;
+        assert_eq!(updated.file_path.flavor(), crate::models::PathFlavor::Unc);
 
-        assert_eq!(updated.file_path, PathBuf::from("/docs/contracts/new.pdf"));
-        assert!(updated.updated_at > doc.created_at);
+        let relative =
+            update_document_path(&pool, &clock, &doc.id, StoredPath::new("docs\\a.pdf")).await;
+        assert!(matches!(relative, Err(Error::RelativePathNotAllowed)));
+        let reserved =
+            update_document_path(&pool, &clock, &doc.id, StoredPath::new("D:\\docs\\a|b.pdf"))
+                .await;
+        assert!(matches!(reserved, Err(Error::InvalidPath(_))));
         Ok(())
     }
 
@@ -333,14 +719,11 @@ mod tests {
 
         let doc = create_document_auto(
             &pool,
-            TypeCode::new("A"),
-            DeptCode::new('G'),
-            SectionCode::new('I'),
-            UserId::new("user001"),
-            PathBuf::from("/docs/contracts/test.pdf"),
-            None,
+            &GenerationClock::default(),
+            auto_request("/docs/contracts/test.pdf"),
         )
-        .await?;
+        .await?
+        .document;
 
         delete_document(&pool, &doc.id).await?;
 
@@ -353,4 +736,472 @@ mod tests {
         assert!(deleted.deleted);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_create_document_auto_uses_business_date() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+
+        // 2025-10-01 08:00 JST is still 2025-09-30 in UTC
+        let clock = jst_clock(2025, 10, 1, 8)?;
+        let doc = create_document_auto(&pool, &clock, auto_request("/docs/contracts/a.pdf"))
+            .await?
+            .document;
+        assert_eq!(doc.document_number, "AGI2510001");
+        assert_eq!(doc.created_at, clock.now());
+
+        // Last moment of the previous month stays in the September scope
+        let clock = jst_clock(2025, 9, 30, 23)?;
+        let doc = create_document_auto(&pool, &clock, auto_request("/docs/contracts/b.pdf"))
+            .await?
+            .document;
+        assert_eq!(doc.document_number, "AGI2509001");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_document_auto_type_timezone_override() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+
+        let utc_type = DocumentType::new(
+            "U",
+            "UTC文書",
+            "/docs/utc/",
+            PathGenerationRule::example_agi(),
+        )
+        .with_timezone(chrono_tz::UTC);
+        document_type::create_document_type(&pool, &utc_type).await?;
+
+        let clock = jst_clock(2026, 1, 1, 3)?;
+        let request = AutoDocumentRequest {
+            type_code: TypeCode::new("U"),
+            ..auto_request("/docs/utc/a.pdf")
+        };
+        let doc = create_document_auto(&pool, &clock, request).await?.document;
+        assert_eq!(doc.document_number, "UGI2512001");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_document_auto_renders_path_template() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = jst_clock(2025, 10, 1, 9)?;
+
+        let templated = DocumentType::new(
+            "T",
+            "テンプレート文書",
+            "/docs/templated/",
+            PathGenerationRule::example_agi(),
+        )
+        .with_path_template("{root}/{yyyy}/{dept}{section}/{number}.{ext}".parse()?);
+        document_type::create_document_type(&pool, &templated).await?;
+
+        // Without the extension nothing is created and no number is used up
+        let request = AutoDocumentRequest {
+            type_code: TypeCode::new("T"),
+            ..auto_request("/ignored")
+        };
+        assert!(matches!(
+            create_document_auto(&pool, &clock, request).await,
+            Err(Error::Validation(_))
+        ));
+
+        let request = AutoDocumentRequest {
+            type_code: TypeCode::new("T"),
+            extension: Some(".pdf".to_string()),
+            ..auto_request("/ignored")
+        };
+        let doc = create_document_auto(&pool, &clock, request).await?.document;
+        assert_eq!(doc.document_number, "TGI2510001");
+        assert_eq!(
+            doc.file_path,
+            StoredPath::new("/docs/templated/2025/GI/TGI2510001.pdf")
+        );
+
+        let stored = document_path::get_document_path_by_number(&pool, "TGI2510001").await?;
+        assert_eq!(stored.map(|d| d.file_path), Some(doc.file_path));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_insert_does_not_burn_number() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = jst_clock(2025, 10, 1, 9)?;
+
+        // Unknown user violates the foreign key: the insert fails after the
+        // counter was advanced in the same transaction
+        let mut request = auto_request("/docs/contracts/test.pdf");
+        request.user_id = UserId::new("ghost");
+        assert!(create_document_auto(&pool, &clock, request).await.is_err());
+        assert_eq!(
+            crate::storage::counter::get_current_counter(&pool, "A_G_I_2025_10").await?,
+            None
+        );
+
+        let doc = create_document_auto(&pool, &clock, auto_request("/docs/contracts/test.pdf"))
+            .await?
+            .document;
+        assert_eq!(doc.document_number, "AGI2510001");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_document_auto_skips_manual_numbers() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = jst_clock(2025, 10, 1, 9)?;
+
+        create_document_manual(
+            &pool,
+            &clock,
+            ManualDocumentRequest {
+                document_number: "AGI2510001".to_string(),
+                type_code: TypeCode::new("A"),
+                dept_code: DeptCode::new('G'),
+                section_code: SectionCode::new('I'),
+                user_id: UserId::new("user001"),
+                file_path: StoredPath::new("/docs/contracts/legacy.pdf"),
+                business_task: None,
+            },
+        )
+        .await?;
+
+        let doc = create_document_auto(&pool, &clock, auto_request("/docs/contracts/test.pdf"))
+            .await?
+            .document;
+        assert_eq!(doc.document_number, "AGI2510002");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_auto_creation_is_gap_free_and_unique() -> anyhow::Result<()> {
+        const WRITERS: usize = 8;
+        const DOCUMENTS_PER_WRITER: usize = 25;
+
+        // File-backed database so that writers contend on real SQLite locks
+        let db_file = std::env::temp_dir().join(format!("stress-{}.db", uuid::Uuid::new_v4()));
+        let pool = init_db_pool(&format!("sqlite:{}", db_file.display())).await?;
+        setup_test_data(&pool).await?;
+        let clock = jst_clock(2025, 10, 1, 9)?;
+
+        let mut writers = Vec::new();
+        for _ in 0..WRITERS {
+            let pool = pool.clone();
+            let clock = clock.clone();
+            writers.push(tokio::spawn(async move {
+                let mut numbers = Vec::new();
+                for _ in 0..DOCUMENTS_PER_WRITER {
+                    let doc = create_document_auto(
+                        &pool,
+                        &clock,
+                        auto_request("/docs/contracts/stress.pdf"),
+                    )
+                    .await?
+                    .document;
+                    numbers.push(doc.document_number);
+                }
+                Ok::<_, crate::error::Error>(numbers)
+            }));
+        }
+
+        let mut numbers = Vec::new();
+        for writer in writers {
+            numbers.extend(writer.await??);
+        }
+        pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", db_file.display(), suffix));
+        }
+
+        numbers.sort();
+        let expected: Vec<String> = (1..=WRITERS * DOCUMENTS_PER_WRITER)
+            .map(|n| format!("AGI2510{:03}", n))
+            .collect();
+        assert_eq!(numbers, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_document_auto_uses_rule_version_in_effect() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let type_code = TypeCode::new("A");
+        let new_rule = PathGenerationRule::from_pattern(
+            "[TYPE]-[YYYY]-[NNNN]",
+            crate::models::CounterScope::TypeAndYear,
+        )?;
+        let version = rule_service::schedule_rule_change(
+            &pool,
+            &jst_clock(2025, 10, 15, 9)?,
+            &type_code,
+            new_rule,
+            crate::models::RuleChangeSchedule::NextMonth,
+        )
+        .await?;
+
+        let before = create_document_auto(
+            &pool,
+            &jst_clock(2025, 10, 31, 23)?,
+            auto_request("/docs/contracts/a.pdf"),
+        )
+        .await?
+        .document;
+        assert_eq!(before.document_number, "AGI2510001");
+        let base = document_type::get_document_type(&pool, &type_code).await?;
+        assert_eq!(
+            before.generation_rule_id,
+            base.and_then(|t| t.generation_rule.id)
+        );
+
+        let after = create_document_auto(
+            &pool,
+            &jst_clock(2025, 11, 1, 9)?,
+            auto_request("/docs/contracts/b.pdf"),
+        )
+        .await?
+        .document;
+        assert_eq!(after.document_number, "A-2025-0001");
+        assert_eq!(after.generation_rule_id, Some(version.id));
+
+        // The recorded rule version is persisted with the document
+        let stored = get_document_by_id(&pool, &after.id).await?;
+        assert_eq!(stored.and_then(|d| d.generation_rule_id), Some(version.id));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_digit_flags_mistyped_numbers() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let rule = PathGenerationRule::from_pattern(
+            "[TYPE][YYMM][NNN]-[CHK10]",
+            crate::models::CounterScope::TypeAndYear,
+        )?;
+        let doc_type = DocumentType::new("C", "検査書", "/docs/checked/", rule);
+        document_type::create_document_type(&pool, &doc_type).await?;
+
+        let clock = jst_clock(2025, 10, 1, 9)?;
+        let mut numbers = Vec::new();
+        for _ in 0..12 {
+            let request = AutoDocumentRequest {
+                type_code: TypeCode::new("C"),
+                ..auto_request("/docs/checked/a.pdf")
+            };
+            numbers.push(
+                create_document_auto(&pool, &clock, request)
+                    .await?
+                    .document
+                    .document_number,
+            );
+        }
+        let issued = numbers[11].clone();
+        assert!(issued.starts_with("C2510012-"));
+        assert!(CheckDigitAlgorithm::Luhn.is_valid(&issued));
+
+        // Swapped digits are caught and the issued number is suggested
+        let typo = issued.replacen("012", "021", 1);
+        match get_document_by_number(&pool, &typo).await {
+            Err(Error::InvalidCheckDigit { suggestions, .. }) => {
+                assert!(suggestions.contains(&issued));
+                assert!(suggestions.iter().all(|s| numbers.contains(s)));
+            }
+            other => anyhow::bail!("expected check digit error, got {:?}", other),
+        }
+
+        // A well-formed number that was never issued is simply not found
+        let unknown = CheckDigitAlgorithm::Luhn.append_check_char("C2510099-");
+        assert!(get_document_by_number(&pool, &unknown).await?.is_none());
+
+        // Manual numbers must carry the right check digit
+        let mut wrong = CheckDigitAlgorithm::Luhn.append_check_char("C2509001-");
+        let right = wrong.clone();
+        let last = wrong.pop().and_then(|c| c.to_digit(10)).unwrap_or(0);
+        wrong.push(char::from_digit((last + 1) % 10, 10).unwrap_or('0'));
+        let manual = |number: String| ManualDocumentRequest {
+            document_number: number,
+            type_code: TypeCode::new("C"),
+            dept_code: DeptCode::new('G'),
+            section_code: SectionCode::new('I'),
+            user_id: UserId::new("user001"),
+            file_path: StoredPath::new("/docs/checked/manual.pdf"),
+            business_task: None,
+        };
+        match create_document_manual(&pool, &clock, manual(wrong)).await {
+            Err(Error::InvalidCheckDigit { suggestions, .. }) => {
+                assert_eq!(suggestions.first(), Some(&right));
+            }
+            other => anyhow::bail!("expected check digit error, got {:?}", other),
+        }
+
+        // A typo in the body is not fixed by recomputing the check digit:
+        // the intended number is among the suggestions
+        let swapped = right.replacen("9001", "9010", 1);
+        match create_document_manual(&pool, &clock, manual(swapped)).await {
+            Err(Error::InvalidCheckDigit { suggestions, .. }) => {
+                assert!(suggestions.contains(&right));
+                assert!(
+                    suggestions
+                        .iter()
+                        .all(|s| CheckDigitAlgorithm::Luhn.is_valid(s))
+                );
+            }
+            other => anyhow::bail!("expected check digit error, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_digit_suggestions_with_leading_literal() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let rule = PathGenerationRule::from_pattern(
+            "JP-[TYPE]-[YYMM][NNN][CHK10]",
+            crate::models::CounterScope::TypeAndYear,
+        )?;
+        let doc_type = DocumentType::new("K", "工事書", "/docs/works/", rule);
+        document_type::create_document_type(&pool, &doc_type).await?;
+
+        let clock = jst_clock(2025, 10, 1, 9)?;
+        let mut issued = String::new();
+        for _ in 0..12 {
+            let request = AutoDocumentRequest {
+                type_code: TypeCode::new("K"),
+                ..auto_request("/docs/works/a.pdf")
+            };
+            issued = create_document_auto(&pool, &clock, request)
+                .await?
+                .document
+                .document_number;
+        }
+        assert!(issued.starts_with("JP-K-2510012"));
+
+        // The number does not start with the type code, but is still read
+        let typo = issued.replacen("012", "021", 1);
+        match get_document_by_number(&pool, &typo).await {
+            Err(Error::InvalidCheckDigit { suggestions, .. }) => {
+                assert!(suggestions.contains(&issued));
+            }
+            other => anyhow::bail!("expected check digit error, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_strict_manual_numbers_reject_live_sequence() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        set_manual_numbers(&pool, ManualNumberMode::Reject, false).await?;
+        let clock = jst_clock(2025, 10, 1, 9)?;
+
+        // Not in the rule's format
+        let result = create_document_manual(&pool, &clock, manual_request("MANUAL-001")).await;
+        assert!(matches!(result, Err(Error::Validation(_))));
+
+        // On this month's sequence
+        let result = create_document_manual(&pool, &clock, manual_request("AGI2510005")).await;
+        assert!(matches!(result, Err(Error::Validation(_))));
+
+        // Last month's numbers can still be entered by hand
+        let created = create_document_manual(&pool, &clock, manual_request("AGI2509005")).await?;
+        assert!(created.warnings.is_empty());
+        assert_eq!(
+            counter::get_current_counter(&pool, "A_G_I_2025_10").await?,
+            None
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_strict_manual_numbers_warn_and_bump_counter() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = jst_clock(2025, 10, 1, 9)?;
+
+        set_manual_numbers(&pool, ManualNumberMode::Warn, false).await?;
+        let created = create_document_manual(&pool, &clock, manual_request("AGI2510003")).await?;
+        assert_eq!(created.warnings.len(), 1);
+        assert_eq!(
+            counter::get_current_counter(&pool, "A_G_I_2025_10").await?,
+            None
+        );
+
+        set_manual_numbers(&pool, ManualNumberMode::Warn, true).await?;
+        let created = create_document_manual(&pool, &clock, manual_request("AGI2510005")).await?;
+        assert_eq!(created.warnings.len(), 2);
+        assert_eq!(
+            counter::get_current_counter(&pool, "A_G_I_2025_10").await?,
+            Some(5)
+        );
+
+        // Auto numbering continues past the manual number
+        let doc = create_document_auto(&pool, &clock, auto_request("/docs/contracts/a.pdf"))
+            .await?
+            .document;
+        assert_eq!(doc.document_number, "AGI2510006");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_strict_manual_numbers_use_scope_of_number() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = jst_clock(2025, 10, 1, 9)?;
+
+        // Entered by a G/I user, but numbered on the K/L sequence
+        set_manual_numbers(&pool, ManualNumberMode::Reject, false).await?;
+        let result = create_document_manual(&pool, &clock, manual_request("AKL2510004")).await;
+        match result {
+            Err(Error::Validation(message)) => assert!(message.contains("A_K_L_2025_10")),
+            other => anyhow::bail!("expected validation error, got {:?}", other),
+        }
+
+        set_manual_numbers(&pool, ManualNumberMode::Warn, true).await?;
+        let created = create_document_manual(&pool, &clock, manual_request("AKL2510004")).await?;
+        assert!(created.warnings[0].contains("A_K_L_2025_10"));
+        assert_eq!(
+            counter::get_current_counter(&pool, "A_K_L_2025_10").await?,
+            Some(4)
+        );
+        assert_eq!(
+            counter::get_current_counter(&pool, "A_G_I_2025_10").await?,
+            None
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_manual_numbers_unchecked_by_default() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = jst_clock(2025, 10, 1, 9)?;
+
+        let created = create_document_manual(&pool, &clock, manual_request("AGI2510005")).await?;
+        assert!(created.warnings.is_empty());
+        let created = create_document_manual(&pool, &clock, manual_request("MANUAL-001")).await?;
+        assert!(created.warnings.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_manual_number_is_conflict() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = jst_clock(2025, 10, 1, 9)?;
+
+        create_document_manual(&pool, &clock, manual_request("MANUAL-001")).await?;
+        let result = create_document_manual(&pool, &clock, manual_request("MANUAL-001")).await;
+        assert!(matches!(result, Err(Error::DuplicateDocumentNumber(_))));
+
+        // Numbers issued automatically are taken as well
+        let doc = create_document_auto(&pool, &clock, auto_request("/docs/contracts/a.pdf"))
+            .await?
+            .document;
+        let result =
+            create_document_manual(&pool, &clock, manual_request(&doc.document_number)).await;
+        assert!(matches!(result, Err(Error::DuplicateDocumentNumber(_))));
+        Ok(())
+    }
 }
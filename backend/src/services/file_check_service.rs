@@ -0,0 +1,292 @@
+//! Optional on-disk verification of document files
+//!
+//! The database only records paths; nothing stops a file from being moved
+//! or deleted afterwards. The verifier looks each live document's file up
+//! on the server's filesystem and records what it found in `file_checks`.
+//! Stored paths are translated with `PathMapping`s first (e.g. a UNC share
+//! mounted under `/mnt`); paths in the host's own format are checked as
+//! they are, anything else is reported as unmapped.
+
+use crate::error::Result;
+use crate::models::{
+    DocumentPath, FileCheck, FileStatus, PathFlavor, PathMapping, StoredPath, TypeCode,
+};
+use crate::services::clock::GenerationClock;
+use crate::storage::{document_path, file_check};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Resolves stored paths to local files and checks them
+#[derive(Debug, Clone, Default)]
+pub struct FileVerifier {
+    mappings: Vec<PathMapping>,
+}
+
+impl FileVerifier {
+    pub fn new(mappings: Vec<PathMapping>) -> Self {
+        Self { mappings }
+    }
+
+    /// Build from a `;`-separated list of `<prefix>=<local directory>`
+    /// mappings (the `FILE_PATH_MAPPINGS` setting)
+    pub fn from_mappings(spec: &str) -> Result<Self> {
+        let mappings = spec
+            .split(';')
+            .filter(|mapping| !mapping.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<PathMapping>>>()?;
+        Ok(Self::new(mappings))
+    }
+
+    /// Local path of a stored path; the longest matching prefix wins
+    pub fn local_path(&self, path: &StoredPath) -> Option<PathBuf> {
+        let mapped = self
+            .mappings
+            .iter()
+            .filter_map(|mapping| Some((mapping.prefix.as_str().len(), mapping.apply(path)?)))
+            .max_by_key(|(prefix_len, _)| *prefix_len)
+            .map(|(_, local)| local);
+        if mapped.is_some() {
+            return mapped;
+        }
+
+        let native = match path.flavor() {
+            PathFlavor::Posix => cfg!(unix),
+            PathFlavor::Drive | PathFlavor::Unc => cfg!(windows),
+            PathFlavor::Relative => false,
+        };
+        native.then(|| PathBuf::from(path.as_str()))
+    }
+
+    /// Check one document's file
+    pub async fn check(&self, document: &DocumentPath, now: DateTime<Utc>) -> FileCheck {
+        let mut check = FileCheck {
+            document_id: document.id.clone(),
+            file_path: document.file_path.clone(),
+            status: FileStatus::Unmapped,
+            size: None,
+            modified_at: None,
+            last_seen_at: None,
+            checked_at: now,
+            error: None,
+        };
+        let Some(local) = self.local_path(&document.file_path) else {
+            return check;
+        };
+
+        match tokio::fs::metadata(&local).await {
+            Ok(metadata) if metadata.is_file() => {
+                check.status = FileStatus::Present;
+                check.size = i64::try_from(metadata.len()).ok();
+                check.modified_at = metadata.modified().ok().map(DateTime::<Utc>::from);
+                check.last_seen_at = Some(now);
+            }
+            Ok(_) => {
+                check.status = FileStatus::Inaccessible;
+                check.error = Some(format!("{} is not a file", local.display()));
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::NotADirectory) => {
+                check.status = FileStatus::Missing;
+            }
+            Err(e) => {
+                check.status = FileStatus::Inaccessible;
+                check.error = Some(e.to_string());
+            }
+        }
+        check
+    }
+}
+
+/// Counts of one verification run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VerificationSummary {
+    pub checked: usize,
+    pub present: usize,
+    pub missing: usize,
+    pub inaccessible: usize,
+    pub unmapped: usize,
+}
+
+/// Check the files of all live documents and record the results
+pub async fn verify_files(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    verifier: &FileVerifier,
+) -> Result<VerificationSummary> {
+    let mut summary = VerificationSummary::default();
+    for document in document_path::list_document_paths(pool, false).await? {
+        let check = verifier.check(&document, clock.now()).await;
+        file_check::record_file_check(pool, &check).await?;
+
+        summary.checked += 1;
+        match check.status {
+            FileStatus::Present => summary.present += 1,
+            FileStatus::Missing => summary.missing += 1,
+            FileStatus::Inaccessible => summary.inaccessible += 1,
+            FileStatus::Unmapped => summary.unmapped += 1,
+        }
+    }
+    Ok(summary)
+}
+
+/// Live document whose file was not found at the last check
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingFile {
+    pub document: DocumentPath,
+    pub check: FileCheck,
+}
+
+/// Live documents whose file was missing or unreadable at the last check,
+/// ordered by document number. Checks of an earlier path of a document
+/// that has moved since are ignored.
+pub async fn find_missing_files(
+    pool: &SqlitePool,
+    type_code: Option<&TypeCode>,
+) -> Result<Vec<MissingFile>> {
+    let mut checks: HashMap<_, FileCheck> =
+        file_check::list_file_checks(pool, &[FileStatus::Missing, FileStatus::Inaccessible])
+            .await?
+            .into_iter()
+            .map(|check| (check.document_id.clone(), check))
+            .collect();
+
+    let mut missing: Vec<MissingFile> = document_path::list_document_paths(pool, false)
+        .await?
+        .into_iter()
+        .filter(|doc| type_code.is_none_or(|code| doc.document_type == *code))
+        .filter_map(|document| {
+            let check = checks.remove(&document.id)?;
+            (check.file_path == document.file_path).then_some(MissingFile { document, check })
+        })
+        .collect();
+    missing.sort_by(|a, b| a.document.document_number.cmp(&b.document.document_number));
+    Ok(missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        Department, DeptCode, DocumentType, PathGenerationRule, Section, SectionCode, User, UserId,
+    };
+    use crate::services::clock::DEFAULT_TIMEZONE;
+    use crate::services::document_service::{self, ManualDocumentRequest, SavedDocument};
+    use crate::storage::db::init_db_pool;
+    use crate::storage::{department, document_type, section, user};
+    use chrono::TimeZone;
+
+    async fn setup(pool: &SqlitePool) -> anyhow::Result<()> {
+        department::create_department(pool, &Department::new('G', "総務")).await?;
+        section::create_section(
+            pool,
+            &Section {
+                code: SectionCode::new('I'),
+                name: "インフラ".to_string(),
+                department: DeptCode::new('G'),
+            },
+        )
+        .await?;
+        user::create_user(pool, &User::new("user001", "田川太郎", 'G', 'I')).await?;
+        let doc_type = DocumentType::new(
+            "A",
+            "契約書",
+            "/docs/contracts/",
+            PathGenerationRule::example_agi(),
+        );
+        document_type::create_document_type(pool, &doc_type).await?;
+        Ok(())
+    }
+
+    async fn create_manual(
+        pool: &SqlitePool,
+        number: &str,
+        file_path: &str,
+    ) -> anyhow::Result<SavedDocument> {
+        Ok(document_service::create_document_manual(
+            pool,
+            &GenerationClock::default(),
+            ManualDocumentRequest {
+                document_number: number.to_string(),
+                type_code: TypeCode::new("A"),
+                dept_code: DeptCode::new('G'),
+                section_code: SectionCode::new('I'),
+                user_id: UserId::new("user001"),
+                file_path: StoredPath::new(file_path),
+                business_task: None,
+            },
+        )
+        .await?)
+    }
+
+    #[tokio::test]
+    async fn test_verify_files_and_missing_report() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup(&pool).await?;
+
+        let share = std::env::temp_dir().join(format!("file-check-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(share.join("contracts"))?;
+        std::fs::write(share.join("contracts").join("a.pdf"), b"contract")?;
+        let verifier = FileVerifier::from_mappings(&format!("/docs={}", share.display()))?;
+
+        let present = create_manual(&pool, "MANUAL-1", "/docs/contracts/a.pdf").await?;
+        let missing = create_manual(&pool, "MANUAL-2", "/docs/contracts/b.pdf").await?;
+        create_manual(&pool, "MANUAL-3", r"\\fs01\docs\c.pdf").await?;
+
+        let now = Utc
+            .with_ymd_and_hms(2025, 10, 1, 0, 0, 0)
+            .single()
+            .ok_or_else(|| anyhow::anyhow!("invalid datetime"))?;
+        let clock = GenerationClock::fixed(now, DEFAULT_TIMEZONE);
+        let summary = verify_files(&pool, &clock, &verifier).await?;
+        assert_eq!(
+            summary,
+            VerificationSummary {
+                checked: 3,
+                present: 1,
+                missing: 1,
+                inaccessible: 0,
+                unmapped: 1,
+            }
+        );
+
+        let report = find_missing_files(&pool, None).await?;
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].document.id, missing.document.id);
+        assert_eq!(report[0].check.status, FileStatus::Missing);
+
+        // The last sighting survives the file disappearing
+        std::fs::remove_file(share.join("contracts").join("a.pdf"))?;
+        verify_files(&pool, &clock, &verifier).await?;
+        let check = file_check::get_file_check(&pool, &present.document.id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("check missing"))?;
+        assert_eq!(check.status, FileStatus::Missing);
+        assert_eq!(check.size, Some(8));
+        assert_eq!(check.last_seen_at, Some(clock.now()));
+        assert_eq!(find_missing_files(&pool, None).await?.len(), 2);
+
+        // A stale check no longer counts once the document has moved
+        document_service::update_document_path(
+            &pool,
+            &clock,
+            &missing.document.id,
+            StoredPath::new("/docs/contracts/b-moved.pdf"),
+        )
+        .await?;
+        let report = find_missing_files(&pool, None).await?;
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].document.id, present.document.id);
+        assert!(
+            find_missing_files(&pool, Some(&TypeCode::new("B")))
+                .await?
+                .is_empty()
+        );
+
+        std::fs::remove_dir_all(&share)?;
+        Ok(())
+    }
+}
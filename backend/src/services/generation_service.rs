@@ -1,33 +1,603 @@
 //! Document number generation service
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::models::{
-    CounterScope, DeptCode, PathGenerationRule, RuleComponent, SectionCode, TypeCode,
+    DeptCode, JapaneseEra, PathGenerationRule, RuleComponent, ScopeDimension, SectionCode, TaskId,
+    TypeCode, UserId,
 };
-use crate::storage::counter;
-use chrono::{Datelike, Utc};
-use sqlx::SqlitePool;
+use crate::storage::{counter, db, document_type};
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use sqlx::{SqliteConnection, SqlitePool};
 
-/// Generate a document number based on the generation rule
+/// Remaining counter capacity for a single scope
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CounterCapacity {
+    /// Counter scope key (e.g., "A_G_I_2025_10")
+    pub scope_key: String,
+    /// Numbers already issued in the scope
+    pub used: i64,
+    /// Highest number the rule can render
+    pub max_value: i64,
+    /// Numbers still available before creation is blocked
+    pub remaining: i64,
+}
+
+/// Generate a document number based on the generation rule.
+/// `date` is the business date of issue (see `GenerationClock::today`).
 pub async fn generate_document_number(
     pool: &SqlitePool,
     rule: &PathGenerationRule,
     type_code: &TypeCode,
     dept_code: &DeptCode,
     section_code: &SectionCode,
+    owner: ScopeOwner<'_>,
+    date: NaiveDate,
 ) -> Result<String> {
-    let now = Utc::now();
-    let year = now.year();
-    let month = now.month();
-    let day = now.day();
+    db::retry_on_busy(|| async {
+        let mut tx = db::begin_write(pool).await?;
+        let issued = issue_document_number(
+            &mut tx,
+            rule,
+            type_code,
+            dept_code,
+            section_code,
+            owner,
+            date,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(issued.document_number)
+    })
+    .await
+}
+
+/// Document number drawn from a counter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IssuedNumber {
+    /// Counter scope key the number was drawn from
+    pub scope_key: String,
+    /// Counter value used by the number
+    pub counter_value: i64,
+    /// Rendered document number
+    pub document_number: String,
+}
 
+/// Draw the next document number inside the caller's transaction.
+/// The counter only advances if the transaction commits, so a failed insert
+/// in the same transaction does not burn the number.
+pub async fn issue_document_number(
+    conn: &mut SqliteConnection,
+    rule: &PathGenerationRule,
+    type_code: &TypeCode,
+    dept_code: &DeptCode,
+    section_code: &SectionCode,
+    owner: ScopeOwner<'_>,
+    date: NaiveDate,
+) -> Result<IssuedNumber> {
     // Build scope key for counter
-    let scope_key = build_scope_key(rule, type_code, dept_code, section_code, year, month as u8);
+    let scope_key = build_scope_key(rule, type_code, dept_code, section_code, owner, date)?;
+
+    // Get next counter value (blocked once the counter no longer fits in counter_digits)
+    let counter_value = counter::increment_counter(
+        conn,
+        &scope_key,
+        rule.counter_start,
+        rule.counter_capacity(),
+    )
+    .await?;
+
+    let document_number = format_document_number(
+        rule,
+        type_code,
+        dept_code,
+        section_code,
+        date,
+        counter_value,
+    )?;
+
+    Ok(IssuedNumber {
+        scope_key,
+        counter_value,
+        document_number,
+    })
+}
+
+/// Preview of the next document number in a scope
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NumberPreview {
+    /// Counter scope key the number would be drawn from
+    pub scope_key: String,
+    /// Counter value the number would use
+    pub counter_value: i64,
+    /// Rendered document number
+    pub document_number: String,
+}
+
+/// Preview the next document number without consuming the counter.
+/// The result is not reserved: a concurrent creation may take the same number first.
+pub async fn preview_document_number(
+    pool: &SqlitePool,
+    rule: &PathGenerationRule,
+    type_code: &TypeCode,
+    dept_code: &DeptCode,
+    section_code: &SectionCode,
+    owner: ScopeOwner<'_>,
+    date: NaiveDate,
+) -> Result<NumberPreview> {
+    let scope_key = build_scope_key(rule, type_code, dept_code, section_code, owner, date)?;
+    let counter_value = counter::get_current_counter(pool, &scope_key)
+        .await?
+        .map_or(rule.counter_start.max(1), |current| current + 1);
+
+    let max_value = rule.counter_capacity();
+    if counter_value > max_value {
+        return Err(Error::CounterExhausted {
+            scope_key,
+            max_value,
+        });
+    }
+
+    let document_number = format_document_number(
+        rule,
+        type_code,
+        dept_code,
+        section_code,
+        date,
+        counter_value,
+    )?;
+
+    Ok(NumberPreview {
+        scope_key,
+        counter_value,
+        document_number,
+    })
+}
+
+/// Get the remaining counter capacity of the scope a new document would use
+pub async fn counter_capacity(
+    pool: &SqlitePool,
+    rule: &PathGenerationRule,
+    type_code: &TypeCode,
+    dept_code: &DeptCode,
+    section_code: &SectionCode,
+    owner: ScopeOwner<'_>,
+    date: NaiveDate,
+) -> Result<CounterCapacity> {
+    let scope_key = build_scope_key(rule, type_code, dept_code, section_code, owner, date)?;
+    // Values below the rule's starting value count as used
+    let used = counter::get_current_counter(pool, &scope_key)
+        .await?
+        .unwrap_or(rule.counter_start.max(1) - 1);
+    let max_value = rule.counter_capacity();
+
+    Ok(CounterCapacity {
+        scope_key,
+        used,
+        max_value,
+        remaining: (max_value - used).max(0),
+    })
+}
+
+/// Render a document number for the given date and counter value
+pub fn format_document_number(
+    rule: &PathGenerationRule,
+    type_code: &TypeCode,
+    dept_code: &DeptCode,
+    section_code: &SectionCode,
+    date: NaiveDate,
+    counter_value: i64,
+) -> Result<String> {
+    let parts = render_components(
+        rule,
+        type_code,
+        dept_code,
+        section_code,
+        date,
+        counter_value,
+    )?;
+
+    // Join parts with separators
+    let mut document_number = String::new();
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            document_number.push_str(rule.separator_after(i - 1));
+        }
+        if let RuleComponent::CheckDigit { algorithm } = &rule.components[i] {
+            let check = algorithm.check_char(&document_number);
+            document_number.push(check);
+        }
+        document_number.push_str(part);
+    }
+
+    Ok(document_number)
+}
+
+/// Text surrounding the counter in numbers rendered for the given date
+/// (e.g., ("AGI2510", "") for example_agi in October 2025).
+/// A trailing check digit is not part of the suffix.
+/// Returns None when the rule has no counter component.
+pub fn counter_affixes(
+    rule: &PathGenerationRule,
+    type_code: &TypeCode,
+    dept_code: &DeptCode,
+    section_code: &SectionCode,
+    date: NaiveDate,
+) -> Result<Option<(String, String)>> {
+    let Some(index) = rule
+        .components
+        .iter()
+        .position(|c| matches!(c, RuleComponent::AutoIncrement))
+    else {
+        return Ok(None);
+    };
+    let parts = render_components(rule, type_code, dept_code, section_code, date, 0)?;
+
+    let mut prefix = String::new();
+    for (i, part) in parts.iter().enumerate().take(index) {
+        prefix.push_str(part);
+        prefix.push_str(rule.separator_after(i));
+    }
+    let mut suffix = String::new();
+    for (i, part) in parts.iter().enumerate().skip(index + 1) {
+        suffix.push_str(rule.separator_after(i - 1));
+        suffix.push_str(part);
+    }
+
+    Ok(Some((prefix, suffix)))
+}
+
+/// Extract the counter value from a number with known counter affixes.
+/// Returns None when the number does not fit the affixes or the counter encoding,
+/// or fails the rule's check digit.
+pub fn counter_value_in(
+    rule: &PathGenerationRule,
+    (prefix, suffix): (&str, &str),
+    document_number: &str,
+) -> Option<i64> {
+    let document_number = match rule.check_digit() {
+        Some(algorithm) if algorithm.is_valid(document_number) => {
+            let mut chars = document_number.chars();
+            chars.next_back();
+            chars.as_str()
+        }
+        Some(_) => return None,
+        None => document_number,
+    };
+    let counter = document_number.strip_prefix(prefix)?.strip_suffix(suffix)?;
+    rule.counter_encoding.decode(counter, rule.counter_digits)
+}
+
+/// Components read back from a document number
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParsedNumber {
+    pub document_type: TypeCode,
+    pub department: Option<DeptCode>,
+    pub section: Option<SectionCode>,
+    /// Calendar year; two-digit years are read as 20YY and era years are converted
+    pub year: Option<i32>,
+    pub era: Option<JapaneseEra>,
+    pub era_year: Option<u32>,
+    /// Fiscal year, named after the calendar year it starts in
+    pub fiscal_year: Option<i32>,
+    pub fiscal_quarter: Option<u32>,
+    pub month: Option<u32>,
+    pub day: Option<u32>,
+    pub counter_value: Option<i64>,
+}
+
+/// A rule version of an active document type that can read a number
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NumberCandidate {
+    /// Rule version that matched (generation_rules.id)
+    pub generation_rule_id: Option<i64>,
+    /// Rule pattern (e.g., "[TYPE][DEPT][SECT][YYMM][NNN]")
+    pub pattern: String,
+    #[serde(flatten)]
+    pub parsed: ParsedNumber,
+}
+
+/// Parse a document number back into the components of `rule` for the
+/// document type `type_code`, without looking anything up.
+/// Returns None when the number cannot have been produced by the rule
+/// (wrong literals or separators, impossible dates, failed check digit).
+pub fn parse_document_number(
+    rule: &PathGenerationRule,
+    type_code: &TypeCode,
+    document_number: &str,
+) -> Option<ParsedNumber> {
+    let document_number = match rule.check_digit() {
+        Some(algorithm) if algorithm.is_valid(document_number) => {
+            let mut chars = document_number.chars();
+            chars.next_back();
+            chars.as_str()
+        }
+        Some(_) => return None,
+        None => document_number,
+    };
+
+    let parsed = ParsedNumber {
+        document_type: type_code.clone(),
+        department: None,
+        section: None,
+        year: None,
+        era: None,
+        era_year: None,
+        fiscal_year: None,
+        fiscal_quarter: None,
+        month: None,
+        day: None,
+        counter_value: None,
+    };
+    parse_components(rule, type_code, 0, document_number, parsed)
+}
+
+/// Try every rule version of every active document type on `document_number`
+pub async fn parse_number_candidates(
+    pool: &SqlitePool,
+    document_number: &str,
+) -> Result<Vec<NumberCandidate>> {
+    let mut candidates: Vec<NumberCandidate> = Vec::new();
+    for doc_type in document_type::list_active_document_types(pool).await? {
+        let versions = document_type::list_rule_versions(pool, &doc_type.code).await?;
+        let rules: Vec<&PathGenerationRule> = if versions.is_empty() {
+            vec![&doc_type.generation_rule]
+        } else {
+            versions.iter().map(|version| &version.rule).collect()
+        };
+
+        for rule in rules {
+            let Some(parsed) = parse_document_number(rule, &doc_type.code, document_number) else {
+                continue;
+            };
+            // Versions that only differ in ways the number does not show read the same
+            if candidates.iter().any(|c| c.parsed == parsed) {
+                continue;
+            }
+            candidates.push(NumberCandidate {
+                generation_rule_id: rule.id,
+                pattern: rule.to_pattern(),
+                parsed,
+            });
+        }
+    }
+    Ok(candidates)
+}
+
+/// A value read from a single rule component
+enum ParsedField {
+    Nothing,
+    Department(char),
+    Section(char),
+    Year(i32),
+    Era(JapaneseEra),
+    EraYear(u32),
+    FiscalYear(i32),
+    FiscalQuarter(u32),
+    Month(u32),
+    Day(u32),
+    Counter(i64),
+}
+
+impl ParsedNumber {
+    /// Record a field; false when it contradicts a value already read
+    fn set(&mut self, field: ParsedField) -> bool {
+        fn merge<T: PartialEq>(slot: &mut Option<T>, value: T) -> bool {
+            match slot {
+                Some(existing) => *existing == value,
+                None => {
+                    *slot = Some(value);
+                    true
+                }
+            }
+        }
+        match field {
+            ParsedField::Nothing => true,
+            ParsedField::Department(code) => merge(&mut self.department, DeptCode::new(code)),
+            ParsedField::Section(code) => merge(&mut self.section, SectionCode::new(code)),
+            ParsedField::Year(year) => merge(&mut self.year, year),
+            ParsedField::Era(era) => merge(&mut self.era, era),
+            ParsedField::EraYear(year) => merge(&mut self.era_year, year),
+            ParsedField::FiscalYear(year) => merge(&mut self.fiscal_year, year),
+            ParsedField::FiscalQuarter(quarter) => merge(&mut self.fiscal_quarter, quarter),
+            ParsedField::Month(month) => merge(&mut self.month, month),
+            ParsedField::Day(day) => merge(&mut self.day, day),
+            ParsedField::Counter(value) => merge(&mut self.counter_value, value),
+        }
+    }
+
+    /// Whether the date components read from the number agree with `date`
+    /// (trivially true for components the rule does not show)
+    pub fn is_dated(&self, rule: &PathGenerationRule, date: NaiveDate) -> Result<bool> {
+        if self.fiscal_year.is_some() || self.fiscal_quarter.is_some() {
+            let (fiscal_year, quarter) = fiscal_period(rule, date)?;
+            if self.fiscal_year.is_some_and(|y| y != fiscal_year)
+                || self.fiscal_quarter.is_some_and(|q| q != quarter)
+            {
+                return Ok(false);
+            }
+        }
+        Ok(self.year.is_none_or(|y| y == date.year())
+            && self.month.is_none_or(|m| m == date.month())
+            && self.day.is_none_or(|d| d == date.day()))
+    }
+
+    /// Resolve era years to calendar years and reject impossible dates
+    fn finish(mut self) -> Option<Self> {
+        if let (Some(era), Some(era_year)) = (self.era, self.era_year) {
+            let year = era.start_date().year() + era_year as i32 - 1;
+            if !self.set(ParsedField::Year(year)) {
+                return None;
+            }
+        }
+        if self.month.is_some_and(|m| !(1..=12).contains(&m))
+            || self.day.is_some_and(|d| !(1..=31).contains(&d))
+            || self.fiscal_quarter.is_some_and(|q| !(1..=4).contains(&q))
+            || self.era_year == Some(0)
+        {
+            return None;
+        }
+        if let (Some(year), Some(month), Some(day)) = (self.year, self.month, self.day) {
+            NaiveDate::from_ymd_opt(year, month, day)?;
+        }
+        Some(self)
+    }
+}
+
+/// Match the components from `index` on against `rest`, backtracking over
+/// the possible lengths of each component
+fn parse_components(
+    rule: &PathGenerationRule,
+    type_code: &TypeCode,
+    index: usize,
+    rest: &str,
+    parsed: ParsedNumber,
+) -> Option<ParsedNumber> {
+    let Some(component) = rule.components.get(index) else {
+        return if rest.is_empty() {
+            parsed.finish()
+        } else {
+            None
+        };
+    };
+
+    for (len, field) in component_matches(rule, type_code, component, rest) {
+        let mut next = parsed.clone();
+        if !next.set(field) {
+            continue;
+        }
+        let mut after = &rest[len..];
+        if index + 1 < rule.components.len() {
+            match after.strip_prefix(rule.separator_after(index)) {
+                Some(stripped) => after = stripped,
+                None => continue,
+            }
+        }
+        if let Some(done) = parse_components(rule, type_code, index + 1, after, next) {
+            return Some(done);
+        }
+    }
+    None
+}
 
-    // Get next counter value
-    let counter_value = counter::get_next_counter(pool, &scope_key).await?;
+/// Possible readings of `component` at the start of `text`,
+/// as (length in bytes, value read)
+fn component_matches(
+    rule: &PathGenerationRule,
+    type_code: &TypeCode,
+    component: &RuleComponent,
+    text: &str,
+) -> Vec<(usize, ParsedField)> {
+    let literal = |expected: &str| {
+        text.starts_with(expected)
+            .then_some((expected.len(), ParsedField::Nothing))
+    };
+    let first_char = || text.chars().next().filter(|c| !c.is_whitespace());
+    let number = |digits: usize| {
+        let head = text.get(..digits)?;
+        head.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| head.parse::<i64>().ok())
+            .flatten()
+            .map(|value| (digits, value))
+    };
+    let year = |digits: u8| {
+        number(digits as usize).map(|(len, value)| {
+            let value = value as i32;
+            (len, if digits == 2 { 2000 + value } else { value })
+        })
+    };
+
+    match component {
+        RuleComponent::TypeName => literal(&type_code.0).into_iter().collect(),
+        RuleComponent::DeptCode => first_char()
+            .map(|c| (c.len_utf8(), ParsedField::Department(c)))
+            .into_iter()
+            .collect(),
+        RuleComponent::SectionCode => first_char()
+            .map(|c| (c.len_utf8(), ParsedField::Section(c)))
+            .into_iter()
+            .collect(),
+        RuleComponent::FixedDeptCode { code } => text
+            .starts_with(*code)
+            .then(|| (code.len_utf8(), ParsedField::Department(*code)))
+            .into_iter()
+            .collect(),
+        RuleComponent::FixedSectionCode { code } => text
+            .starts_with(*code)
+            .then(|| (code.len_utf8(), ParsedField::Section(*code)))
+            .into_iter()
+            .collect(),
+        RuleComponent::Literal { text: expected } => literal(expected).into_iter().collect(),
+        RuleComponent::Year { digits } => year(*digits)
+            .map(|(len, value)| (len, ParsedField::Year(value)))
+            .into_iter()
+            .collect(),
+        RuleComponent::EraLetter => JapaneseEra::ALL
+            .into_iter()
+            .filter(|era| text.starts_with(era.letter()))
+            .map(|era| (1, ParsedField::Era(era)))
+            .collect(),
+        RuleComponent::EraName => JapaneseEra::ALL
+            .into_iter()
+            .filter(|era| text.starts_with(era.kanji()))
+            .map(|era| (era.kanji().len(), ParsedField::Era(era)))
+            .collect(),
+        // Era years can outgrow a one-digit width
+        RuleComponent::EraYear { digits } => (*digits as usize..=(*digits as usize).max(2))
+            .filter_map(&number)
+            .map(|(len, value)| (len, ParsedField::EraYear(value as u32)))
+            .collect(),
+        RuleComponent::FiscalYear { digits } => year(*digits)
+            .map(|(len, value)| (len, ParsedField::FiscalYear(value)))
+            .into_iter()
+            .collect(),
+        RuleComponent::FiscalQuarter => number(1)
+            .map(|(len, value)| (len, ParsedField::FiscalQuarter(value as u32)))
+            .into_iter()
+            .collect(),
+        RuleComponent::Month => number(2)
+            .map(|(len, value)| (len, ParsedField::Month(value as u32)))
+            .into_iter()
+            .collect(),
+        RuleComponent::Day => number(2)
+            .map(|(len, value)| (len, ParsedField::Day(value as u32)))
+            .into_iter()
+            .collect(),
+        // Counters are never issued beyond the capacity, which also keeps
+        // a variable-width component before the counter unambiguous
+        RuleComponent::AutoIncrement => {
+            let run = text
+                .bytes()
+                .take_while(|b| b.is_ascii_alphanumeric())
+                .count();
+            (1..=run)
+                .filter_map(|len| {
+                    rule.counter_encoding
+                        .decode(&text[..len], rule.counter_digits)
+                        .filter(|value| (1..=rule.counter_capacity()).contains(value))
+                        .map(|value| (len, ParsedField::Counter(value)))
+                })
+                .collect()
+        }
+        // Verified and removed before parsing
+        RuleComponent::CheckDigit { .. } => vec![(0, ParsedField::Nothing)],
+    }
+}
+
+/// Render each rule component for the given date and counter value
+fn render_components(
+    rule: &PathGenerationRule,
+    type_code: &TypeCode,
+    dept_code: &DeptCode,
+    section_code: &SectionCode,
+    date: NaiveDate,
+    counter_value: i64,
+) -> Result<Vec<String>> {
+    let year = date.year();
+    let month = date.month();
+    let day = date.day();
 
-    // Build document number
     let mut parts = Vec::new();
 
     for component in &rule.components {
@@ -35,6 +605,10 @@ pub async fn generate_document_number(
             RuleComponent::TypeName => type_code.0.clone(),
             RuleComponent::DeptCode => dept_code.0.to_string(),
             RuleComponent::SectionCode => section_code.0.to_string(),
+            RuleComponent::FixedDeptCode { code } | RuleComponent::FixedSectionCode { code } => {
+                code.to_string()
+            }
+            RuleComponent::Literal { text } => text.clone(),
             RuleComponent::Year { digits } => {
                 if *digits == 2 {
                     format!("{:02}", year % 100)
@@ -42,71 +616,192 @@ pub async fn generate_document_number(
                     format!("{:04}", year)
                 }
             }
+            RuleComponent::EraLetter => era_of(date)?.0.letter().to_string(),
+            RuleComponent::EraName => era_of(date)?.0.kanji().to_string(),
+            RuleComponent::EraYear { digits } => {
+                format!("{:0width$}", era_of(date)?.1, width = *digits as usize)
+            }
+            RuleComponent::FiscalYear { digits } => {
+                let fiscal_year = fiscal_period(rule, date)?.0;
+                if *digits == 2 {
+                    format!("{:02}", fiscal_year % 100)
+                } else {
+                    format!("{:04}", fiscal_year)
+                }
+            }
+            RuleComponent::FiscalQuarter => fiscal_period(rule, date)?.1.to_string(),
             RuleComponent::Month => format!("{:02}", month),
             RuleComponent::Day => format!("{:02}", day),
-            RuleComponent::AutoIncrement => {
-                format!(
-                    "{:0width$}",
-                    counter_value,
-                    width = rule.counter_digits as usize
-                )
-            }
+            RuleComponent::AutoIncrement => rule
+                .counter_encoding
+                .encode(counter_value, rule.counter_digits),
+            // Filled in once the text before it is joined
+            RuleComponent::CheckDigit { .. } => String::new(),
         };
         parts.push(part);
     }
 
-    // Join parts with separators
-    let document_number = if rule.separators.is_empty() {
-        parts.join("")
-    } else if rule.separators.len() == 1 {
-        parts.join(&rule.separators[0])
-    } else {
-        // Multiple separators: interleave with parts
-        let mut result = String::new();
-        for (i, part) in parts.iter().enumerate() {
-            result.push_str(part);
-            if i < parts.len() - 1 {
-                let sep_idx = i.min(rule.separators.len() - 1);
-                result.push_str(&rule.separators[sep_idx]);
-            }
-        }
-        result
-    };
+    Ok(parts)
+}
 
-    Ok(document_number)
+/// Document attributes a counter scope can be keyed on
+/// besides the type, department and section
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScopeOwner<'a> {
+    /// Business task the document is filed under
+    pub business_task: Option<&'a TaskId>,
+    /// User creating the document
+    pub user: Option<&'a UserId>,
 }
 
-/// Build scope key for counter based on the counter scope
+impl<'a> ScopeOwner<'a> {
+    pub fn new(user: &'a UserId, business_task: Option<&'a TaskId>) -> Self {
+        Self {
+            business_task,
+            user: Some(user),
+        }
+    }
+}
+
+/// Build scope key for counter based on the counter scope.
+/// The key joins the value of each scope dimension in order with "_".
+/// Department and section fixed by the rule take precedence over the creator's,
+/// so that every creator shares the counter behind the same rendered prefix.
 pub fn build_scope_key(
     rule: &PathGenerationRule,
     type_code: &TypeCode,
     dept_code: &DeptCode,
     section_code: &SectionCode,
-    year: i32,
-    month: u8,
-) -> String {
-    match rule.counter_scope {
-        CounterScope::TypeOnly => type_code.0.clone(),
-        CounterScope::TypeAndYear => {
-            format!("{}_{}", type_code.0, year)
-        }
-        CounterScope::TypeSectionYear => {
-            format!("{}_{}_{}", type_code.0, section_code.0, year)
-        }
-        CounterScope::TypeDeptSectionYearMonth => {
-            format!(
-                "{}_{}_{}_{:04}_{:02}",
-                type_code.0, dept_code.0, section_code.0, year, month
-            )
+    owner: ScopeOwner<'_>,
+    date: NaiveDate,
+) -> Result<String> {
+    rule.counter_scope.validate()?;
+    let dept_code = rule.fixed_dept_code().unwrap_or(*dept_code);
+    let section_code = rule.fixed_section_code().unwrap_or(*section_code);
+
+    let mut parts = Vec::new();
+    for dimension in rule.counter_scope.dimensions() {
+        let part = match dimension {
+            ScopeDimension::Type => type_code.0.clone(),
+            ScopeDimension::Dept => dept_code.0.to_string(),
+            ScopeDimension::Section => section_code.0.to_string(),
+            ScopeDimension::Task => {
+                owner
+                    .business_task
+                    .map(|task| task.0.clone())
+                    .ok_or_else(|| {
+                        Error::Validation(format!(
+                            "Counter scope of type '{}' requires a business task",
+                            type_code.0
+                        ))
+                    })?
+            }
+            ScopeDimension::Year => format!("{:04}", date.year()),
+            ScopeDimension::EraYear => {
+                let (era, era_year) = era_of(date)?;
+                format!("{}{:02}", era.letter(), era_year)
+            }
+            ScopeDimension::FiscalYear => format!("FY{}", fiscal_period(rule, date)?.0),
+            ScopeDimension::FiscalQuarter => format!("Q{}", fiscal_period(rule, date)?.1),
+            ScopeDimension::Month => format!("{:02}", date.month()),
+            ScopeDimension::Day => format!("{:02}", date.day()),
+            ScopeDimension::User => owner.user.map(|user| user.0.clone()).ok_or_else(|| {
+                Error::Validation(format!(
+                    "Counter scope of type '{}' requires a user",
+                    type_code.0
+                ))
+            })?,
+        };
+        parts.push(part);
+    }
+
+    Ok(parts.join("_"))
+}
+
+/// Whether `scope_key` has the shape `build_scope_key` gives keys of
+/// `type_code` under `rule`: one part per scope dimension, each in its format
+pub fn scope_key_matches(rule: &PathGenerationRule, type_code: &TypeCode, scope_key: &str) -> bool {
+    let dimensions = rule.counter_scope.dimensions();
+    let parts: Vec<&str> = scope_key.split('_').collect();
+    if parts.len() != dimensions.len() {
+        return false;
+    }
+
+    let digits = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+    let number_in = |s: &str, range: std::ops::RangeInclusive<u32>| {
+        digits(s, 2) && s.parse().is_ok_and(|n| range.contains(&n))
+    };
+    let code = |s: &str, fixed: Option<char>| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => c.is_ascii_uppercase() && fixed.is_none_or(|f| f == c),
+            _ => false,
         }
+    };
+
+    dimensions
+        .iter()
+        .zip(parts)
+        .all(|(dimension, part)| match dimension {
+            ScopeDimension::Type => part == type_code.0,
+            ScopeDimension::Dept => code(part, rule.fixed_dept_code().map(|d| d.0)),
+            ScopeDimension::Section => code(part, rule.fixed_section_code().map(|s| s.0)),
+            ScopeDimension::Task | ScopeDimension::User => !part.is_empty(),
+            ScopeDimension::Year => digits(part, 4),
+            ScopeDimension::EraYear => {
+                let mut chars = part.chars();
+                chars
+                    .next()
+                    .is_some_and(|letter| JapaneseEra::ALL.iter().any(|era| era.letter() == letter))
+                    && digits(chars.as_str(), 2)
+            }
+            ScopeDimension::FiscalYear => part.strip_prefix("FY").is_some_and(|y| digits(y, 4)),
+            ScopeDimension::FiscalQuarter => matches!(part, "Q1" | "Q2" | "Q3" | "Q4"),
+            ScopeDimension::Month => number_in(part, 1..=12),
+            ScopeDimension::Day => number_in(part, 1..=31),
+        })
+}
+
+/// Resolve the fiscal year and fiscal quarter (1-4) for a date.
+/// The fiscal year is named after the calendar year it starts in
+/// (e.g., with an April start, 2026-03-31 belongs to FY2025).
+pub fn fiscal_period(rule: &PathGenerationRule, date: NaiveDate) -> Result<(i32, u32)> {
+    let start_month = rule.fiscal_year_start_month as u32;
+    if !(1..=12).contains(&start_month) {
+        return Err(Error::InvalidRuleComponent(format!(
+            "Fiscal year start month must be 1-12, got {}",
+            start_month
+        )));
     }
+
+    let month = date.month();
+    let fiscal_year = if month >= start_month {
+        date.year()
+    } else {
+        date.year() - 1
+    };
+    let quarter = (month + 12 - start_month) % 12 / 3 + 1;
+
+    Ok((fiscal_year, quarter))
+}
+
+/// Resolve the Japanese era for a date
+fn era_of(date: NaiveDate) -> Result<(JapaneseEra, u32)> {
+    JapaneseEra::from_date(date).ok_or_else(|| {
+        Error::InvalidRuleComponent(format!("No Japanese era defined for date {}", date))
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::CounterScope;
     use crate::storage::db::init_db_pool;
 
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap_or(NaiveDate::MIN)
+    }
+
     #[tokio::test]
     async fn test_generate_document_number_agi() -> anyhow::Result<()> {
         let pool = init_db_pool("sqlite::memory:").await?;
@@ -116,11 +811,20 @@ mod tests {
         let dept_code = DeptCode::new('G');
         let section_code = SectionCode::new('I');
 
-        let number =
-            generate_document_number(&pool, &rule, &type_code, &dept_code, &section_code).await?;
+        let number = generate_document_number(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2025, 10, 1),
+        )
+        .await?;
 
         // Should be like "AGI-2510001" (AGI + YY + MM + counter)
         assert!(number.starts_with("AGI"));
+        assert_eq!(number, "AGI2510001");
         assert_eq!(number.len(), 10); // AGI(3) + YY(2) + MM(2) + NNN(3) = 10
         Ok(())
     }
@@ -134,8 +838,16 @@ mod tests {
         let dept_code = DeptCode::new('G');
         let section_code = SectionCode::new('I');
 
-        let number =
-            generate_document_number(&pool, &rule, &type_code, &dept_code, &section_code).await?;
+        let number = generate_document_number(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2025, 10, 1),
+        )
+        .await?;
 
         // Should be like "りん議I-25001" (りん議 + I + - + YY + counter)
         assert!(number.starts_with("りん議"));
@@ -144,7 +856,7 @@ mod tests {
     }
 
     #[test]
-    fn test_build_scope_key_type_only() {
+    fn test_build_scope_key_type_only() -> Result<()> {
         let rule = PathGenerationRule::new(
             vec![RuleComponent::TypeName, RuleComponent::AutoIncrement],
             CounterScope::TypeOnly,
@@ -154,12 +866,20 @@ mod tests {
         let dept_code = DeptCode::new('G');
         let section_code = SectionCode::new('I');
 
-        let key = build_scope_key(&rule, &type_code, &dept_code, &section_code, 2025, 10);
+        let key = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2025, 10, 1),
+        )?;
         assert_eq!(key, "A");
+        Ok(())
     }
 
     #[test]
-    fn test_build_scope_key_type_and_year() {
+    fn test_build_scope_key_type_and_year() -> Result<()> {
         let rule = PathGenerationRule::new(
             vec![
                 RuleComponent::TypeName,
@@ -173,19 +893,160 @@ mod tests {
         let dept_code = DeptCode::new('G');
         let section_code = SectionCode::new('I');
 
-        let key = build_scope_key(&rule, &type_code, &dept_code, &section_code, 2025, 10);
+        let key = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2025, 10, 1),
+        )?;
         assert_eq!(key, "A_2025");
+        Ok(())
     }
 
     #[test]
-    fn test_build_scope_key_full() {
+    fn test_build_scope_key_full() -> Result<()> {
         let rule = PathGenerationRule::example_agi();
         let type_code = TypeCode::new("A");
         let dept_code = DeptCode::new('G');
         let section_code = SectionCode::new('I');
 
-        let key = build_scope_key(&rule, &type_code, &dept_code, &section_code, 2025, 9);
+        let key = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2025, 9, 1),
+        )?;
         assert_eq!(key, "A_G_I_2025_09");
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_scope_key_custom_dimensions() -> Result<()> {
+        let mut rule = PathGenerationRule::example_agi();
+        let type_code = TypeCode::new("A");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+        let task = TaskId::new("T001");
+        let user = UserId::new("user001");
+        let owner = ScopeOwner::new(&user, Some(&task));
+
+        rule.counter_scope = CounterScope::Custom {
+            dimensions: vec![
+                ScopeDimension::Type,
+                ScopeDimension::Dept,
+                ScopeDimension::Year,
+            ],
+        };
+        let key = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            owner,
+            date(2025, 9, 1),
+        )?;
+        assert_eq!(key, "A_G_2025");
+
+        rule.counter_scope = CounterScope::Custom {
+            dimensions: vec![
+                ScopeDimension::Type,
+                ScopeDimension::Task,
+                ScopeDimension::User,
+                ScopeDimension::Day,
+            ],
+        };
+        let key = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            owner,
+            date(2025, 9, 1),
+        )?;
+        assert_eq!(key, "A_T001_user001_01");
+
+        // A task-scoped counter cannot be drawn without a task
+        let result = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::new(&user, None),
+            date(2025, 9, 1),
+        );
+        assert!(matches!(result, Err(Error::Validation(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_scope_key_presets_match_their_dimensions() -> Result<()> {
+        // Presets keep their historical keys, so existing counters carry over
+        let presets = [
+            (CounterScope::TypeOnly, "A"),
+            (CounterScope::TypeAndYear, "A_2025"),
+            (CounterScope::TypeSectionYear, "A_I_2025"),
+            (CounterScope::TypeDeptSectionYearMonth, "A_G_I_2025_09"),
+            (CounterScope::TypeAndEraYear, "A_R07"),
+            (CounterScope::TypeSectionEraYear, "A_I_R07"),
+            (CounterScope::TypeAndFiscalYear, "A_FY2025"),
+            (CounterScope::TypeSectionFiscalYear, "A_I_FY2025"),
+            (CounterScope::TypeFiscalYearQuarter, "A_FY2025_Q3"),
+        ];
+        for (preset, expected) in presets {
+            let mut rule = PathGenerationRule::example_agi();
+            rule.counter_scope = CounterScope::Custom {
+                dimensions: preset.dimensions(),
+            };
+            let custom = build_scope_key(
+                &rule,
+                &TypeCode::new("A"),
+                &DeptCode::new('G'),
+                &SectionCode::new('I'),
+                ScopeOwner::default(),
+                date(2025, 9, 1),
+            )?;
+            rule.counter_scope = preset;
+            let key = build_scope_key(
+                &rule,
+                &TypeCode::new("A"),
+                &DeptCode::new('G'),
+                &SectionCode::new('I'),
+                ScopeOwner::default(),
+                date(2025, 9, 1),
+            )?;
+            assert_eq!(key, expected);
+            assert_eq!(custom, expected);
+            assert!(scope_key_matches(&rule, &TypeCode::new("A"), &key));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_scope_key_matches_rejects_malformed_keys() {
+        let mut rule = PathGenerationRule::example_agi();
+        let type_code = TypeCode::new("A");
+        assert!(scope_key_matches(&rule, &type_code, "A_G_I_2025_10"));
+
+        for key in [
+            "A_G_I_2025",      // missing month
+            "A_G_I_2025_10_1", // extra part
+            "A_G_I_2025_1",    // month not two digits
+            "A_G_I_2025_13",   // no such month
+            "A_g_I_2025_10",   // lowercase department
+            "A_G_I_25_10",     // two-digit year
+            "B_G_I_2025_10",   // other type
+        ] {
+            assert!(!scope_key_matches(&rule, &type_code, key), "{}", key);
+        }
+
+        rule.counter_scope = CounterScope::TypeFiscalYearQuarter;
+        assert!(scope_key_matches(&rule, &type_code, "A_FY2025_Q4"));
+        assert!(!scope_key_matches(&rule, &type_code, "A_FY2025_Q5"));
+        assert!(!scope_key_matches(&rule, &type_code, "A_2025_Q1"));
     }
 
     #[tokio::test]
@@ -197,10 +1058,27 @@ mod tests {
         let dept_code = DeptCode::new('G');
         let section_code = SectionCode::new('I');
 
-        let num1 =
-            generate_document_number(&pool, &rule, &type_code, &dept_code, &section_code).await?;
-        let num2 =
-            generate_document_number(&pool, &rule, &type_code, &dept_code, &section_code).await?;
+        let today = date(2025, 10, 1);
+        let num1 = generate_document_number(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            today,
+        )
+        .await?;
+        let num2 = generate_document_number(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            today,
+        )
+        .await?;
 
         // Counter should increment
         assert_ne!(num1, num2);
@@ -209,4 +1087,723 @@ mod tests {
         assert!(num2.ends_with("002"));
         Ok(())
     }
+
+    #[test]
+    fn test_format_document_number_era() -> Result<()> {
+        let rule = PathGenerationRule::new(
+            vec![
+                RuleComponent::TypeName,
+                RuleComponent::EraLetter,
+                RuleComponent::EraYear { digits: 2 },
+                RuleComponent::AutoIncrement,
+            ],
+            CounterScope::TypeAndEraYear,
+            3,
+        )
+        .with_separators(vec!["-".to_string()]);
+        let type_code = TypeCode::new("総務");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+
+        let number = format_document_number(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            date(2025, 10, 1),
+            5,
+        )?;
+        assert_eq!(number, "総務-R-07-005");
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_document_number_era_name() -> Result<()> {
+        let rule = PathGenerationRule::new(
+            vec![
+                RuleComponent::EraName,
+                RuleComponent::EraYear { digits: 1 },
+                RuleComponent::AutoIncrement,
+            ],
+            CounterScope::TypeAndEraYear,
+            3,
+        );
+        let type_code = TypeCode::new("りん議");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+
+        let heisei = format_document_number(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            date(2019, 4, 30),
+            1,
+        )?;
+        let reiwa = format_document_number(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            date(2019, 5, 1),
+            1,
+        )?;
+        assert_eq!(heisei, "平成31001");
+        assert_eq!(reiwa, "令和1001");
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_scope_key_era_year_rolls_over_at_era_boundary() -> Result<()> {
+        let rule = PathGenerationRule::new(
+            vec![
+                RuleComponent::TypeName,
+                RuleComponent::SectionCode,
+                RuleComponent::EraYear { digits: 2 },
+                RuleComponent::AutoIncrement,
+            ],
+            CounterScope::TypeSectionEraYear,
+            3,
+        );
+        let type_code = TypeCode::new("りん議");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+
+        let before = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2019, 4, 30),
+        )?;
+        let after = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2019, 5, 1),
+        )?;
+        let next_year = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2020, 1, 1),
+        )?;
+
+        assert_eq!(before, "りん議_I_H31");
+        assert_eq!(after, "りん議_I_R01");
+        assert_eq!(next_year, "りん議_I_R02");
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_scope_key_era_before_meiji() {
+        let rule = PathGenerationRule::new(
+            vec![
+                RuleComponent::EraYear { digits: 2 },
+                RuleComponent::AutoIncrement,
+            ],
+            CounterScope::TypeAndEraYear,
+            3,
+        );
+        let type_code = TypeCode::new("A");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+
+        let result = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(1800, 1, 1),
+        );
+        assert!(matches!(result, Err(Error::InvalidRuleComponent(_))));
+    }
+
+    #[test]
+    fn test_fiscal_period_april_start() -> Result<()> {
+        let rule = PathGenerationRule::example_agi().with_fiscal_year_start_month(4);
+
+        assert_eq!(fiscal_period(&rule, date(2026, 3, 31))?, (2025, 4));
+        assert_eq!(fiscal_period(&rule, date(2026, 4, 1))?, (2026, 1));
+        assert_eq!(fiscal_period(&rule, date(2025, 9, 30))?, (2025, 2));
+        assert_eq!(fiscal_period(&rule, date(2025, 10, 1))?, (2025, 3));
+        assert_eq!(fiscal_period(&rule, date(2026, 1, 1))?, (2025, 4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fiscal_period_calendar_year() -> Result<()> {
+        let rule = PathGenerationRule::example_agi();
+
+        assert_eq!(fiscal_period(&rule, date(2026, 1, 1))?, (2026, 1));
+        assert_eq!(fiscal_period(&rule, date(2026, 12, 31))?, (2026, 4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fiscal_period_invalid_start_month() {
+        let rule = PathGenerationRule::example_agi().with_fiscal_year_start_month(13);
+        let result = fiscal_period(&rule, date(2026, 1, 1));
+        assert!(matches!(result, Err(Error::InvalidRuleComponent(_))));
+    }
+
+    #[test]
+    fn test_format_document_number_fiscal_year() -> Result<()> {
+        let rule = PathGenerationRule::new(
+            vec![
+                RuleComponent::TypeName,
+                RuleComponent::FiscalYear { digits: 2 },
+                RuleComponent::FiscalQuarter,
+                RuleComponent::AutoIncrement,
+            ],
+            CounterScope::TypeAndFiscalYear,
+            3,
+        )
+        .with_fiscal_year_start_month(4);
+        let type_code = TypeCode::new("A");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+
+        let number = format_document_number(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            date(2026, 3, 15),
+            12,
+        )?;
+        assert_eq!(number, "A254012");
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_scope_key_fiscal_year_resets_on_fiscal_boundary() -> Result<()> {
+        let rule = PathGenerationRule::new(
+            vec![
+                RuleComponent::TypeName,
+                RuleComponent::FiscalYear { digits: 2 },
+                RuleComponent::AutoIncrement,
+            ],
+            CounterScope::TypeSectionFiscalYear,
+            3,
+        )
+        .with_fiscal_year_start_month(4);
+        let type_code = TypeCode::new("A");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+
+        let january = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2026, 1, 5),
+        )?;
+        let march = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2026, 3, 31),
+        )?;
+        let april = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2026, 4, 1),
+        )?;
+
+        assert_eq!(january, "A_I_FY2025");
+        assert_eq!(march, "A_I_FY2025");
+        assert_eq!(april, "A_I_FY2026");
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_scope_key_fiscal_quarter() -> Result<()> {
+        let mut rule = PathGenerationRule::example_agi().with_fiscal_year_start_month(4);
+        rule.counter_scope = CounterScope::TypeFiscalYearQuarter;
+        let type_code = TypeCode::new("A");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+
+        let key = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2025, 7, 1),
+        )?;
+        assert_eq!(key, "A_FY2025_Q2");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generate_document_number_blocks_on_exhaustion() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        let mut rule = PathGenerationRule::example_agi();
+        rule.counter_digits = 1;
+        let type_code = TypeCode::new("A");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+        let today = date(2025, 10, 1);
+
+        counter::reset_counter(&pool, "A_G_I_2025_10", 8).await?;
+        let last = generate_document_number(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            today,
+        )
+        .await?;
+        assert_eq!(last, "AGI25109");
+
+        let result = generate_document_number(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            today,
+        )
+        .await;
+        assert!(matches!(result, Err(Error::CounterExhausted { .. })));
+        assert_eq!(
+            counter::get_current_counter(&pool, "A_G_I_2025_10").await?,
+            Some(9)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_counter_capacity() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        let rule = PathGenerationRule::example_agi();
+        let type_code = TypeCode::new("A");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+        let today = date(2025, 10, 1);
+
+        let capacity = counter_capacity(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            today,
+        )
+        .await?;
+        assert_eq!(capacity.used, 0);
+        assert_eq!(capacity.remaining, 999);
+
+        counter::reset_counter(&pool, "A_G_I_2025_10", 990).await?;
+        let capacity = counter_capacity(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            today,
+        )
+        .await?;
+        assert_eq!(capacity.scope_key, "A_G_I_2025_10");
+        assert_eq!(capacity.used, 990);
+        assert_eq!(capacity.max_value, 999);
+        assert_eq!(capacity.remaining, 9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_document_number_literal_and_fixed_dept() -> Result<()> {
+        let rule = PathGenerationRule::new(
+            vec![
+                RuleComponent::Literal {
+                    text: "JP".to_string(),
+                },
+                RuleComponent::TypeName,
+                RuleComponent::FixedDeptCode { code: 'G' },
+                RuleComponent::Year { digits: 2 },
+                RuleComponent::Month,
+                RuleComponent::AutoIncrement,
+            ],
+            CounterScope::TypeDeptSectionYearMonth,
+            3,
+        )
+        .with_separators(vec![
+            "-".to_string(),
+            "-".to_string(),
+            "-".to_string(),
+            "".to_string(),
+            "-".to_string(),
+        ]);
+        let type_code = TypeCode::new("A");
+        let dept_code = DeptCode::new('K');
+        let section_code = SectionCode::new('T');
+
+        let number = format_document_number(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            date(2025, 10, 1),
+            1,
+        )?;
+        assert_eq!(number, "JP-A-G-2510-001");
+
+        // Creators from other departments share the fixed department's counter
+        let key = build_scope_key(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2025, 10, 1),
+        )?;
+        assert_eq!(key, "A_G_T_2025_10");
+        Ok(())
+    }
+
+    #[test]
+    fn test_counter_affixes_and_value() -> Result<()> {
+        let rule = PathGenerationRule::example_ringi();
+        let type_code = TypeCode::new("りん議");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+
+        let affixes = counter_affixes(
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            date(2025, 10, 1),
+        )?
+        .ok_or_else(|| Error::Internal("rule has a counter".to_string()))?;
+        assert_eq!(affixes, ("りん議-I-25-".to_string(), String::new()));
+
+        let affixes = (affixes.0.as_str(), affixes.1.as_str());
+        assert_eq!(
+            counter_value_in(&rule, affixes, "りん議-I-25-042"),
+            Some(42)
+        );
+        assert_eq!(counter_value_in(&rule, affixes, "りん議-I-24-042"), None);
+        assert_eq!(counter_value_in(&rule, affixes, "りん議-I-25-42"), None);
+        assert_eq!(counter_value_in(&rule, affixes, "りん議-I-25-04x"), None);
+
+        let no_counter =
+            PathGenerationRule::new(vec![RuleComponent::TypeName], CounterScope::TypeOnly, 3);
+        assert!(
+            counter_affixes(
+                &no_counter,
+                &type_code,
+                &dept_code,
+                &section_code,
+                date(2025, 10, 1)
+            )?
+            .is_none()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_preview_document_number_does_not_consume_counter() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        let rule = PathGenerationRule::example_agi();
+        let type_code = TypeCode::new("A");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+        let today = date(2025, 10, 1);
+
+        let first = preview_document_number(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            today,
+        )
+        .await?;
+        let second = preview_document_number(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            today,
+        )
+        .await?;
+        assert_eq!(first, second);
+        assert_eq!(first.document_number, "AGI2510001");
+        assert!(counter::list_counters(&pool).await?.is_empty());
+
+        // The preview matches what generation actually issues next
+        let issued = generate_document_number(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            today,
+        )
+        .await?;
+        assert_eq!(issued, first.document_number);
+
+        let next = preview_document_number(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            today,
+        )
+        .await?;
+        assert_eq!(next.document_number, "AGI2510002");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_preview_document_number_exhausted() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        let rule = PathGenerationRule::example_agi();
+        let type_code = TypeCode::new("A");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+
+        counter::reset_counter(&pool, "A_G_I_2025_10", 999).await?;
+        let result = preview_document_number(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            date(2025, 10, 1),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::CounterExhausted { .. })));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_decimal_then_alpha_counter_overflows_into_letters() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        let rule = PathGenerationRule::example_agi()
+            .with_counter_encoding(crate::models::CounterEncoding::DecimalThenAlpha);
+        let type_code = TypeCode::new("A");
+        let dept_code = DeptCode::new('G');
+        let section_code = SectionCode::new('I');
+        let today = date(2025, 10, 1);
+
+        counter::reset_counter(&pool, "A_G_I_2025_10", 998).await?;
+        let mut numbers = Vec::new();
+        for _ in 0..3 {
+            numbers.push(
+                generate_document_number(
+                    &pool,
+                    &rule,
+                    &type_code,
+                    &dept_code,
+                    &section_code,
+                    ScopeOwner::default(),
+                    today,
+                )
+                .await?,
+            );
+        }
+        assert_eq!(numbers, ["AGI2510999", "AGI2510A00", "AGI2510A01"]);
+
+        // Overflow numbers map back to their counter value
+        let affixes = counter_affixes(&rule, &type_code, &dept_code, &section_code, today)?
+            .ok_or_else(|| anyhow::anyhow!("rule has a counter"))?;
+        assert_eq!(
+            counter_value_in(&rule, (&affixes.0, &affixes.1), "AGI2510A01"),
+            Some(1001)
+        );
+
+        // Capacity accounts for the letter overflow (999 + 26 × 100)
+        let capacity = counter_capacity(
+            &pool,
+            &rule,
+            &type_code,
+            &dept_code,
+            &section_code,
+            ScopeOwner::default(),
+            today,
+        )
+        .await?;
+        assert_eq!(capacity.max_value, 3_599);
+        assert_eq!(capacity.remaining, 2_598);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_document_number_agi() {
+        let parsed = parse_document_number(
+            &PathGenerationRule::example_agi(),
+            &TypeCode::new("A"),
+            "AGI2509001",
+        );
+        let Some(parsed) = parsed else {
+            panic!("AGI2509001 should parse");
+        };
+        assert_eq!(parsed.document_type, TypeCode::new("A"));
+        assert_eq!(parsed.department, Some(DeptCode::new('G')));
+        assert_eq!(parsed.section, Some(SectionCode::new('I')));
+        assert_eq!(parsed.year, Some(2025));
+        assert_eq!(parsed.month, Some(9));
+        assert_eq!(parsed.counter_value, Some(1));
+
+        let rule = PathGenerationRule::example_agi();
+        let type_code = TypeCode::new("A");
+        // Wrong type, impossible month, short counter, trailing text
+        assert_eq!(parse_document_number(&rule, &type_code, "BGI2509001"), None);
+        assert_eq!(parse_document_number(&rule, &type_code, "AGI2513001"), None);
+        assert_eq!(parse_document_number(&rule, &type_code, "AGI250901"), None);
+        assert_eq!(
+            parse_document_number(&rule, &type_code, "AGI2509001X"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_document_number_multibyte_type_and_separators() {
+        let parsed = parse_document_number(
+            &PathGenerationRule::example_ringi(),
+            &TypeCode::new("りん議"),
+            "りん議-I-25-042",
+        );
+        let Some(parsed) = parsed else {
+            panic!("りん議-I-25-042 should parse");
+        };
+        assert_eq!(parsed.section, Some(SectionCode::new('I')));
+        assert_eq!(parsed.department, None);
+        assert_eq!(parsed.year, Some(2025));
+        assert_eq!(parsed.counter_value, Some(42));
+
+        // Separators are required
+        assert_eq!(
+            parse_document_number(
+                &PathGenerationRule::example_ringi(),
+                &TypeCode::new("りん議"),
+                "りん議I25042",
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_document_number_era_year() {
+        let rule = PathGenerationRule::new(
+            vec![
+                RuleComponent::EraName,
+                RuleComponent::EraYear { digits: 1 },
+                RuleComponent::AutoIncrement,
+            ],
+            CounterScope::TypeAndEraYear,
+            3,
+        );
+        let type_code = TypeCode::new("りん議");
+
+        let heisei = parse_document_number(&rule, &type_code, "平成31001");
+        assert_eq!(
+            heisei.as_ref().and_then(|p| p.era),
+            Some(JapaneseEra::Heisei)
+        );
+        assert_eq!(heisei.as_ref().and_then(|p| p.era_year), Some(31));
+        assert_eq!(heisei.as_ref().and_then(|p| p.year), Some(2019));
+        assert_eq!(heisei.and_then(|p| p.counter_value), Some(1));
+
+        let reiwa = parse_document_number(&rule, &type_code, "令和1001");
+        assert_eq!(reiwa.as_ref().and_then(|p| p.year), Some(2019));
+        assert_eq!(reiwa.and_then(|p| p.counter_value), Some(1));
+    }
+
+    #[test]
+    fn test_parse_document_number_roundtrips_formatted_numbers() -> Result<()> {
+        let rule = PathGenerationRule::from_pattern(
+            "[TYPE]/[YYYY][MM][DD]-Q[FQ]-[NNNN][CHK37]",
+            CounterScope::TypeAndFiscalYear,
+        )?
+        .with_fiscal_year_start_month(4)
+        .with_separators(Vec::new());
+        let type_code = TypeCode::new("見積");
+        let number = format_document_number(
+            &rule,
+            &type_code,
+            &DeptCode::new('G'),
+            &SectionCode::new('I'),
+            date(2026, 2, 28),
+            17,
+        )?;
+
+        let parsed = parse_document_number(&rule, &type_code, &number);
+        assert_eq!(parsed.as_ref().and_then(|p| p.year), Some(2026));
+        assert_eq!(parsed.as_ref().and_then(|p| p.month), Some(2));
+        assert_eq!(parsed.as_ref().and_then(|p| p.day), Some(28));
+        assert_eq!(parsed.as_ref().and_then(|p| p.fiscal_quarter), Some(4));
+        assert_eq!(parsed.and_then(|p| p.counter_value), Some(17));
+
+        // A mistyped number fails the check digit
+        let typo = number.replacen("0017", "0071", 1);
+        assert_eq!(parse_document_number(&rule, &type_code, &typo), None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_parse_number_candidates_tries_active_types() -> anyhow::Result<()> {
+        use crate::models::DocumentType;
+
+        let pool = init_db_pool("sqlite::memory:").await?;
+        let agi = DocumentType::new(
+            "A",
+            "契約書",
+            "/docs/contracts/",
+            PathGenerationRule::example_agi(),
+        );
+        let ringi = DocumentType::new(
+            "りん議",
+            "稟議書",
+            "/docs/ringi/",
+            PathGenerationRule::example_ringi(),
+        );
+        document_type::create_document_type(&pool, &agi).await?;
+        document_type::create_document_type(&pool, &ringi).await?;
+
+        let candidates = parse_number_candidates(&pool, "AGI2509001").await?;
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].parsed.document_type, TypeCode::new("A"));
+        assert_eq!(candidates[0].pattern, "[TYPE][DEPT][SECT][YY][MM][NNN]");
+
+        let candidates = parse_number_candidates(&pool, "りん議-I-25-042").await?;
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].parsed.document_type, TypeCode::new("りん議"));
+
+        assert!(parse_number_candidates(&pool, "unknown").await?.is_empty());
+        Ok(())
+    }
 }
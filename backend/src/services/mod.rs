@@ -1,6 +1,14 @@
 //! Business logic services
 
+pub mod audit_service;
+pub mod clock;
+pub mod counter_service;
 pub mod document_service;
+pub mod file_check_service;
 pub mod generation_service;
 pub mod organization_service;
+pub mod path_service;
 pub mod query_service;
+pub mod reservation_service;
+pub mod rule_service;
+pub mod watch_service;
@@ -0,0 +1,431 @@
+//! File path checks across documents
+//!
+//! Paths are compared in their canonical form (see `StoredPath`), so
+//! `/docs/a/../b/x.pdf` and `/docs/b/x.pdf/` name the same file, as do
+//! `C:\Docs\x.pdf` and `c:/docs/X.PDF`. Each document type decides whether
+//! documents may share a path (`PathUniqueness`) and whether paths must lie
+//! under its root directory (`PathContainment`).
+
+use crate::error::{Error, Result};
+use crate::models::{
+    DocumentId, DocumentPath, DocumentType, PathContainment, PathUniqueness, StoredPath, TypeCode,
+};
+use crate::storage::{document_path, document_type};
+use serde::Serialize;
+use sqlx::{SqliteExecutor, SqlitePool};
+use std::collections::HashMap;
+
+/// Live documents sharing one file path
+#[derive(Debug, Clone, Serialize)]
+pub struct PathDuplicate {
+    pub file_path: StoredPath,
+    pub documents: Vec<DocumentPath>,
+}
+
+/// Apply a document type's uniqueness policy to a document about to be
+/// written at `path` (`exclude` is the document itself when it moves).
+/// Returns the warnings raised in warn mode.
+pub async fn check_path_uniqueness<'e, E>(
+    executor: E,
+    policy: PathUniqueness,
+    path: &StoredPath,
+    exclude: Option<&DocumentId>,
+) -> Result<Vec<String>>
+where
+    E: SqliteExecutor<'e>,
+{
+    if policy == PathUniqueness::Allow {
+        return Ok(Vec::new());
+    }
+
+    let document_numbers = document_path::live_documents_at_path(executor, path, exclude).await?;
+    if document_numbers.is_empty() {
+        return Ok(Vec::new());
+    }
+    let conflict = Error::DuplicateFilePath {
+        file_path: path.to_string(),
+        document_numbers,
+    };
+    match policy {
+        PathUniqueness::Reject => Err(conflict),
+        PathUniqueness::Allow | PathUniqueness::Warn => Ok(vec![conflict.to_string()]),
+    }
+}
+
+/// Live document outside its type's root directory
+#[derive(Debug, Clone, Serialize)]
+pub struct ContainmentViolation {
+    pub root_directory: StoredPath,
+    pub policy: PathContainment,
+    pub document: DocumentPath,
+}
+
+/// Apply a document type's containment policy to a document about to be
+/// written at `path`. Returns the warnings raised in warn mode.
+pub fn check_containment(doc_type: &DocumentType, path: &StoredPath) -> Result<Vec<String>> {
+    if doc_type.path_containment == PathContainment::Off || path.is_within(&doc_type.root_directory)
+    {
+        return Ok(Vec::new());
+    }
+    let violation = Error::PathOutsideRoot {
+        file_path: path.to_string(),
+        root_directory: doc_type.root_directory.to_string(),
+    };
+    match doc_type.path_containment {
+        PathContainment::Strict => Err(violation),
+        PathContainment::Off | PathContainment::Warn => Ok(vec![violation.to_string()]),
+    }
+}
+
+/// Live documents whose path lies outside their type's root directory,
+/// ordered by document number. Types are reported whatever their policy,
+/// so violations can be cleaned up before switching to strict.
+pub async fn find_containment_violations(
+    pool: &SqlitePool,
+    type_code: Option<&TypeCode>,
+) -> Result<Vec<ContainmentViolation>> {
+    let doc_types: HashMap<TypeCode, DocumentType> = document_type::list_document_types(pool)
+        .await?
+        .into_iter()
+        .map(|doc_type| (doc_type.code.clone(), doc_type))
+        .collect();
+
+    let mut violations: Vec<ContainmentViolation> = document_path::list_document_paths(pool, false)
+        .await?
+        .into_iter()
+        .filter(|doc| type_code.is_none_or(|code| doc.document_type == *code))
+        .filter_map(|doc| {
+            let doc_type = doc_types.get(&doc.document_type)?;
+            (!doc.file_path.is_within(&doc_type.root_directory)).then(|| ContainmentViolation {
+                root_directory: doc_type.root_directory.clone(),
+                policy: doc_type.path_containment,
+                document: doc,
+            })
+        })
+        .collect();
+    violations.sort_by(|a, b| a.document.document_number.cmp(&b.document.document_number));
+    Ok(violations)
+}
+
+/// Bring the stored comparison keys of all documents in line with their
+/// canonical paths, so that uniqueness checks also catch documents written
+/// before canonicalisation. Run at startup; returns the rows updated.
+pub async fn refresh_path_keys(pool: &SqlitePool) -> Result<u64> {
+    document_path::refresh_path_keys(pool).await
+}
+
+/// Groups of live documents that share a file path, ordered by path.
+/// Every stored path is canonicalised again, so documents written before
+/// canonicalisation are matched as well.
+pub async fn find_duplicate_paths(pool: &SqlitePool) -> Result<Vec<PathDuplicate>> {
+    let mut by_path: HashMap<StoredPath, Vec<DocumentPath>> = HashMap::new();
+    for doc in document_path::list_document_paths(pool, false).await? {
+        by_path.entry(doc.file_path.clone()).or_default().push(doc);
+    }
+
+    let mut duplicates: Vec<PathDuplicate> = by_path
+        .into_iter()
+        .filter(|(_, documents)| documents.len() > 1)
+        .map(|(file_path, mut documents)| {
+            documents.sort_by(|a, b| a.document_number.cmp(&b.document_number));
+            PathDuplicate {
+                file_path,
+                documents,
+            }
+        })
+        .collect();
+    duplicates.sort_by(|a, b| a.file_path.as_str().cmp(b.file_path.as_str()));
+    Ok(duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        Department, DeptCode, DocumentType, PathGenerationRule, Section, SectionCode, TypeCode,
+        User, UserId,
+    };
+    use crate::services::clock::GenerationClock;
+    use crate::services::document_service::{
+        self, AutoDocumentRequest, ManualDocumentRequest, SavedDocument,
+    };
+    use crate::services::reservation_service::{self, ReserveNumberRequest};
+    use crate::storage::db::init_db_pool;
+    use crate::storage::{department, document_type, section, user};
+
+    async fn setup(pool: &SqlitePool, policy: PathUniqueness) -> anyhow::Result<()> {
+        department::create_department(pool, &Department::new('G', "総務")).await?;
+        section::create_section(
+            pool,
+            &Section {
+                code: SectionCode::new('I'),
+                name: "インフラ".to_string(),
+                department: DeptCode::new('G'),
+            },
+        )
+        .await?;
+        user::create_user(pool, &User::new("user001", "田川太郎", 'G', 'I')).await?;
+        let doc_type = DocumentType::new(
+            "A",
+            "契約書",
+            "/docs/contracts/",
+            PathGenerationRule::example_agi(),
+        )
+        .with_path_uniqueness(policy);
+        document_type::create_document_type(pool, &doc_type).await?;
+        Ok(())
+    }
+
+    async fn create_manual(
+        pool: &SqlitePool,
+        number: &str,
+        file_path: &str,
+    ) -> crate::error::Result<SavedDocument> {
+        document_service::create_document_manual(
+            pool,
+            &GenerationClock::default(),
+            ManualDocumentRequest {
+                document_number: number.to_string(),
+                type_code: TypeCode::new("A"),
+                dept_code: DeptCode::new('G'),
+                section_code: SectionCode::new('I'),
+                user_id: UserId::new("user001"),
+                file_path: StoredPath::new(file_path),
+                business_task: None,
+            },
+        )
+        .await
+    }
+
+    async fn update_type(
+        pool: &SqlitePool,
+        update: impl FnOnce(DocumentType) -> DocumentType,
+    ) -> anyhow::Result<()> {
+        let doc_type = document_type::get_document_type(pool, &TypeCode::new("A"))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("document type missing"))?;
+        document_type::update_document_type(pool, &update(doc_type)).await?;
+        Ok(())
+    }
+
+    async fn set_policy(pool: &SqlitePool, policy: PathUniqueness) -> anyhow::Result<()> {
+        update_type(pool, |doc_type| doc_type.with_path_uniqueness(policy)).await
+    }
+
+    #[tokio::test]
+    async fn test_path_uniqueness_policies() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup(&pool, PathUniqueness::Reject).await?;
+
+        let first = create_manual(&pool, "MANUAL-1", "/docs/b/x.pdf").await?;
+        assert!(first.warnings.is_empty());
+
+        // Same file once canonicalised
+        let rejected = create_manual(&pool, "MANUAL-2", "/docs/a/../b//x.pdf/").await;
+        match rejected {
+            Err(Error::DuplicateFilePath {
+                file_path,
+                document_numbers,
+            }) => {
+                assert_eq!(file_path, "/docs/b/x.pdf");
+                assert_eq!(document_numbers, vec!["MANUAL-1".to_string()]);
+            }
+            other => panic!("expected duplicate file path, got {:?}", other),
+        }
+
+        // Moving a document onto its own path is not a conflict
+        let moved = document_service::update_document_path(
+            &pool,
+            &GenerationClock::default(),
+            &first.document.id,
+            StoredPath::new("/docs/b/./x.pdf"),
+        )
+        .await?;
+        assert!(moved.warnings.is_empty());
+
+        set_policy(&pool, PathUniqueness::Warn).await?;
+        let warned = create_manual(&pool, "MANUAL-2", "/docs/b/x.pdf").await?;
+        assert_eq!(warned.warnings.len(), 1);
+        assert!(warned.warnings[0].contains("MANUAL-1"));
+
+        // Deleted documents do not hold on to their path
+        set_policy(&pool, PathUniqueness::Reject).await?;
+        document_service::delete_document(&pool, &first.document.id).await?;
+        document_service::delete_document(&pool, &warned.document.id).await?;
+        create_manual(&pool, "MANUAL-3", "/docs/b/x.pdf").await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_find_duplicate_paths() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup(&pool, PathUniqueness::Allow).await?;
+
+        create_manual(&pool, "MANUAL-1", "/docs/b/x.pdf").await?;
+        create_manual(&pool, "MANUAL-2", "/docs/b/y.pdf").await?;
+        create_manual(&pool, "WIN-1", "C:\\Docs\\a.pdf").await?;
+        create_manual(&pool, "WIN-2", "c:/docs/A.PDF").await?;
+        assert_eq!(find_duplicate_paths(&pool).await?.len(), 1);
+
+        // A path stored before canonicalisation is still matched
+        sqlx::query(
+            "UPDATE documents SET file_path = '/docs/a/../b/x.pdf', path_key = NULL \
+             WHERE document_number = 'MANUAL-2'",
+        )
+        .execute(&pool)
+        .await?;
+
+        let duplicates = find_duplicate_paths(&pool).await?;
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(duplicates[0].file_path.as_str(), "/docs/b/x.pdf");
+        let numbers: Vec<&str> = duplicates[0]
+            .documents
+            .iter()
+            .map(|d| d.document_number.as_str())
+            .collect();
+        assert_eq!(numbers, vec!["MANUAL-1", "MANUAL-2"]);
+        assert_eq!(duplicates[1].documents.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_path_containment_policies() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup(&pool, PathUniqueness::Allow).await?;
+
+        // Off: anything goes
+        let stray = create_manual(&pool, "MANUAL-1", "/tmp/stray.pdf").await?;
+        assert!(stray.warnings.is_empty());
+
+        update_type(&pool, |t| t.with_path_containment(PathContainment::Strict)).await?;
+        let rejected = create_manual(&pool, "MANUAL-2", "/docs/contracts/../invoices/a.pdf").await;
+        assert!(matches!(rejected, Err(Error::PathOutsideRoot { .. })));
+        let inside = create_manual(&pool, "MANUAL-2", "/docs/contracts/2025/a.pdf").await?;
+        let moved = document_service::update_document_path(
+            &pool,
+            &GenerationClock::default(),
+            &inside.document.id,
+            StoredPath::new("/docs/contracts-old/a.pdf"),
+        )
+        .await;
+        assert!(matches!(moved, Err(Error::PathOutsideRoot { .. })));
+
+        update_type(&pool, |t| t.with_path_containment(PathContainment::Warn)).await?;
+        let warned = create_manual(&pool, "MANUAL-3", "/tmp/other.pdf").await?;
+        assert_eq!(warned.warnings.len(), 1);
+        assert!(warned.warnings[0].contains("/docs/contracts"));
+
+        let violations = find_containment_violations(&pool, None).await?;
+        let numbers: Vec<&str> = violations
+            .iter()
+            .map(|v| v.document.document_number.as_str())
+            .collect();
+        assert_eq!(numbers, vec!["MANUAL-1", "MANUAL-3"]);
+        assert_eq!(violations[0].policy, PathContainment::Warn);
+        assert_eq!(violations[0].root_directory.as_str(), "/docs/contracts");
+        assert!(
+            find_containment_violations(&pool, Some(&TypeCode::new("B")))
+                .await?
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_warnings_from_auto_numbers_and_reservations() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup(&pool, PathUniqueness::Warn).await?;
+        update_type(&pool, |t| t.with_path_containment(PathContainment::Warn)).await?;
+        let clock = GenerationClock::default();
+        let auto_request = |file_path: &str| AutoDocumentRequest {
+            type_code: TypeCode::new("A"),
+            dept_code: DeptCode::new('G'),
+            section_code: SectionCode::new('I'),
+            user_id: UserId::new("user001"),
+            file_path: StoredPath::new(file_path),
+            business_task: None,
+            extension: None,
+        };
+
+        create_manual(&pool, "MANUAL-1", "/docs/contracts/x.pdf").await?;
+        let duplicate = document_service::create_document_auto(
+            &pool,
+            &clock,
+            auto_request("/docs/contracts/x.pdf"),
+        )
+        .await?;
+        assert_eq!(duplicate.warnings.len(), 1);
+        assert!(duplicate.warnings[0].contains("MANUAL-1"));
+
+        let outside =
+            document_service::create_document_auto(&pool, &clock, auto_request("/tmp/x.pdf"))
+                .await?;
+        assert_eq!(outside.warnings.len(), 1);
+        assert!(outside.warnings[0].contains("/docs/contracts"));
+
+        // Confirming a reservation reports both policies
+        let reserved = reservation_service::reserve_number(
+            &pool,
+            &clock,
+            ReserveNumberRequest {
+                type_code: TypeCode::new("A"),
+                dept_code: DeptCode::new('G'),
+                section_code: SectionCode::new('I'),
+                user_id: UserId::new("user001"),
+                business_task: None,
+                ttl: chrono::Duration::hours(1),
+            },
+        )
+        .await?;
+        let confirmed = reservation_service::confirm_reservation(
+            &pool,
+            &clock,
+            &reserved.id,
+            StoredPath::new("/tmp/x.pdf"),
+            None,
+        )
+        .await?;
+        assert_eq!(confirmed.warnings.len(), 2);
+        assert!(confirmed.warnings[0].contains("/docs/contracts"));
+        assert!(confirmed.warnings[1].contains(&outside.document.document_number));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_refresh_path_keys_of_legacy_rows() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup(&pool, PathUniqueness::Reject).await?;
+        create_manual(&pool, "MANUAL-1", "/docs/b/y.pdf").await?;
+        create_manual(&pool, "WIN-1", "C:\\Docs\\a.pdf").await?;
+
+        // Rows as left by the SQL backfill: raw path, approximate key
+        sqlx::query(
+            "UPDATE documents SET file_path = '/docs/a/../b//x.pdf', \
+             path_key = '/docs/a/../b//x.pdf' WHERE document_number = 'MANUAL-1'",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "UPDATE documents SET file_path = 'c:/docs/./A.PDF', \
+             path_key = 'c:\\docs\\.\\a.pdf' WHERE document_number = 'WIN-1'",
+        )
+        .execute(&pool)
+        .await?;
+        // The stale key lets a second document onto the same file
+        create_manual(&pool, "MANUAL-2", "/docs/b/x.pdf").await?;
+
+        assert_eq!(refresh_path_keys(&pool).await?, 2);
+        assert_eq!(refresh_path_keys(&pool).await?, 0);
+
+        let rejected = create_manual(&pool, "MANUAL-3", "/docs/b/x.pdf").await;
+        match rejected {
+            Err(Error::DuplicateFilePath {
+                document_numbers, ..
+            }) => assert_eq!(document_numbers, vec!["MANUAL-1", "MANUAL-2"]),
+            other => panic!("expected duplicate file path, got {:?}", other),
+        }
+        let rejected = create_manual(&pool, "WIN-2", "C:\\docs\\a.pdf").await;
+        assert!(matches!(rejected, Err(Error::DuplicateFilePath { .. })));
+        Ok(())
+    }
+}
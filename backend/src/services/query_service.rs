@@ -144,10 +144,11 @@ impl Default for DocumentQueryBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{Department, DocumentType, PathGenerationRule, Section, User, UserId};
+    use crate::models::{
+        Department, DocumentType, PathGenerationRule, Section, StoredPath, User, UserId,
+    };
     use crate::storage::db::init_db_pool;
     use crate::storage::{department, document_path, document_type, section, user};
-    use std::path::PathBuf;
 
     async fn setup_test_data(pool: &SqlitePool) -> Result<()> {
         // Create organization
@@ -180,7 +181,7 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/docs/contracts/AGI-2509001.pdf"),
+            StoredPath::new("/docs/contracts/AGI-2509001.pdf"),
         );
 
         let doc2 = DocumentPath::new_auto(
@@ -189,7 +190,7 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/docs/ringi/りん議I-25009.pdf"),
+            StoredPath::new("/docs/ringi/りん議I-25009.pdf"),
         );
 
         document_path::create_document_path(pool, &doc1).await?;
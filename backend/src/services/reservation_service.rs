@@ -0,0 +1,578 @@
+//! Number reservation service
+//!
+//! A reservation draws a number from the counter immediately and holds it
+//! until the file is attached (confirm), the user gives it up (release), or
+//! it runs out (expire). Released and expired numbers are kept as a record
+//! of numbers that were issued but never used; they are not handed out again.
+
+use crate::error::{Error, Result};
+use crate::models::{
+    DeptCode, DocumentId, DocumentPath, Reservation, ReservationId, ReservationStatus, SectionCode,
+    StoredPath, TaskId, TypeCode, UserId,
+};
+use crate::services::clock::GenerationClock;
+use crate::services::document_service::SavedDocument;
+use crate::services::generation_service::{self, ScopeOwner};
+use crate::services::{path_service, rule_service};
+use crate::storage::{db, document_path, document_type, reservation};
+use crate::validation;
+use chrono::Duration;
+use sqlx::SqlitePool;
+
+/// How long an unconfirmed reservation is held by default
+pub const DEFAULT_RESERVATION_TTL_HOURS: i64 = 7 * 24;
+
+/// Request parameters for reserving a number
+pub struct ReserveNumberRequest {
+    pub type_code: TypeCode,
+    pub dept_code: DeptCode,
+    pub section_code: SectionCode,
+    pub user_id: UserId,
+    /// Business task the number is drawn for (required by task-scoped counters);
+    /// the confirmed document is filed under it
+    pub business_task: Option<TaskId>,
+    /// How long the number is held before it expires
+    pub ttl: Duration,
+}
+
+/// Reserve the next number of a document type without creating a document
+pub async fn reserve_number(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    request: ReserveNumberRequest,
+) -> Result<Reservation> {
+    if request.ttl <= Duration::zero() {
+        return Err(Error::Validation(
+            "Reservation lifetime must be positive".to_string(),
+        ));
+    }
+
+    let doc_type = document_type::get_document_type(pool, &request.type_code)
+        .await?
+        .ok_or_else(|| {
+            Error::NotFound(format!("Document type '{}' not found", request.type_code.0))
+        })?;
+
+    if !doc_type.active {
+        return Err(Error::Validation(format!(
+            "Document type '{}' is not active",
+            request.type_code.0
+        )));
+    }
+
+    let date = clock.today(doc_type.timezone);
+    let rule = rule_service::effective_rule(pool, &doc_type, date).await?;
+    let now = clock.now();
+    let draft = Reservation {
+        id: ReservationId::new(uuid::Uuid::new_v4().to_string()),
+        document_number: String::new(),
+        document_type: request.type_code,
+        department: request.dept_code,
+        section: request.section_code,
+        user: request.user_id,
+        scope_key: String::new(),
+        counter_value: 0,
+        status: ReservationStatus::Reserved,
+        document_id: None,
+        reserved_at: now,
+        expires_at: now + request.ttl,
+        resolved_at: None,
+        generation_rule_id: rule.id,
+        business_task: request.business_task,
+    };
+
+    // Draw the number and record the reservation in one transaction
+    db::retry_on_busy(|| async {
+        let mut tx = db::begin_write(pool).await?;
+        let mut reserved = draft.clone();
+        loop {
+            let issued = generation_service::issue_document_number(
+                &mut tx,
+                &rule,
+                &reserved.document_type,
+                &reserved.department,
+                &reserved.section,
+                ScopeOwner::new(&reserved.user, reserved.business_task.as_ref()),
+                date,
+            )
+            .await?;
+            reserved.scope_key = issued.scope_key;
+            reserved.counter_value = issued.counter_value;
+            reserved.document_number = issued.document_number;
+
+            // Skip numbers already taken by manually created documents
+            if !document_path::document_number_exists(&mut *tx, &reserved.document_number).await? {
+                break;
+            }
+        }
+        reservation::create_reservation(&mut *tx, &reserved).await?;
+        tx.commit().await?;
+        Ok(reserved)
+    })
+    .await
+}
+
+/// Turn a reservation into a document at `file_path`.
+/// The type's path containment and uniqueness policies are enforced in
+/// strict/reject mode; warn mode returns warnings.
+pub async fn confirm_reservation(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    id: &ReservationId,
+    file_path: StoredPath,
+    business_task: Option<TaskId>,
+) -> Result<SavedDocument> {
+    // Validate file path is absolute
+    validation::validate_absolute_path(&file_path)?;
+
+    let now = clock.now();
+    let reserved = get_open_reservation(pool, clock, id).await?;
+
+    // The number belongs to the task it was drawn for
+    if let Some(task) = &business_task
+        && reserved.business_task.as_ref() != Some(task)
+    {
+        return Err(Error::Validation(format!(
+            "Reservation '{}' was not made for business task '{}'",
+            id.0, task.0
+        )));
+    }
+
+    let doc_type = document_type::get_document_type(pool, &reserved.document_type).await?;
+    let containment_warnings = match &doc_type {
+        Some(doc_type) => path_service::check_containment(doc_type, &file_path)?,
+        None => Vec::new(),
+    };
+    let policy = doc_type
+        .map(|doc_type| doc_type.path_uniqueness)
+        .unwrap_or_default();
+
+    let doc = DocumentPath {
+        id: DocumentId::new(uuid::Uuid::new_v4().to_string()),
+        document_number: reserved.document_number,
+        document_type: reserved.document_type,
+        department: reserved.department,
+        section: reserved.section,
+        business_task: reserved.business_task,
+        user: reserved.user,
+        file_path,
+        created_at: now,
+        updated_at: now,
+        generated: true,
+        deleted: false,
+        generation_rule_id: reserved.generation_rule_id,
+    };
+
+    // Claim the reservation, check the path and create the document in one
+    // transaction: a concurrent release/expiry cannot leave a document behind
+    // an unused number, and a failed insert leaves the number reserved
+    db::retry_on_busy(|| async {
+        let mut tx = db::begin_write(pool).await?;
+        if !reservation::transition_reservation(
+            &mut *tx,
+            id,
+            ReservationStatus::Reserved,
+            ReservationStatus::Confirmed,
+            Some(now),
+        )
+        .await?
+        {
+            return Err(Error::ConcurrentModification);
+        }
+        let mut warnings = containment_warnings.clone();
+        warnings.extend(
+            path_service::check_path_uniqueness(&mut *tx, policy, &doc.file_path, None).await?,
+        );
+        document_path::create_document_path(&mut *tx, &doc).await?;
+        reservation::set_reservation_document(&mut *tx, id, &doc.id).await?;
+        tx.commit().await?;
+        Ok(SavedDocument {
+            document: doc.clone(),
+            warnings,
+        })
+    })
+    .await
+}
+
+/// Give up a reservation; the number is recorded as unused
+pub async fn release_reservation(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    id: &ReservationId,
+) -> Result<Reservation> {
+    let mut reserved = get_open_reservation(pool, clock, id).await?;
+    let now = clock.now();
+
+    if !reservation::transition_reservation(
+        pool,
+        id,
+        ReservationStatus::Reserved,
+        ReservationStatus::Released,
+        Some(now),
+    )
+    .await?
+    {
+        return Err(Error::ConcurrentModification);
+    }
+
+    reserved.status = ReservationStatus::Released;
+    reserved.resolved_at = Some(now);
+    Ok(reserved)
+}
+
+/// Expire every reservation that was not confirmed in time.
+/// Returns the number of reservations expired.
+pub async fn expire_reservations(pool: &SqlitePool, clock: &GenerationClock) -> Result<u64> {
+    reservation::expire_reservations(pool, clock.now()).await
+}
+
+/// List reservations, optionally filtered by status
+pub async fn list_reservations(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    status: Option<ReservationStatus>,
+) -> Result<Vec<Reservation>> {
+    expire_reservations(pool, clock).await?;
+    reservation::list_reservations(pool, status).await
+}
+
+/// List numbers that were reserved but never used (released or expired)
+pub async fn list_unused_reservations(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+) -> Result<Vec<Reservation>> {
+    expire_reservations(pool, clock).await?;
+    reservation::list_unused_reservations(pool).await
+}
+
+/// Load a reservation that can still be confirmed or released,
+/// expiring it on the spot if its time has run out
+async fn get_open_reservation(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    id: &ReservationId,
+) -> Result<Reservation> {
+    let reserved = reservation::get_reservation(pool, id)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Reservation '{}' not found", id.0)))?;
+
+    if reserved.status != ReservationStatus::Reserved {
+        return Err(Error::Validation(format!(
+            "Reservation '{}' is already {}",
+            id.0, reserved.status
+        )));
+    }
+
+    let now = clock.now();
+    if !reserved.is_active(now) {
+        reservation::transition_reservation(
+            pool,
+            id,
+            ReservationStatus::Reserved,
+            ReservationStatus::Expired,
+            Some(now),
+        )
+        .await?;
+        return Err(Error::Validation(format!(
+            "Reservation '{}' expired at {}",
+            id.0,
+            reserved.expires_at.to_rfc3339()
+        )));
+    }
+
+    Ok(reserved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        BusinessTask, CounterScope, Department, DocumentType, PathGenerationRule, PathUniqueness,
+        ScopeDimension, Section, User,
+    };
+    use crate::storage::db::init_db_pool;
+    use crate::storage::{business_task, department, section, user};
+    use chrono::TimeZone;
+
+    async fn setup_test_data(pool: &SqlitePool) -> anyhow::Result<()> {
+        department::create_department(pool, &Department::new('G', "総務")).await?;
+        let sec = Section {
+            code: SectionCode::new('I'),
+            name: "インフラ".to_string(),
+            department: DeptCode::new('G'),
+        };
+        section::create_section(pool, &sec).await?;
+        user::create_user(pool, &User::new("user001", "田川太郎", 'G', 'I')).await?;
+        let doc_type = DocumentType::new(
+            "A",
+            "契約書",
+            "/docs/contracts/",
+            PathGenerationRule::example_agi(),
+        );
+        document_type::create_document_type(pool, &doc_type).await?;
+        Ok(())
+    }
+
+    fn jst_clock(d: u32, h: u32) -> anyhow::Result<GenerationClock> {
+        chrono_tz::Asia::Tokyo
+            .with_ymd_and_hms(2025, 10, d, h, 0, 0)
+            .single()
+            .map(GenerationClock::fixed_local)
+            .ok_or_else(|| anyhow::anyhow!("invalid datetime"))
+    }
+
+    fn reserve_request(ttl_hours: i64) -> ReserveNumberRequest {
+        ReserveNumberRequest {
+            type_code: TypeCode::new("A"),
+            dept_code: DeptCode::new('G'),
+            section_code: SectionCode::new('I'),
+            user_id: UserId::new("user001"),
+            business_task: None,
+            ttl: Duration::hours(ttl_hours),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reserve_and_confirm() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = jst_clock(1, 9)?;
+
+        let reserved = reserve_number(&pool, &clock, reserve_request(48)).await?;
+        assert_eq!(reserved.document_number, "AGI2510001");
+        assert_eq!(reserved.status, ReservationStatus::Reserved);
+
+        // The reserved number is not handed out again
+        let next = generation_service::preview_document_number(
+            &pool,
+            &PathGenerationRule::example_agi(),
+            &TypeCode::new("A"),
+            &DeptCode::new('G'),
+            &SectionCode::new('I'),
+            ScopeOwner::default(),
+            clock.today(None),
+        )
+        .await?;
+        assert_eq!(next.document_number, "AGI2510002");
+
+        let doc = confirm_reservation(
+            &pool,
+            &jst_clock(2, 9)?,
+            &reserved.id,
+            StoredPath::new("/docs/contracts/AGI2510001.pdf"),
+            None,
+        )
+        .await?
+        .document;
+        assert_eq!(doc.document_number, "AGI2510001");
+        assert!(doc.generated);
+
+        let stored = reservation::get_reservation(&pool, &reserved.id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("reservation missing"))?;
+        assert_eq!(stored.status, ReservationStatus::Confirmed);
+        assert_eq!(stored.document_id, Some(doc.id));
+
+        // A confirmed reservation cannot be confirmed twice
+        let again = confirm_reservation(
+            &pool,
+            &clock,
+            &reserved.id,
+            StoredPath::new("/docs/contracts/other.pdf"),
+            None,
+        )
+        .await;
+        assert!(matches!(again, Err(Error::Validation(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_rejects_taken_path() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let doc_type = document_type::get_document_type(&pool, &TypeCode::new("A"))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("type missing"))?
+            .with_path_uniqueness(PathUniqueness::Reject);
+        document_type::update_document_type(&pool, &doc_type).await?;
+        let clock = jst_clock(1, 9)?;
+        let path = StoredPath::new("/docs/contracts/shared.pdf");
+
+        let first = reserve_number(&pool, &clock, reserve_request(24)).await?;
+        confirm_reservation(&pool, &clock, &first.id, path.clone(), None).await?;
+
+        let second = reserve_number(&pool, &clock, reserve_request(24)).await?;
+        let result = confirm_reservation(&pool, &clock, &second.id, path, None).await;
+        assert!(matches!(result, Err(Error::DuplicateFilePath { .. })));
+
+        // The number stays reserved for another attempt
+        let stored = reservation::get_reservation(&pool, &second.id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("reservation missing"))?;
+        assert_eq!(stored.status, ReservationStatus::Reserved);
+        assert!(
+            document_path::get_document_path_by_number(&pool, &second.document_number)
+                .await?
+                .is_none()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reserve_and_confirm_task_scoped_number() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        business_task::create_business_task(&pool, &BusinessTask::new("T001", "移転")).await?;
+        business_task::create_business_task(&pool, &BusinessTask::new("T002", "監査")).await?;
+        let mut rule = PathGenerationRule::example_agi();
+        rule.counter_scope = CounterScope::Custom {
+            dimensions: vec![ScopeDimension::Type, ScopeDimension::Task],
+        };
+        let doc_type = DocumentType::new("K", "工事書", "/docs/works/", rule);
+        document_type::create_document_type(&pool, &doc_type).await?;
+        let clock = jst_clock(1, 9)?;
+        let request = |task: Option<&str>| ReserveNumberRequest {
+            type_code: TypeCode::new("K"),
+            business_task: task.map(TaskId::new),
+            ..reserve_request(24)
+        };
+
+        // The task is part of the scope
+        let result = reserve_number(&pool, &clock, request(None)).await;
+        assert!(matches!(result, Err(Error::Validation(_))));
+        let reserved = reserve_number(&pool, &clock, request(Some("T001"))).await?;
+        assert_eq!(reserved.scope_key, "K_T001");
+        assert_eq!(reserved.business_task, Some(TaskId::new("T001")));
+
+        // Confirming under another task is refused; the number stays reserved
+        let file_path = StoredPath::new("/docs/works/a.pdf");
+        let result = confirm_reservation(
+            &pool,
+            &clock,
+            &reserved.id,
+            file_path.clone(),
+            Some(TaskId::new("T002")),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::Validation(_))));
+
+        let doc = confirm_reservation(&pool, &clock, &reserved.id, file_path, None)
+            .await?
+            .document;
+        assert_eq!(doc.business_task, Some(TaskId::new("T001")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_manual_number_cannot_take_reserved_number() -> anyhow::Result<()> {
+        use crate::services::document_service::{self, ManualDocumentRequest};
+
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = jst_clock(1, 9)?;
+
+        let reserved = reserve_number(&pool, &clock, reserve_request(24)).await?;
+        let result = document_service::create_document_manual(
+            &pool,
+            &clock,
+            ManualDocumentRequest {
+                document_number: reserved.document_number.clone(),
+                type_code: TypeCode::new("A"),
+                dept_code: DeptCode::new('G'),
+                section_code: SectionCode::new('I'),
+                user_id: UserId::new("user001"),
+                file_path: StoredPath::new("/docs/contracts/manual.pdf"),
+                business_task: None,
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(Error::ReservedDocumentNumber(_))));
+
+        // The reservation can still be confirmed
+        confirm_reservation(
+            &pool,
+            &clock,
+            &reserved.id,
+            StoredPath::new("/docs/contracts/a.pdf"),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_release_records_unused_number() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let clock = jst_clock(1, 9)?;
+
+        let reserved = reserve_number(&pool, &clock, reserve_request(24)).await?;
+        let released = release_reservation(&pool, &clock, &reserved.id).await?;
+        assert_eq!(released.status, ReservationStatus::Released);
+
+        let unused = list_unused_reservations(&pool, &clock).await?;
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].document_number, "AGI2510001");
+
+        // Released numbers are not reused
+        let next = reserve_number(&pool, &clock, reserve_request(24)).await?;
+        assert_eq!(next.document_number, "AGI2510002");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired_reservation_cannot_be_confirmed() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+
+        let reserved = reserve_number(&pool, &jst_clock(1, 9)?, reserve_request(24)).await?;
+
+        let result = confirm_reservation(
+            &pool,
+            &jst_clock(2, 10)?,
+            &reserved.id,
+            StoredPath::new("/docs/contracts/late.pdf"),
+            None,
+        )
+        .await;
+        assert!(matches!(result, Err(Error::Validation(_))));
+
+        let stored = reservation::get_reservation(&pool, &reserved.id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("reservation missing"))?;
+        assert_eq!(stored.status, ReservationStatus::Expired);
+        assert!(
+            document_path::get_document_path_by_number(&pool, "AGI2510001")
+                .await?
+                .is_none()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expire_reservations_sweep() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+
+        reserve_number(&pool, &jst_clock(1, 9)?, reserve_request(1)).await?;
+        reserve_number(&pool, &jst_clock(1, 9)?, reserve_request(48)).await?;
+
+        assert_eq!(expire_reservations(&pool, &jst_clock(2, 9)?).await?, 1);
+
+        let active =
+            list_reservations(&pool, &jst_clock(2, 9)?, Some(ReservationStatus::Reserved)).await?;
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].document_number, "AGI2510002");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reserve_rejects_non_positive_ttl() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+
+        let result = reserve_number(&pool, &jst_clock(1, 9)?, reserve_request(0)).await;
+        assert!(matches!(result, Err(Error::Validation(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,266 @@
+//! Generation rule versioning service
+//!
+//! Rule changes only affect documents numbered after the change: every change
+//! is stored as a new immutable version with the business date it takes
+//! effect, and numbers are generated with the version in effect on their date.
+
+use crate::error::{Error, Result};
+use crate::models::{DocumentType, PathGenerationRule, RuleChangeSchedule, RuleVersion, TypeCode};
+use crate::services::clock::GenerationClock;
+use crate::storage::{db, document_type};
+use chrono::NaiveDate;
+use sqlx::SqlitePool;
+
+/// Rule of `doc_type` in effect on the business date `date`
+pub async fn effective_rule(
+    pool: &SqlitePool,
+    doc_type: &DocumentType,
+    date: NaiveDate,
+) -> Result<PathGenerationRule> {
+    let versions = document_type::list_rule_versions(pool, &doc_type.code).await?;
+    Ok(RuleVersion::effective_on(&versions, date)
+        .map(|version| version.rule.clone())
+        .unwrap_or_else(|| doc_type.generation_rule.clone()))
+}
+
+/// List the rule versions of a document type, oldest first
+pub async fn list_rule_versions(
+    pool: &SqlitePool,
+    type_code: &TypeCode,
+) -> Result<Vec<RuleVersion>> {
+    get_document_type(pool, type_code).await?;
+    document_type::list_rule_versions(pool, type_code).await
+}
+
+/// Schedule a new rule version for a document type.
+/// The change must take effect after today's business date so that numbers
+/// already issued today keep their rule, and after every existing version.
+pub async fn schedule_rule_change(
+    pool: &SqlitePool,
+    clock: &GenerationClock,
+    type_code: &TypeCode,
+    rule: PathGenerationRule,
+    schedule: RuleChangeSchedule,
+) -> Result<RuleVersion> {
+    let doc_type = get_document_type(pool, type_code).await?;
+    let today = clock.today(doc_type.timezone);
+    let current = effective_rule(pool, &doc_type, today).await?;
+
+    let effective_from = schedule
+        .effective_date(today, current.fiscal_year_start_month)
+        .ok_or_else(|| {
+            Error::Validation(format!(
+                "Cannot schedule rule change: invalid fiscal year start month {}",
+                current.fiscal_year_start_month
+            ))
+        })?;
+    if effective_from <= today {
+        return Err(Error::Validation(format!(
+            "Rule changes must take effect after {} (got {})",
+            today, effective_from
+        )));
+    }
+
+    // Check and insert in one write transaction so that two concurrent
+    // changes cannot both pass the check
+    db::retry_on_busy(|| async {
+        let mut tx = db::begin_write(pool).await?;
+        let versions = document_type::list_rule_versions(&mut *tx, type_code).await?;
+        if let Some(latest) = versions
+            .iter()
+            .filter_map(|v| v.effective_from)
+            .filter(|from| *from >= effective_from)
+            .max()
+        {
+            return Err(Error::Validation(format!(
+                "A rule version for '{}' already takes effect on {}",
+                type_code.0, latest
+            )));
+        }
+
+        let version =
+            document_type::create_rule_version(&mut *tx, type_code, &rule, effective_from).await?;
+        tx.commit().await?;
+        Ok(version)
+    })
+    .await
+}
+
+async fn get_document_type(pool: &SqlitePool, type_code: &TypeCode) -> Result<DocumentType> {
+    document_type::get_document_type(pool, type_code)
+        .await?
+        .ok_or_else(|| Error::NotFound(format!("Document type '{}' not found", type_code.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CounterScope;
+    use crate::storage::db::init_db_pool;
+    use chrono::TimeZone;
+
+    fn jst_clock(y: i32, m: u32, d: u32) -> anyhow::Result<GenerationClock> {
+        chrono_tz::Asia::Tokyo
+            .with_ymd_and_hms(y, m, d, 9, 0, 0)
+            .single()
+            .map(GenerationClock::fixed_local)
+            .ok_or_else(|| anyhow::anyhow!("invalid datetime"))
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap_or(NaiveDate::MIN)
+    }
+
+    async fn setup(pool: &SqlitePool) -> anyhow::Result<DocumentType> {
+        let rule = PathGenerationRule::example_agi().with_fiscal_year_start_month(4);
+        let doc_type = DocumentType::new("A", "契約書", "/docs/contracts/", rule);
+        document_type::create_document_type(pool, &doc_type).await?;
+        document_type::get_document_type(pool, &doc_type.code)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("document type missing"))
+    }
+
+    #[tokio::test]
+    async fn test_schedule_rule_change_next_month() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        let doc_type = setup(&pool).await?;
+        let new_rule =
+            PathGenerationRule::from_pattern("[TYPE]-[YYYY]-[NNNN]", CounterScope::TypeAndYear)?;
+
+        let version = schedule_rule_change(
+            &pool,
+            &jst_clock(2025, 10, 15)?,
+            &doc_type.code,
+            new_rule.clone(),
+            RuleChangeSchedule::NextMonth,
+        )
+        .await?;
+        assert_eq!(version.effective_from, Some(date(2025, 11, 1)));
+
+        // The old version still applies until the change takes effect
+        let before = effective_rule(&pool, &doc_type, date(2025, 10, 31)).await?;
+        assert_eq!(before.id, doc_type.generation_rule.id);
+        assert_eq!(before.to_pattern(), doc_type.generation_rule.to_pattern());
+
+        let after = effective_rule(&pool, &doc_type, date(2025, 11, 1)).await?;
+        assert_eq!(after.id, Some(version.id));
+        assert_eq!(after.to_pattern(), new_rule.to_pattern());
+
+        let versions = list_rule_versions(&pool, &doc_type.code).await?;
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].effective_from, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_schedule_rule_change_next_fiscal_year() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        let doc_type = setup(&pool).await?;
+
+        let version = schedule_rule_change(
+            &pool,
+            &jst_clock(2025, 10, 15)?,
+            &doc_type.code,
+            PathGenerationRule::example_agi(),
+            RuleChangeSchedule::NextFiscalYear,
+        )
+        .await?;
+        assert_eq!(version.effective_from, Some(date(2026, 4, 1)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_schedule_rule_change_rejects_past_and_overlapping_dates() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        let doc_type = setup(&pool).await?;
+        let clock = jst_clock(2025, 10, 15)?;
+
+        let today = schedule_rule_change(
+            &pool,
+            &clock,
+            &doc_type.code,
+            PathGenerationRule::example_agi(),
+            RuleChangeSchedule::On(date(2025, 10, 15)),
+        )
+        .await;
+        assert!(matches!(today, Err(Error::Validation(_))));
+
+        schedule_rule_change(
+            &pool,
+            &clock,
+            &doc_type.code,
+            PathGenerationRule::example_agi(),
+            RuleChangeSchedule::On(date(2026, 1, 1)),
+        )
+        .await?;
+        let earlier = schedule_rule_change(
+            &pool,
+            &clock,
+            &doc_type.code,
+            PathGenerationRule::example_agi(),
+            RuleChangeSchedule::NextMonth,
+        )
+        .await;
+        assert!(matches!(earlier, Err(Error::Validation(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_rule_changes_for_same_date() -> anyhow::Result<()> {
+        const WRITERS: usize = 8;
+
+        // File-backed database so that writers contend on real SQLite locks
+        let db_file = std::env::temp_dir().join(format!("rules-{}.db", uuid::Uuid::new_v4()));
+        let pool = init_db_pool(&format!("sqlite:{}", db_file.display())).await?;
+        let doc_type = setup(&pool).await?;
+        let clock = jst_clock(2025, 10, 15)?;
+
+        let mut writers = Vec::new();
+        for _ in 0..WRITERS {
+            let pool = pool.clone();
+            let clock = clock.clone();
+            let code = doc_type.code.clone();
+            writers.push(tokio::spawn(async move {
+                schedule_rule_change(
+                    &pool,
+                    &clock,
+                    &code,
+                    PathGenerationRule::example_agi(),
+                    RuleChangeSchedule::NextMonth,
+                )
+                .await
+            }));
+        }
+
+        let mut scheduled = 0;
+        for writer in writers {
+            match writer.await? {
+                Ok(_) => scheduled += 1,
+                Err(Error::Validation(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let versions = list_rule_versions(&pool, &doc_type.code).await?;
+        pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", db_file.display(), suffix));
+        }
+
+        assert_eq!(scheduled, 1);
+        assert_eq!(versions.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rule_versions_are_immutable() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        let doc_type = setup(&pool).await?;
+
+        let result = sqlx::query("UPDATE generation_rules SET counter_digits = 5 WHERE id = ?")
+            .bind(doc_type.generation_rule.id)
+            .execute(&pool)
+            .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+}
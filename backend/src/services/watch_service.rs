@@ -0,0 +1,532 @@
+//! Filesystem watcher for document type root directories
+//!
+//! Root directories of active types with a `WatchPattern` are watched
+//! recursively (resolved to local directories with the same mappings as the
+//! file verifier). A new file whose name matches its type's pattern is
+//! registered as a document created by the watcher's user; a file or
+//! directory renamed inside the watched tree moves the documents at or
+//! under its old path. In dry-run mode the actions are only logged.
+//!
+//! Renames are recognised from paired from/to events (inotify and
+//! ReadDirectoryChangesW); a file moved in from outside the watched tree is
+//! treated as new, one moved out keeps its document's old path. Document
+//! types and patterns are read at startup.
+
+use crate::error::{Error, Result};
+use crate::models::{DocumentPath, DocumentType, StoredPath, User, UserId, WatchMatch};
+use crate::services::clock::GenerationClock;
+use crate::services::document_service::{self, AutoDocumentRequest, ManualDocumentRequest};
+use crate::services::file_check_service::FileVerifier;
+use crate::storage::{document_path, document_type, user};
+use notify::event::{CreateKind, ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use sqlx::SqlitePool;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// What the watcher does with the changes it sees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchMode {
+    /// Register and move documents
+    Apply,
+    /// Only log what would be done
+    DryRun,
+}
+
+impl WatchMode {
+    /// Value of the `FILE_WATCH` setting
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WatchMode::Apply => "on",
+            WatchMode::DryRun => "dry-run",
+        }
+    }
+}
+
+impl fmt::Display for WatchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WatchMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "on" => Ok(WatchMode::Apply),
+            "dry-run" => Ok(WatchMode::DryRun),
+            other => Err(Error::Validation(format!(
+                "Unknown watch mode: {} (expected on or dry-run)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Root directory of a watched document type
+#[derive(Debug, Clone)]
+struct WatchedRoot {
+    doc_type: DocumentType,
+    local: PathBuf,
+}
+
+impl WatchedRoot {
+    /// Stored form of a local path under this root
+    fn stored_path(&self, local: &Path) -> Option<StoredPath> {
+        let rest = local.strip_prefix(&self.local).ok()?;
+        let separator = self.doc_type.root_directory.flavor().separator();
+        let mut path = self.doc_type.root_directory.as_str().to_string();
+        for component in rest.components() {
+            path.push(separator);
+            path.push_str(component.as_os_str().to_str()?);
+        }
+        Some(StoredPath::new(path))
+    }
+}
+
+/// Turns filesystem events under the watched roots into document changes
+pub struct FileWatcher {
+    pool: SqlitePool,
+    clock: GenerationClock,
+    mode: WatchMode,
+    user: User,
+    roots: Vec<WatchedRoot>,
+    /// Source of a rename whose destination event has not arrived yet
+    pending_rename: Option<(Option<usize>, PathBuf)>,
+}
+
+impl FileWatcher {
+    /// Load the watched types; documents are created as `user_id`
+    pub async fn new(
+        pool: SqlitePool,
+        clock: GenerationClock,
+        mode: WatchMode,
+        user_id: &UserId,
+        verifier: &FileVerifier,
+    ) -> Result<Self> {
+        let user = user::get_user(&pool, user_id)
+            .await?
+            .ok_or_else(|| Error::UserNotFound(user_id.0.clone()))?;
+
+        let mut roots = Vec::new();
+        for doc_type in document_type::list_active_document_types(&pool).await? {
+            if doc_type.watch_pattern.is_none() {
+                continue;
+            }
+            match verifier.local_path(&doc_type.root_directory) {
+                Some(local) if local.is_dir() => roots.push(WatchedRoot { doc_type, local }),
+                _ => tracing::warn!(
+                    "Not watching document type {}: root directory {} is not available locally",
+                    doc_type.code.0,
+                    doc_type.root_directory
+                ),
+            }
+        }
+        // Deepest roots first, so nested types win over their parents
+        roots.sort_by_key(|root| std::cmp::Reverse(root.local.components().count()));
+
+        Ok(Self {
+            pool,
+            clock,
+            mode,
+            user,
+            roots,
+            pending_rename: None,
+        })
+    }
+
+    /// Local directories to watch (nested roots are covered by their parent)
+    pub fn watch_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self.roots.iter().map(|root| root.local.clone()).collect();
+        paths.sort();
+        paths.dedup();
+        let all = paths.clone();
+        paths.retain(|path| {
+            !all.iter()
+                .any(|other| other != path && path.starts_with(other))
+        });
+        paths
+    }
+
+    /// Watch the roots until the event stream ends
+    pub async fn run(mut self) -> Result<()> {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })
+        .map_err(watch_error)?;
+        for path in self.watch_paths() {
+            watcher
+                .watch(&path, RecursiveMode::Recursive)
+                .map_err(watch_error)?;
+            tracing::info!("Watching {} ({})", path.display(), self.mode);
+        }
+
+        while let Some(event) = rx.recv().await {
+            match event {
+                Ok(event) => self.handle_event(event).await,
+                Err(e) => tracing::warn!("File watcher error: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Act on one filesystem event; failures are logged, not returned
+    pub async fn handle_event(&mut self, event: Event) {
+        let tracker = event.tracker();
+        let result = match (event.kind, event.paths.as_slice()) {
+            (EventKind::Create(CreateKind::File | CreateKind::Any), [path]) => {
+                self.file_created(path).await
+            }
+            (EventKind::Modify(ModifyKind::Name(RenameMode::From)), [path]) => {
+                if let Some((_, previous)) = self.pending_rename.replace((tracker, path.clone())) {
+                    tracing::debug!("{} moved out of the watched tree", previous.display());
+                }
+                Ok(())
+            }
+            (EventKind::Modify(ModifyKind::Name(RenameMode::To)), [path]) => {
+                match self.pending_rename.take() {
+                    Some((from_tracker, from)) if from_tracker == tracker => {
+                        self.file_renamed(&from, path).await
+                    }
+                    unpaired => {
+                        self.pending_rename = unpaired;
+                        self.file_created(path).await
+                    }
+                }
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            tracing::warn!("File watcher: {}", e);
+        }
+    }
+
+    /// Register a new file if its name matches its type's pattern
+    async fn file_created(&self, local: &Path) -> Result<()> {
+        if !local.is_file() {
+            return Ok(());
+        }
+        let Some((doc_type, file_path, number)) = self.match_file(local) else {
+            return Ok(());
+        };
+
+        let existing = document_path::live_documents_at_path(&self.pool, &file_path, None).await?;
+        if !existing.is_empty() {
+            tracing::debug!(
+                "{} is already registered as {}",
+                file_path,
+                existing.join(", ")
+            );
+            return Ok(());
+        }
+
+        if self.mode == WatchMode::DryRun {
+            match &number {
+                WatchMatch::Manual(number) => tracing::info!(
+                    "[dry-run] Would register {} as {} document {}",
+                    file_path,
+                    doc_type.code.0,
+                    number
+                ),
+                WatchMatch::Auto => tracing::info!(
+                    "[dry-run] Would register {} as a new {} document",
+                    file_path,
+                    doc_type.code.0
+                ),
+            }
+            return Ok(());
+        }
+
+        let document = match number {
+            WatchMatch::Manual(number) => {
+                let saved = document_service::create_document_manual(
+                    &self.pool,
+                    &self.clock,
+                    ManualDocumentRequest {
+                        document_number: number,
+                        type_code: doc_type.code.clone(),
+                        dept_code: self.user.department,
+                        section_code: self.user.section,
+                        user_id: self.user.id.clone(),
+                        file_path,
+                        business_task: None,
+                    },
+                )
+                .await?;
+                for warning in &saved.warnings {
+                    tracing::warn!("{}", warning);
+                }
+                saved.document
+            }
+            WatchMatch::Auto => {
+                let saved = document_service::register_existing_file(
+                    &self.pool,
+                    &self.clock,
+                    AutoDocumentRequest {
+                        type_code: doc_type.code.clone(),
+                        dept_code: self.user.department,
+                        section_code: self.user.section,
+                        user_id: self.user.id.clone(),
+                        file_path,
+                        business_task: None,
+                        extension: None,
+                    },
+                )
+                .await?;
+                for warning in &saved.warnings {
+                    tracing::warn!("{}", warning);
+                }
+                saved.document
+            }
+        };
+        tracing::info!(
+            "Registered {} as {}",
+            document.file_path,
+            document.document_number
+        );
+        Ok(())
+    }
+
+    /// Move the documents at or under `from` to `to`. A renamed file
+    /// without a document is treated as new (e.g. `scan.tmp` -> `scan.pdf`).
+    async fn file_renamed(&self, from: &Path, to: &Path) -> Result<()> {
+        let (Some(old_path), Some(new_path)) = (self.stored_path(from), self.stored_path(to))
+        else {
+            return Ok(());
+        };
+
+        let moved: Vec<DocumentPath> = document_path::list_document_paths(&self.pool, false)
+            .await?
+            .into_iter()
+            .filter(|doc| doc.file_path.is_within(&old_path))
+            .collect();
+        if moved.is_empty() {
+            return self.file_created(to).await;
+        }
+
+        for document in moved {
+            let rest = document
+                .file_path
+                .as_str()
+                .get(old_path.as_str().len()..)
+                .unwrap_or_default();
+            let target = StoredPath::new(format!("{}{}", new_path, rest));
+            if self.mode == WatchMode::DryRun {
+                tracing::info!(
+                    "[dry-run] Would move {} from {} to {}",
+                    document.document_number,
+                    document.file_path,
+                    target
+                );
+                continue;
+            }
+
+            let saved = document_service::update_document_path(
+                &self.pool,
+                &self.clock,
+                &document.id,
+                target,
+            )
+            .await?;
+            for warning in &saved.warnings {
+                tracing::warn!("{}", warning);
+            }
+            tracing::info!(
+                "Moved {} from {} to {}",
+                document.document_number,
+                document.file_path,
+                saved.document.file_path
+            );
+        }
+        Ok(())
+    }
+
+    /// Stored form of a local path, via the deepest root containing it
+    fn stored_path(&self, local: &Path) -> Option<StoredPath> {
+        self.roots.iter().find_map(|root| root.stored_path(local))
+    }
+
+    /// Type, stored path and numbering for a new file; the deepest root
+    /// whose type's pattern matches the file name wins
+    fn match_file(&self, local: &Path) -> Option<(&DocumentType, StoredPath, WatchMatch)> {
+        let file_name = local.file_name()?.to_str()?;
+        self.roots.iter().find_map(|root| {
+            let file_path = root.stored_path(local)?;
+            let number = root.doc_type.watch_pattern.as_ref()?.matches(file_name)?;
+            Some((&root.doc_type, file_path, number))
+        })
+    }
+}
+
+fn watch_error(e: notify::Error) -> Error {
+    Error::Internal(format!("File watcher: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Department, DeptCode, PathGenerationRule, Section, SectionCode, TypeCode};
+    use crate::storage::db::init_db_pool;
+    use crate::storage::{department, section};
+    use std::fs;
+
+    async fn setup(pool: &SqlitePool) -> anyhow::Result<()> {
+        department::create_department(pool, &Department::new('G', "総務")).await?;
+        section::create_section(
+            pool,
+            &Section {
+                code: SectionCode::new('I'),
+                name: "インフラ".to_string(),
+                department: DeptCode::new('G'),
+            },
+        )
+        .await?;
+        user::create_user(pool, &User::new("user001", "田川太郎", 'G', 'I')).await?;
+        let contracts = DocumentType::new(
+            "A",
+            "契約書",
+            "/docs/contracts/",
+            PathGenerationRule::example_agi(),
+        )
+        .with_watch_pattern("{number}.pdf".parse()?);
+        document_type::create_document_type(pool, &contracts).await?;
+        let scans = DocumentType::new(
+            "B",
+            "スキャン",
+            "/docs/contracts/",
+            PathGenerationRule::example_agi(),
+        )
+        .with_watch_pattern("*.tif".parse()?);
+        document_type::create_document_type(pool, &scans).await?;
+        Ok(())
+    }
+
+    fn created(path: PathBuf) -> Event {
+        Event::new(EventKind::Create(CreateKind::File)).add_path(path)
+    }
+
+    fn renamed(mode: RenameMode, tracker: usize, path: PathBuf) -> Event {
+        Event::new(EventKind::Modify(ModifyKind::Name(mode)))
+            .set_tracker(tracker)
+            .add_path(path)
+    }
+
+    async fn path_of(pool: &SqlitePool, number: &str) -> anyhow::Result<Option<String>> {
+        Ok(document_path::get_document_path_by_number(pool, number)
+            .await?
+            .map(|doc| doc.file_path.to_string()))
+    }
+
+    #[tokio::test]
+    async fn test_watcher_registers_and_moves_documents() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup(&pool).await?;
+        let share = std::env::temp_dir().join(format!("watch-{}", uuid::Uuid::new_v4()));
+        let root = share.join("contracts");
+        fs::create_dir_all(root.join("inbox"))?;
+        let verifier = FileVerifier::from_mappings(&format!("/docs={}", share.display()))?;
+        let clock = GenerationClock::default();
+        let user_id = UserId::new("user001");
+
+        let mut dry_run = FileWatcher::new(
+            pool.clone(),
+            clock.clone(),
+            WatchMode::DryRun,
+            &user_id,
+            &verifier,
+        )
+        .await?;
+        assert_eq!(dry_run.watch_paths(), vec![root.clone()]);
+        fs::write(root.join("X-100.pdf"), b"contract")?;
+        dry_run.handle_event(created(root.join("X-100.pdf"))).await;
+        assert_eq!(path_of(&pool, "X-100").await?, None);
+
+        let mut watcher =
+            FileWatcher::new(pool.clone(), clock, WatchMode::Apply, &user_id, &verifier).await?;
+
+        // Manual number from the file name; auto number for the other type
+        watcher.handle_event(created(root.join("X-100.pdf"))).await;
+        assert_eq!(
+            path_of(&pool, "X-100").await?.as_deref(),
+            Some("/docs/contracts/X-100.pdf")
+        );
+        fs::write(root.join("inbox").join("scan.tif"), b"scan")?;
+        watcher
+            .handle_event(created(root.join("inbox").join("scan.tif")))
+            .await;
+        fs::write(root.join("notes.txt"), b"ignored")?;
+        watcher.handle_event(created(root.join("notes.txt"))).await;
+        let documents = document_path::list_document_paths(&pool, false).await?;
+        assert_eq!(documents.len(), 2);
+        let scan = documents
+            .iter()
+            .find(|doc| doc.document_type == TypeCode::new("B"))
+            .ok_or_else(|| anyhow::anyhow!("scan not registered"))?;
+        assert!(scan.generated);
+        assert_eq!(scan.file_path.as_str(), "/docs/contracts/inbox/scan.tif");
+
+        // Renaming a file, then its directory, moves the documents
+        fs::rename(root.join("X-100.pdf"), root.join("inbox").join("X-100.pdf"))?;
+        watcher
+            .handle_event(renamed(RenameMode::From, 1, root.join("X-100.pdf")))
+            .await;
+        watcher
+            .handle_event(renamed(
+                RenameMode::To,
+                1,
+                root.join("inbox").join("X-100.pdf"),
+            ))
+            .await;
+        assert_eq!(
+            path_of(&pool, "X-100").await?.as_deref(),
+            Some("/docs/contracts/inbox/X-100.pdf")
+        );
+        fs::rename(root.join("inbox"), root.join("2025"))?;
+        watcher
+            .handle_event(renamed(RenameMode::From, 2, root.join("inbox")))
+            .await;
+        watcher
+            .handle_event(renamed(RenameMode::To, 2, root.join("2025")))
+            .await;
+        assert_eq!(
+            path_of(&pool, "X-100").await?.as_deref(),
+            Some("/docs/contracts/2025/X-100.pdf")
+        );
+        assert_eq!(
+            path_of(&pool, &scan.document_number).await?.as_deref(),
+            Some("/docs/contracts/2025/scan.tif")
+        );
+
+        // A file moved in from outside is new; events for it are not repeated
+        fs::write(root.join("Y-7.pdf"), b"moved in")?;
+        watcher
+            .handle_event(renamed(RenameMode::To, 3, root.join("Y-7.pdf")))
+            .await;
+        watcher.handle_event(created(root.join("Y-7.pdf"))).await;
+        assert_eq!(
+            path_of(&pool, "Y-7").await?.as_deref(),
+            Some("/docs/contracts/Y-7.pdf")
+        );
+        assert_eq!(
+            document_path::list_document_paths(&pool, false)
+                .await?
+                .len(),
+            3
+        );
+
+        fs::remove_dir_all(&share)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_watch_mode_roundtrip() -> Result<()> {
+        for mode in [WatchMode::Apply, WatchMode::DryRun] {
+            assert_eq!(mode.as_str().parse::<WatchMode>()?, mode);
+        }
+        assert!("off".parse::<WatchMode>().is_err());
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 //! Business Task storage operations
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::models::{BusinessTask, DeptCode, SectionCode, TaskId};
+use crate::storage::db;
 use sqlx::SqlitePool;
 
 /// Create a new business task
@@ -22,7 +23,11 @@ pub async fn create_business_task(pool: &SqlitePool, task: &BusinessTask) -> Res
         active
     )
     .execute(pool)
-    .await?;
+    .await
+    .map_err(db::on_unique_violation(|| Error::AlreadyExists {
+        entity: "Business task",
+        key: task.id.0.clone(),
+    }))?;
 
     Ok(())
 }
@@ -1,59 +1,70 @@
 //! Counter management for auto-increment document numbers
 
-use crate::error::Result;
-use sqlx::SqlitePool;
+use crate::error::{Error, Result};
+use crate::models::Counter;
+use crate::storage::db;
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
 
 /// Get the next counter value for a given scope key
 /// This function increments the counter atomically and returns the new value
 pub async fn get_next_counter(pool: &SqlitePool, scope_key: &str) -> Result<i64> {
-    let mut tx = pool.begin().await?;
+    get_next_counter_with_limit(pool, scope_key, i64::MAX).await
+}
+
+/// Get the next counter value, refusing to advance past `max_value`.
+/// When the scope is exhausted, returns `Error::CounterExhausted` and leaves the counter untouched.
+pub async fn get_next_counter_with_limit(
+    pool: &SqlitePool,
+    scope_key: &str,
+    max_value: i64,
+) -> Result<i64> {
+    db::retry_on_busy(|| async {
+        let mut tx = db::begin_write(pool).await?;
+        let value = increment_counter(&mut tx, scope_key, 1, max_value).await?;
+        tx.commit().await?;
+        Ok(value)
+    })
+    .await
+}
+
+/// Atomically advance the counter for `scope_key` inside the caller's transaction
+/// and return the new value, so that a rollback also returns the number.
+/// A new scope starts at `first_value`; the values below it count as seeded.
+/// Returns `Error::CounterExhausted` once the counter has reached `max_value`.
+pub async fn increment_counter(
+    conn: &mut SqliteConnection,
+    scope_key: &str,
+    first_value: i64,
+    max_value: i64,
+) -> Result<i64> {
+    let exhausted = || Error::CounterExhausted {
+        scope_key: scope_key.to_string(),
+        max_value,
+    };
+    let first_value = first_value.max(1);
+    if max_value < first_value {
+        return Err(exhausted());
+    }
 
-    // Try to get existing counter
-    let existing = sqlx::query!(
+    // Insert at the first value or increment in a single statement; the WHERE
+    // clause suppresses the update (and the returned row) once the limit is reached
+    let row = sqlx::query!(
         r#"
-        SELECT current_value
-        FROM counters
-        WHERE scope_key = ?
+        INSERT INTO counters (scope_key, current_value, seeded_value)
+        VALUES (?1, ?2, ?2 - 1)
+        ON CONFLICT(scope_key) DO UPDATE
+        SET current_value = current_value + 1, updated_at = CURRENT_TIMESTAMP
+        WHERE current_value < ?3
+        RETURNING current_value
         "#,
-        scope_key
+        scope_key,
+        first_value,
+        max_value
     )
-    .fetch_optional(&mut *tx)
+    .fetch_optional(conn)
     .await?;
 
-    let next_value = match existing {
-        Some(row) => {
-            // Increment existing counter
-            let next = row.current_value + 1;
-            sqlx::query!(
-                r#"
-                UPDATE counters
-                SET current_value = ?, updated_at = CURRENT_TIMESTAMP
-                WHERE scope_key = ?
-                "#,
-                next,
-                scope_key
-            )
-            .execute(&mut *tx)
-            .await?;
-            next
-        }
-        None => {
-            // Initialize new counter
-            sqlx::query!(
-                r#"
-                INSERT INTO counters (scope_key, current_value)
-                VALUES (?, 1)
-                "#,
-                scope_key
-            )
-            .execute(&mut *tx)
-            .await?;
-            1
-        }
-    };
-
-    tx.commit().await?;
-    Ok(next_value)
+    row.map(|r| r.current_value).ok_or_else(exhausted)
 }
 
 /// Get current counter value without incrementing (for preview/testing)
@@ -90,6 +101,72 @@ pub async fn reset_counter(pool: &SqlitePool, scope_key: &str, value: i64) -> Re
     Ok(())
 }
 
+/// Seed a counter so that the next number drawn is `current_value + 1`,
+/// marking every value up to `current_value` as issued outside the system.
+/// Only applies while the counter still holds `expected` (None = no counter yet);
+/// returns false when it was changed concurrently.
+pub async fn seed_counter(
+    pool: &SqlitePool,
+    scope_key: &str,
+    expected: Option<i64>,
+    current_value: i64,
+) -> Result<bool> {
+    let result = match expected {
+        Some(expected) => {
+            sqlx::query!(
+                r#"
+                UPDATE counters
+                SET current_value = ?1, seeded_value = ?1, updated_at = CURRENT_TIMESTAMP
+                WHERE scope_key = ?2 AND current_value = ?3
+                "#,
+                current_value,
+                scope_key,
+                expected
+            )
+            .execute(pool)
+            .await?
+        }
+        None => {
+            sqlx::query!(
+                r#"
+                INSERT INTO counters (scope_key, current_value, seeded_value)
+                VALUES (?1, ?2, ?2)
+                ON CONFLICT(scope_key) DO NOTHING
+                "#,
+                scope_key,
+                current_value
+            )
+            .execute(pool)
+            .await?
+        }
+    };
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// Raise the counter for `scope_key` to `value`, creating it if needed.
+/// Never lowers a counter; returns false when it was already at or above `value`.
+pub async fn raise_counter<'e, E>(executor: E, scope_key: &str, value: i64) -> Result<bool>
+where
+    E: SqliteExecutor<'e>,
+{
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO counters (scope_key, current_value)
+        VALUES (?1, ?2)
+        ON CONFLICT(scope_key) DO UPDATE
+        SET current_value = excluded.current_value, updated_at = CURRENT_TIMESTAMP
+        WHERE current_value < excluded.current_value
+        "#,
+        scope_key,
+        value
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
 /// Delete a counter (for cleanup)
 pub async fn delete_counter(pool: &SqlitePool, scope_key: &str) -> Result<()> {
     sqlx::query!(
@@ -106,10 +183,10 @@ pub async fn delete_counter(pool: &SqlitePool, scope_key: &str) -> Result<()> {
 }
 
 /// List all counters (for administrative/debugging purposes)
-pub async fn list_counters(pool: &SqlitePool) -> Result<Vec<(String, i64)>> {
+pub async fn list_counters(pool: &SqlitePool) -> Result<Vec<Counter>> {
     let rows = sqlx::query!(
         r#"
-        SELECT scope_key, current_value
+        SELECT scope_key, current_value, seeded_value
         FROM counters
         ORDER BY scope_key
         "#
@@ -119,7 +196,11 @@ pub async fn list_counters(pool: &SqlitePool) -> Result<Vec<(String, i64)>> {
 
     let counters = rows
         .into_iter()
-        .map(|r| (r.scope_key, r.current_value))
+        .map(|r| Counter {
+            scope_key: r.scope_key,
+            current_value: r.current_value,
+            seeded_value: r.seeded_value,
+        })
         .collect();
 
     Ok(counters)
@@ -188,6 +269,30 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_get_next_counter_with_limit_blocks_when_exhausted() -> Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        assert_eq!(
+            get_next_counter_with_limit(&pool, "test_scope", 2).await?,
+            1
+        );
+        assert_eq!(
+            get_next_counter_with_limit(&pool, "test_scope", 2).await?,
+            2
+        );
+
+        let result = get_next_counter_with_limit(&pool, "test_scope", 2).await;
+        assert!(matches!(
+            result,
+            Err(Error::CounterExhausted { max_value: 2, .. })
+        ));
+
+        // The rejected attempt must not consume a value
+        assert_eq!(get_current_counter(&pool, "test_scope").await?, Some(2));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_different_scopes_independent() -> Result<()> {
         let pool = init_db_pool("sqlite::memory:").await?;
@@ -211,9 +316,54 @@ mod tests {
         get_next_counter(&pool, "scope_b").await?;
 
         let counters = list_counters(&pool).await?;
-        assert_eq!(counters.len(), 2);
-        assert!(counters.contains(&("scope_a".to_string(), 1)));
-        assert!(counters.contains(&("scope_b".to_string(), 2)));
+        let values: Vec<(&str, i64)> = counters
+            .iter()
+            .map(|c| (c.scope_key.as_str(), c.current_value))
+            .collect();
+        assert_eq!(values, [("scope_a", 1), ("scope_b", 2)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_increment_counter_starts_at_first_value() -> Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        let mut conn = pool.acquire().await?;
+
+        assert_eq!(increment_counter(&mut conn, "scope", 43, 999).await?, 43);
+        assert_eq!(increment_counter(&mut conn, "scope", 43, 999).await?, 44);
+        drop(conn);
+
+        let counters = list_counters(&pool).await?;
+        assert_eq!(counters[0].seeded_value, 42);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_seed_counter_checks_expected_value() -> Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        assert!(seed_counter(&pool, "scope", None, 42).await?);
+        assert!(!seed_counter(&pool, "scope", None, 50).await?);
+        assert!(!seed_counter(&pool, "scope", Some(41), 50).await?);
+        assert_eq!(get_next_counter(&pool, "scope").await?, 43);
+
+        assert!(seed_counter(&pool, "scope", Some(43), 50).await?);
+        assert_eq!(get_current_counter(&pool, "scope").await?, Some(50));
+        assert_eq!(list_counters(&pool).await?[0].seeded_value, 50);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_raise_counter_never_lowers() -> Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        assert!(raise_counter(&pool, "scope", 5).await?);
+        assert!(!raise_counter(&pool, "scope", 3).await?);
+        assert!(!raise_counter(&pool, "scope", 5).await?);
+        assert_eq!(get_current_counter(&pool, "scope").await?, Some(5));
+
+        assert!(raise_counter(&pool, "scope", 8).await?);
+        assert_eq!(get_next_counter(&pool, "scope").await?, 9);
         Ok(())
     }
 }
@@ -1,11 +1,18 @@
 //! Database storage initialization and configuration
 
-use sqlx::ConnectOptions;
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::sqlite::{Sqlite, SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::{ConnectOptions, Transaction};
+use std::future::Future;
 use std::str::FromStr;
 use std::time::Duration;
 
-use crate::error::Result;
+use crate::error::{Error, Result};
+
+/// Attempts made by `retry_on_busy` before giving up
+pub const MAX_BUSY_ATTEMPTS: u32 = 5;
+
+/// Delay before the first retry; doubled on every further attempt
+const BUSY_RETRY_BASE_DELAY: Duration = Duration::from_millis(20);
 
 /// Initialize database connection pool with WAL mode
 pub async fn init_db_pool(database_url: &str) -> Result<SqlitePool> {
@@ -27,6 +34,67 @@ pub async fn init_db_pool(database_url: &str) -> Result<SqlitePool> {
     Ok(pool)
 }
 
+/// Begin a write transaction.
+/// `BEGIN IMMEDIATE` takes the write lock up front, so concurrent writers queue
+/// on the busy timeout instead of failing when a read lock is upgraded.
+pub async fn begin_write(pool: &SqlitePool) -> Result<Transaction<'static, Sqlite>> {
+    Ok(pool.begin_with("BEGIN IMMEDIATE").await?)
+}
+
+/// Whether the error is SQLite reporting the database as busy or locked
+/// (SQLITE_BUSY / SQLITE_LOCKED, including their extended codes)
+pub fn is_busy(error: &Error) -> bool {
+    let Error::Database(sqlx::Error::Database(db_error)) = error else {
+        return false;
+    };
+    db_error
+        .code()
+        .and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| matches!(code & 0xff, 5 | 6))
+}
+
+/// Whether the error is SQLite rejecting a row that violates a UNIQUE or
+/// PRIMARY KEY constraint
+pub fn is_unique_violation(error: &sqlx::Error) -> bool {
+    matches!(error, sqlx::Error::Database(db_error) if db_error.is_unique_violation())
+}
+
+/// Error mapper for inserts: a unique constraint violation becomes the typed
+/// `conflict` error, anything else stays a database error.
+/// Use as `.await.map_err(db::on_unique_violation(|| ...))`.
+pub fn on_unique_violation(conflict: impl FnOnce() -> Error) -> impl FnOnce(sqlx::Error) -> Error {
+    move |error| {
+        if is_unique_violation(&error) {
+            conflict()
+        } else {
+            Error::Database(error)
+        }
+    }
+}
+
+/// Run a write transaction, retrying with exponential backoff while the
+/// database stays busy past the busy timeout. `operation` must start its own
+/// transaction so that every attempt begins from a clean state.
+pub async fn retry_on_busy<T, F, Fut>(mut operation: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut delay = BUSY_RETRY_BASE_DELAY;
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Err(e) if is_busy(&e) && attempt < MAX_BUSY_ATTEMPTS => {
+                tracing::debug!("Database busy (attempt {}), retrying: {}", attempt, e);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -37,4 +105,57 @@ mod tests {
         assert!(pool.acquire().await.is_ok());
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_retry_on_busy_passes_through_other_errors() {
+        let mut attempts = 0;
+        let result: Result<()> = retry_on_busy(|| {
+            attempts += 1;
+            async { Err(Error::Validation("not busy".to_string())) }
+        })
+        .await;
+        assert!(matches!(result, Err(Error::Validation(_))));
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_begin_write_commits() -> Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        let mut tx = begin_write(&pool).await?;
+        sqlx::query("INSERT INTO counters (scope_key, current_value) VALUES ('tx_scope', 7)")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        let value: i64 =
+            sqlx::query_scalar("SELECT current_value FROM counters WHERE scope_key = 'tx_scope'")
+                .fetch_one(&pool)
+                .await?;
+        assert_eq!(value, 7);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_on_unique_violation_maps_conflicts_only() -> Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        let insert = "INSERT INTO counters (scope_key, current_value) VALUES ('scope', 1)";
+        sqlx::query(insert).execute(&pool).await?;
+
+        let conflict = || Error::AlreadyExists {
+            entity: "Counter",
+            key: "scope".to_string(),
+        };
+        let duplicate = sqlx::query(insert)
+            .execute(&pool)
+            .await
+            .map_err(on_unique_violation(conflict));
+        assert!(matches!(duplicate, Err(Error::AlreadyExists { .. })));
+
+        let other = sqlx::query("INSERT INTO missing_table VALUES (1)")
+            .execute(&pool)
+            .await
+            .map_err(on_unique_violation(conflict));
+        assert!(matches!(other, Err(Error::Database(_))));
+        Ok(())
+    }
 }
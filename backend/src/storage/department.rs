@@ -1,7 +1,8 @@
 //! Department storage operations
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::models::{Department, DeptCode, SectionCode};
+use crate::storage::db;
 use sqlx::SqlitePool;
 
 /// Create a new department
@@ -17,7 +18,11 @@ pub async fn create_department(pool: &SqlitePool, dept: &Department) -> Result<(
         dept.name
     )
     .execute(pool)
-    .await?;
+    .await
+    .map_err(db::on_unique_violation(|| Error::AlreadyExists {
+        entity: "Department",
+        key: code.clone(),
+    }))?;
 
     Ok(())
 }
@@ -210,4 +215,17 @@ mod tests {
         assert!(retrieved.is_none());
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_create_department_twice_is_conflict() -> Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        create_department(&pool, &Department::new('G', "総務")).await?;
+        let result = create_department(&pool, &Department::new('G', "経理")).await;
+        assert!(matches!(
+            result,
+            Err(Error::AlreadyExists { entity: "Department", ref key }) if key == "G"
+        ));
+        Ok(())
+    }
 }
@@ -1,15 +1,22 @@
 //! Document Path storage operations
 
-use crate::error::Result;
-use crate::models::{DeptCode, DocumentId, DocumentPath, SectionCode, TaskId, TypeCode, UserId};
+use crate::error::{Error, Result};
+use crate::models::{
+    DeptCode, DocumentId, DocumentPath, SectionCode, StoredPath, TaskId, TypeCode, UserId,
+};
+use crate::storage::db;
 use chrono::{DateTime, Utc};
-use sqlx::SqlitePool;
-use std::path::PathBuf;
-
-/// Create a new document path
-pub async fn create_document_path(pool: &SqlitePool, doc: &DocumentPath) -> Result<()> {
+use sqlx::{SqliteExecutor, SqlitePool};
+
+/// Create a new document path.
+/// Accepts a pool or an open transaction (`&mut *tx`).
+pub async fn create_document_path<'e, E>(executor: E, doc: &DocumentPath) -> Result<()>
+where
+    E: SqliteExecutor<'e>,
+{
     let task_id = doc.business_task.as_ref().map(|t| t.0.clone());
-    let file_path_str = doc.file_path.to_string_lossy().to_string();
+    let file_path_str = doc.file_path.to_string();
+    let path_key = doc.file_path.comparison_key();
     let dept_code = doc.department.0.to_string();
     let section_code = doc.section.0.to_string();
     let created_at = doc.created_at.to_rfc3339();
@@ -21,9 +28,10 @@ pub async fn create_document_path(pool: &SqlitePool, doc: &DocumentPath) -> Resu
         r#"
         INSERT INTO documents (
             id, document_number, document_type_code, department_code, section_code,
-            business_task_id, user_id, file_path, created_at, updated_at, generated, deleted
+            business_task_id, user_id, file_path, created_at, updated_at, generated, deleted,
+            generation_rule_id, path_key
         )
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         doc.id.0,
         doc.document_number,
@@ -36,20 +44,45 @@ pub async fn create_document_path(pool: &SqlitePool, doc: &DocumentPath) -> Resu
         created_at,
         updated_at,
         generated,
-        deleted
+        deleted,
+        doc.generation_rule_id,
+        path_key
     )
-    .execute(pool)
-    .await?;
+    .execute(executor)
+    .await
+    .map_err(db::on_unique_violation(|| {
+        Error::DuplicateDocumentNumber(doc.document_number.clone())
+    }))?;
 
     Ok(())
 }
 
+/// Whether a document (live or logically deleted) already uses `number`
+pub async fn document_number_exists<'e, E>(executor: E, number: &str) -> Result<bool>
+where
+    E: SqliteExecutor<'e>,
+{
+    let row = sqlx::query!(
+        r#"
+        SELECT id
+        FROM documents
+        WHERE document_number = ?
+        "#,
+        number
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(row.is_some())
+}
+
 /// Get a document path by ID
 pub async fn get_document_path(pool: &SqlitePool, id: &DocumentId) -> Result<Option<DocumentPath>> {
     let row = sqlx::query!(
         r#"
         SELECT id, document_number, document_type_code, department_code, section_code,
-               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted
+               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted,
+               generation_rule_id
         FROM documents
         WHERE id = ?
         "#,
@@ -71,7 +104,7 @@ pub async fn get_document_path(pool: &SqlitePool, id: &DocumentId) -> Result<Opt
                 section: SectionCode::new(section_char),
                 business_task: r.business_task_id.map(TaskId::new),
                 user: UserId::new(r.user_id),
-                file_path: PathBuf::from(r.file_path),
+                file_path: StoredPath::new(r.file_path),
                 created_at: DateTime::parse_from_rfc3339(&r.created_at)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
@@ -80,6 +113,7 @@ pub async fn get_document_path(pool: &SqlitePool, id: &DocumentId) -> Result<Opt
                     .unwrap_or_else(|_| Utc::now()),
                 generated: r.generated != 0,
                 deleted: r.deleted != 0,
+                generation_rule_id: r.generation_rule_id,
             }))
         }
         None => Ok(None),
@@ -94,7 +128,8 @@ pub async fn get_document_path_by_number(
     let row = sqlx::query!(
         r#"
         SELECT id, document_number, document_type_code, department_code, section_code,
-               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted
+               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted,
+               generation_rule_id
         FROM documents
         WHERE document_number = ?
         "#,
@@ -116,7 +151,7 @@ pub async fn get_document_path_by_number(
                 section: SectionCode::new(section_char),
                 business_task: r.business_task_id.map(TaskId::new),
                 user: UserId::new(r.user_id),
-                file_path: PathBuf::from(r.file_path),
+                file_path: StoredPath::new(r.file_path),
                 created_at: DateTime::parse_from_rfc3339(&r.created_at)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
@@ -125,6 +160,7 @@ pub async fn get_document_path_by_number(
                     .unwrap_or_else(|_| Utc::now()),
                 generated: r.generated != 0,
                 deleted: r.deleted != 0,
+                generation_rule_id: r.generation_rule_id,
             }))
         }
         None => Ok(None),
@@ -141,7 +177,8 @@ pub async fn list_document_paths(
     let rows = sqlx::query!(
         r#"
         SELECT id, document_number, document_type_code, department_code, section_code,
-               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted
+               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted,
+               generation_rule_id
         FROM documents
         WHERE (deleted = 0 OR ? = 1)
         ORDER BY created_at DESC
@@ -165,7 +202,7 @@ pub async fn list_document_paths(
                 section: SectionCode::new(section_char),
                 business_task: r.business_task_id.map(TaskId::new),
                 user: UserId::new(r.user_id),
-                file_path: PathBuf::from(r.file_path),
+                file_path: StoredPath::new(r.file_path),
                 created_at: DateTime::parse_from_rfc3339(&r.created_at)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
@@ -174,6 +211,7 @@ pub async fn list_document_paths(
                     .unwrap_or_else(|_| Utc::now()),
                 generated: r.generated != 0,
                 deleted: r.deleted != 0,
+                generation_rule_id: r.generation_rule_id,
             })
         })
         .collect();
@@ -181,26 +219,92 @@ pub async fn list_document_paths(
     Ok(docs)
 }
 
-/// Update a document path (only file_path can be updated)
-pub async fn update_document_path(
-    pool: &SqlitePool,
+/// Numbers of the live documents at `path` (compared canonically), other
+/// than `exclude`
+pub async fn live_documents_at_path<'e, E>(
+    executor: E,
+    path: &StoredPath,
+    exclude: Option<&DocumentId>,
+) -> Result<Vec<String>>
+where
+    E: SqliteExecutor<'e>,
+{
+    let path_key = path.comparison_key();
+    let exclude_id = exclude.map(|id| id.0.as_str());
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT document_number
+        FROM documents
+        WHERE path_key = ? AND deleted = 0 AND (? IS NULL OR id != ?)
+        ORDER BY document_number
+        "#,
+        path_key,
+        exclude_id,
+        exclude_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows.into_iter().map(|r| r.document_number).collect())
+}
+
+/// Recompute `path_key` of every document whose stored key is not the
+/// comparison key of its canonical path (e.g. rows keyed by the SQL
+/// backfill of migration 009). Returns the number of rows updated.
+pub async fn refresh_path_keys(pool: &SqlitePool) -> Result<u64> {
+    let mut tx = db::begin_write(pool).await?;
+    let rows = sqlx::query!("SELECT id, file_path, path_key FROM documents")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let mut updated = 0;
+    for row in rows {
+        let path_key = StoredPath::new(row.file_path).comparison_key().into_owned();
+        if row.path_key.as_deref() == Some(path_key.as_str()) {
+            continue;
+        }
+        sqlx::query!(
+            "UPDATE documents SET path_key = ? WHERE id = ?",
+            path_key,
+            row.id
+        )
+        .execute(&mut *tx)
+        .await?;
+        updated += 1;
+    }
+    tx.commit().await?;
+
+    Ok(updated)
+}
+
+/// Update a document path (only file_path can be updated).
+/// Accepts a pool or an open transaction (`&mut *tx`).
+pub async fn update_document_path<'e, E>(
+    executor: E,
     id: &DocumentId,
-    new_path: PathBuf,
-) -> Result<()> {
-    let file_path_str = new_path.to_string_lossy().to_string();
-    let now = Utc::now().to_rfc3339();
+    new_path: StoredPath,
+    updated_at: DateTime<Utc>,
+) -> Result<()>
+where
+    E: SqliteExecutor<'e>,
+{
+    let file_path_str = new_path.to_string();
+    let path_key = new_path.comparison_key();
+    let now = updated_at.to_rfc3339();
 
     sqlx::query!(
         r#"
         UPDATE documents
-        SET file_path = ?, updated_at = ?
+        SET file_path = ?, path_key = ?, updated_at = ?
         WHERE id = ?
         "#,
         file_path_str,
+        path_key,
         now,
         id.0
     )
-    .execute(pool)
+    .execute(executor)
     .await?;
 
     Ok(())
@@ -286,7 +390,7 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/docs/contracts/AGI-2509001.pdf"),
+            StoredPath::new("/docs/contracts/AGI-2509001.pdf"),
         );
 
         create_document_path(&pool, &doc).await?;
@@ -310,7 +414,7 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/docs/contracts/AGI-2509001.pdf"),
+            StoredPath::new("/docs/contracts/AGI-2509001.pdf"),
         );
 
         create_document_path(&pool, &doc).await?;
@@ -334,7 +438,7 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/docs/test.pdf"),
+            StoredPath::new("/docs/test.pdf"),
         );
 
         create_document_path(&pool, &doc).await?;
@@ -376,18 +480,42 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/old/path.pdf"),
+            StoredPath::new("/old/path.pdf"),
         );
 
         create_document_path(&pool, &doc).await?;
 
-        let new_path = PathBuf::from("/new/path.pdf");
-        update_document_path(&pool, &doc.id, new_path.clone()).await?;
+        let new_path = StoredPath::new("/new/path.pdf");
+        let now = Utc::now();
+        update_document_path(&pool, &doc.id, new_path.clone(), now).await?;
 
         let updated = get_document_path(&pool, &doc.id)
             .await?
             .ok_or_else(|| crate::error::Error::NotFound("Document not found".to_string()))?;
         assert_eq!(updated.file_path, new_path);
+        assert_eq!(updated.updated_at, now);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_document_number_is_typed() -> Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+
+        let new_doc = |file: &str| {
+            DocumentPath::new_auto(
+                "AGI-2509001",
+                TypeCode::new("A"),
+                DeptCode::new('G'),
+                SectionCode::new('I'),
+                UserId::new("user001"),
+                StoredPath::new(file),
+            )
+        };
+        create_document_path(&pool, &new_doc("/docs/contracts/a.pdf")).await?;
+
+        let result = create_document_path(&pool, &new_doc("/docs/contracts/b.pdf")).await;
+        assert!(matches!(result, Err(Error::DuplicateDocumentNumber(ref n)) if n == "AGI-2509001"));
         Ok(())
     }
 }
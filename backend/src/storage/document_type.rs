@@ -1,11 +1,19 @@
 //! Document Type storage operations
 
-use crate::error::Result;
-use crate::models::{DocumentType, PathGenerationRule, TypeCode};
-use sqlx::SqlitePool;
-
-/// Create a new document type
+use crate::error::{Error, Result};
+use crate::models::{
+    DocumentType, ManualNumberPolicy, PathGenerationRule, RuleVersion, StoredPath, TypeCode,
+};
+use crate::storage::db;
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use sqlx::{SqliteExecutor, SqlitePool};
+
+/// Create a new document type.
+/// The type and its first rule version are written in one transaction.
 pub async fn create_document_type(pool: &SqlitePool, doc_type: &DocumentType) -> Result<()> {
+    let mut tx = db::begin_write(pool).await?;
+
     // First, insert the generation rule and get its ID
     let rule_json = serde_json::to_string(&doc_type.generation_rule)?;
     let counter_scope = format!("{:?}", doc_type.generation_rule.counter_scope);
@@ -13,36 +21,61 @@ pub async fn create_document_type(pool: &SqlitePool, doc_type: &DocumentType) ->
 
     let rule_id = sqlx::query!(
         r#"
-        INSERT INTO generation_rules (components, separators, counter_scope, counter_digits)
-        VALUES (?, ?, ?, ?)
+        INSERT INTO generation_rules (components, separators, counter_scope, counter_digits, document_type_code)
+        VALUES (?, ?, ?, ?, ?)
         RETURNING id
         "#,
         rule_json,
         "[]", // separators stored in the components JSON
         counter_scope,
-        counter_digits
+        counter_digits,
+        doc_type.code.0
     )
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?
     .id;
 
     // Then insert the document type
     let active = doc_type.active as i32;
+    let root_directory = doc_type.root_directory.as_str();
+    let timezone = doc_type.timezone.map(|tz| tz.name().to_string());
+    let manual_number_mode = doc_type.manual_numbers.mode.as_str();
+    let manual_number_bump = doc_type.manual_numbers.bump_counter as i32;
+    let path_template = doc_type.path_template.as_ref().map(|t| t.as_str());
+    let path_uniqueness = doc_type.path_uniqueness.as_str();
+    let path_containment = doc_type.path_containment.as_str();
+    let watch_pattern = doc_type.watch_pattern.as_ref().map(|p| p.as_str());
 
     sqlx::query!(
         r#"
-        INSERT INTO document_types (code, description, root_directory, generation_rule_id, active)
-        VALUES (?, ?, ?, ?, ?)
+        INSERT INTO document_types (
+            code, description, root_directory, generation_rule_id, active, timezone,
+            manual_number_mode, manual_number_bump, path_template, path_uniqueness,
+            path_containment, watch_pattern
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         doc_type.code.0,
         doc_type.description,
-        doc_type.root_directory,
+        root_directory,
         rule_id,
-        active
+        active,
+        timezone,
+        manual_number_mode,
+        manual_number_bump,
+        path_template,
+        path_uniqueness,
+        path_containment,
+        watch_pattern
     )
-    .execute(pool)
-    .await?;
-
+    .execute(&mut *tx)
+    .await
+    .map_err(db::on_unique_violation(|| Error::AlreadyExists {
+        entity: "Document type",
+        key: doc_type.code.0.clone(),
+    }))?;
+
+    tx.commit().await?;
     Ok(())
 }
 
@@ -50,7 +83,9 @@ pub async fn create_document_type(pool: &SqlitePool, doc_type: &DocumentType) ->
 pub async fn get_document_type(pool: &SqlitePool, code: &TypeCode) -> Result<Option<DocumentType>> {
     let row = sqlx::query!(
         r#"
-        SELECT dt.code, dt.description, dt.root_directory, dt.active,
+        SELECT dt.code, dt.description, dt.root_directory, dt.active, dt.timezone,
+               dt.manual_number_mode, dt.manual_number_bump, dt.path_template, dt.path_uniqueness,
+               dt.path_containment, dt.watch_pattern,
                gr.id as rule_id, gr.components as rule_components
         FROM document_types dt
         LEFT JOIN generation_rules gr ON dt.generation_rule_id = gr.id
@@ -63,15 +98,27 @@ pub async fn get_document_type(pool: &SqlitePool, code: &TypeCode) -> Result<Opt
 
     match row {
         Some(r) => {
-            let generation_rule = serde_json::from_str::<PathGenerationRule>(&r.rule_components)
-                .unwrap_or_else(|_| PathGenerationRule::example_agi());
+            let mut generation_rule =
+                serde_json::from_str::<PathGenerationRule>(&r.rule_components)
+                    .unwrap_or_else(|_| PathGenerationRule::example_agi());
+            generation_rule.id = r.rule_id;
+            let timezone = stored_timezone(&r.code, r.timezone);
 
             Ok(Some(DocumentType {
                 code: TypeCode::new(r.code),
                 description: r.description,
-                root_directory: r.root_directory,
+                root_directory: StoredPath::new(r.root_directory),
                 generation_rule,
                 active: r.active != 0,
+                timezone,
+                manual_numbers: ManualNumberPolicy {
+                    mode: r.manual_number_mode.parse().unwrap_or_default(),
+                    bump_counter: r.manual_number_bump != 0,
+                },
+                path_template: r.path_template.and_then(|t| t.parse().ok()),
+                path_uniqueness: r.path_uniqueness.parse().unwrap_or_default(),
+                path_containment: r.path_containment.parse().unwrap_or_default(),
+                watch_pattern: r.watch_pattern.and_then(|p| p.parse().ok()),
             }))
         }
         None => Ok(None),
@@ -82,7 +129,9 @@ pub async fn get_document_type(pool: &SqlitePool, code: &TypeCode) -> Result<Opt
 pub async fn list_document_types(pool: &SqlitePool) -> Result<Vec<DocumentType>> {
     let rows = sqlx::query!(
         r#"
-        SELECT dt.code, dt.description, dt.root_directory, dt.active,
+        SELECT dt.code, dt.description, dt.root_directory, dt.active, dt.timezone,
+               dt.manual_number_mode, dt.manual_number_bump, dt.path_template, dt.path_uniqueness,
+               dt.path_containment, dt.watch_pattern,
                gr.id as rule_id, gr.components as rule_components
         FROM document_types dt
         LEFT JOIN generation_rules gr ON dt.generation_rule_id = gr.id
@@ -95,17 +144,28 @@ pub async fn list_document_types(pool: &SqlitePool) -> Result<Vec<DocumentType>>
     let doc_types = rows
         .into_iter()
         .map(|r| {
-            let generation_rule = r
+            let mut generation_rule = r
                 .rule_components
                 .and_then(|json| serde_json::from_str::<PathGenerationRule>(&json).ok())
                 .unwrap_or_else(PathGenerationRule::example_agi);
+            generation_rule.id = Some(r.rule_id);
+            let timezone = stored_timezone(&r.code, r.timezone);
 
             DocumentType {
                 code: TypeCode::new(r.code),
                 description: r.description,
-                root_directory: r.root_directory,
+                root_directory: StoredPath::new(r.root_directory),
                 generation_rule,
                 active: r.active != 0,
+                timezone,
+                manual_numbers: ManualNumberPolicy {
+                    mode: r.manual_number_mode.parse().unwrap_or_default(),
+                    bump_counter: r.manual_number_bump != 0,
+                },
+                path_template: r.path_template.and_then(|t| t.parse().ok()),
+                path_uniqueness: r.path_uniqueness.parse().unwrap_or_default(),
+                path_containment: r.path_containment.parse().unwrap_or_default(),
+                watch_pattern: r.watch_pattern.and_then(|p| p.parse().ok()),
             }
         })
         .collect();
@@ -117,7 +177,9 @@ pub async fn list_document_types(pool: &SqlitePool) -> Result<Vec<DocumentType>>
 pub async fn list_active_document_types(pool: &SqlitePool) -> Result<Vec<DocumentType>> {
     let rows = sqlx::query!(
         r#"
-        SELECT dt.code, dt.description, dt.root_directory, dt.active,
+        SELECT dt.code, dt.description, dt.root_directory, dt.active, dt.timezone,
+               dt.manual_number_mode, dt.manual_number_bump, dt.path_template, dt.path_uniqueness,
+               dt.path_containment, dt.watch_pattern,
                gr.id as rule_id, gr.components as rule_components
         FROM document_types dt
         LEFT JOIN generation_rules gr ON dt.generation_rule_id = gr.id
@@ -131,17 +193,28 @@ pub async fn list_active_document_types(pool: &SqlitePool) -> Result<Vec<Documen
     let doc_types = rows
         .into_iter()
         .map(|r| {
-            let generation_rule = r
+            let mut generation_rule = r
                 .rule_components
                 .and_then(|json| serde_json::from_str::<PathGenerationRule>(&json).ok())
                 .unwrap_or_else(PathGenerationRule::example_agi);
+            generation_rule.id = Some(r.rule_id);
+            let timezone = stored_timezone(&r.code, r.timezone);
 
             DocumentType {
                 code: TypeCode::new(r.code),
                 description: r.description,
-                root_directory: r.root_directory,
+                root_directory: StoredPath::new(r.root_directory),
                 generation_rule,
                 active: r.active != 0,
+                timezone,
+                manual_numbers: ManualNumberPolicy {
+                    mode: r.manual_number_mode.parse().unwrap_or_default(),
+                    bump_counter: r.manual_number_bump != 0,
+                },
+                path_template: r.path_template.and_then(|t| t.parse().ok()),
+                path_uniqueness: r.path_uniqueness.parse().unwrap_or_default(),
+                path_containment: r.path_containment.parse().unwrap_or_default(),
+                watch_pattern: r.watch_pattern.and_then(|p| p.parse().ok()),
             }
         })
         .collect();
@@ -152,16 +225,33 @@ pub async fn list_active_document_types(pool: &SqlitePool) -> Result<Vec<Documen
 /// Update a document type (note: generation rule is immutable)
 pub async fn update_document_type(pool: &SqlitePool, doc_type: &DocumentType) -> Result<()> {
     let active = doc_type.active as i32;
+    let root_directory = doc_type.root_directory.as_str();
+    let timezone = doc_type.timezone.map(|tz| tz.name().to_string());
+    let manual_number_mode = doc_type.manual_numbers.mode.as_str();
+    let manual_number_bump = doc_type.manual_numbers.bump_counter as i32;
+    let path_template = doc_type.path_template.as_ref().map(|t| t.as_str());
+    let path_uniqueness = doc_type.path_uniqueness.as_str();
+    let path_containment = doc_type.path_containment.as_str();
+    let watch_pattern = doc_type.watch_pattern.as_ref().map(|p| p.as_str());
 
     sqlx::query!(
         r#"
         UPDATE document_types
-        SET description = ?, root_directory = ?, active = ?
+        SET description = ?, root_directory = ?, active = ?, timezone = ?,
+            manual_number_mode = ?, manual_number_bump = ?, path_template = ?,
+            path_uniqueness = ?, path_containment = ?, watch_pattern = ?
         WHERE code = ?
         "#,
         doc_type.description,
-        doc_type.root_directory,
+        root_directory,
         active,
+        timezone,
+        manual_number_mode,
+        manual_number_bump,
+        path_template,
+        path_uniqueness,
+        path_containment,
+        watch_pattern,
         doc_type.code.0
     )
     .execute(pool)
@@ -170,6 +260,141 @@ pub async fn update_document_type(pool: &SqlitePool, doc_type: &DocumentType) ->
     Ok(())
 }
 
+/// Add a new rule version to a document type, effective from `effective_from`.
+/// Existing versions are never modified.
+/// Accepts a pool or an open transaction (`&mut *tx`).
+pub async fn create_rule_version<'e, E>(
+    executor: E,
+    code: &TypeCode,
+    rule: &PathGenerationRule,
+    effective_from: NaiveDate,
+) -> Result<RuleVersion>
+where
+    E: SqliteExecutor<'e>,
+{
+    let mut rule = rule.clone();
+    rule.id = None;
+    let rule_json = serde_json::to_string(&rule)?;
+    let counter_scope = format!("{:?}", rule.counter_scope);
+    let counter_digits = rule.counter_digits as i32;
+    let effective_from_str = effective_from.to_string();
+
+    let id = sqlx::query!(
+        r#"
+        INSERT INTO generation_rules (
+            components, separators, counter_scope, counter_digits, document_type_code, effective_from
+        )
+        VALUES (?, ?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+        rule_json,
+        "[]", // separators stored in the components JSON
+        counter_scope,
+        counter_digits,
+        code.0,
+        effective_from_str
+    )
+    .fetch_one(executor)
+    .await?
+    .id;
+
+    rule.id = Some(id);
+    Ok(RuleVersion {
+        id,
+        document_type: code.clone(),
+        effective_from: Some(effective_from),
+        rule,
+    })
+}
+
+/// List the rule versions of a document type, oldest first.
+/// Accepts a pool or an open transaction (`&mut *tx`).
+pub async fn list_rule_versions<'e, E>(executor: E, code: &TypeCode) -> Result<Vec<RuleVersion>>
+where
+    E: SqliteExecutor<'e>,
+{
+    let rows = sqlx::query!(
+        r#"
+        SELECT id as "id!", components, document_type_code as "document_type_code!", effective_from
+        FROM generation_rules
+        WHERE document_type_code = ?
+        ORDER BY COALESCE(effective_from, ''), id
+        "#,
+        code.0
+    )
+    .fetch_all(executor)
+    .await?;
+
+    rows.into_iter()
+        .map(|r| rule_version(r.id, r.document_type_code, r.effective_from, &r.components))
+        .collect()
+}
+
+/// Get a single rule version by ID
+pub async fn get_rule_version(pool: &SqlitePool, id: i64) -> Result<Option<RuleVersion>> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id as "id!", components, document_type_code, effective_from
+        FROM generation_rules
+        WHERE id = ?
+        "#,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(|r| {
+        rule_version(
+            r.id,
+            r.document_type_code.unwrap_or_default(),
+            r.effective_from,
+            &r.components,
+        )
+    })
+    .transpose()
+}
+
+/// Parse a stored timezone name. A name this build does not know (e.g.,
+/// written by a newer tz database) is logged and the type falls back to the
+/// deployment timezone rather than failing every read.
+fn stored_timezone(code: &str, name: Option<String>) -> Option<Tz> {
+    let name = name?;
+    match name.parse() {
+        Ok(timezone) => Some(timezone),
+        Err(_) => {
+            tracing::warn!(
+                "Document type '{}' has unknown timezone '{}', using the deployment timezone",
+                code,
+                name
+            );
+            None
+        }
+    }
+}
+
+fn rule_version(
+    id: i64,
+    document_type_code: String,
+    effective_from: Option<String>,
+    components: &str,
+) -> Result<RuleVersion> {
+    let mut rule = serde_json::from_str::<PathGenerationRule>(components)?;
+    rule.id = Some(id);
+    let effective_from = effective_from
+        .map(|date| {
+            date.parse::<NaiveDate>()
+                .map_err(|e| Error::Internal(format!("Invalid effective_from '{}': {}", date, e)))
+        })
+        .transpose()?;
+
+    Ok(RuleVersion {
+        id,
+        document_type: TypeCode::new(document_type_code),
+        effective_from,
+        rule,
+    })
+}
+
 /// Delete a document type
 pub async fn delete_document_type(pool: &SqlitePool, code: &TypeCode) -> Result<()> {
     sqlx::query!(
@@ -240,4 +465,92 @@ mod tests {
         assert_eq!(active_list[0].code.0, "A");
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_document_type_timezone_roundtrip() -> Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        let rule = PathGenerationRule::example_agi();
+        let doc_type = DocumentType::new("A", "契約書", "/docs/contracts/", rule)
+            .with_timezone(chrono_tz::America::New_York);
+        create_document_type(&pool, &doc_type).await?;
+
+        let retrieved = get_document_type(&pool, &TypeCode::new("A"))
+            .await?
+            .ok_or_else(|| crate::error::Error::NotFound("Document type not found".to_string()))?;
+        assert_eq!(retrieved.timezone, Some(chrono_tz::America::New_York));
+
+        let cleared = DocumentType {
+            timezone: None,
+            ..retrieved
+        };
+        update_document_type(&pool, &cleared).await?;
+        let retrieved = get_document_type(&pool, &TypeCode::new("A"))
+            .await?
+            .ok_or_else(|| crate::error::Error::NotFound("Document type not found".to_string()))?;
+        assert!(retrieved.timezone.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_document_type_unknown_timezone_falls_back() -> Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        let rule = PathGenerationRule::example_agi();
+        let doc_type = DocumentType::new("A", "契約書", "/docs/contracts/", rule);
+        create_document_type(&pool, &doc_type).await?;
+        sqlx::query("UPDATE document_types SET timezone = 'Mars/Olympus_Mons' WHERE code = 'A'")
+            .execute(&pool)
+            .await?;
+
+        let retrieved = get_document_type(&pool, &TypeCode::new("A"))
+            .await?
+            .ok_or_else(|| crate::error::Error::NotFound("Document type not found".to_string()))?;
+        assert!(retrieved.timezone.is_none());
+        assert!(list_document_types(&pool).await?[0].timezone.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_document_type_manual_number_policy_roundtrip() -> Result<()> {
+        use crate::models::ManualNumberMode;
+
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        let rule = PathGenerationRule::example_agi();
+        let policy = ManualNumberPolicy {
+            mode: ManualNumberMode::Warn,
+            bump_counter: true,
+        };
+        let doc_type =
+            DocumentType::new("A", "契約書", "/docs/contracts/", rule).with_manual_numbers(policy);
+        create_document_type(&pool, &doc_type).await?;
+
+        let retrieved = get_document_type(&pool, &TypeCode::new("A"))
+            .await?
+            .ok_or_else(|| crate::error::Error::NotFound("Document type not found".to_string()))?;
+        assert_eq!(retrieved.manual_numbers, policy);
+
+        let relaxed = retrieved.with_manual_numbers(ManualNumberPolicy::default());
+        update_document_type(&pool, &relaxed).await?;
+        let listed = list_document_types(&pool).await?;
+        assert_eq!(listed[0].manual_numbers.mode, ManualNumberMode::Off);
+        assert!(!listed[0].manual_numbers.bump_counter);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_create_document_type_twice_is_conflict() -> Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+
+        let rule = PathGenerationRule::example_agi();
+        let doc_type = DocumentType::new("A", "契約書", "/docs/contracts/", rule);
+        create_document_type(&pool, &doc_type).await?;
+
+        let result = create_document_type(&pool, &doc_type).await;
+        assert!(matches!(result, Err(Error::AlreadyExists { .. })));
+        // The failed attempt leaves no stray rule version behind
+        assert_eq!(list_rule_versions(&pool, &doc_type.code).await?.len(), 1);
+        Ok(())
+    }
 }
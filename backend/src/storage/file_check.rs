@@ -0,0 +1,132 @@
+//! File check storage operations
+
+use crate::error::Result;
+use crate::models::{DocumentId, FileCheck, FileStatus, StoredPath};
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::SqlitePool;
+
+/// Raw `file_checks` row
+struct FileCheckRow {
+    document_id: String,
+    file_path: String,
+    status: String,
+    size: Option<i64>,
+    modified_at: Option<String>,
+    last_seen_at: Option<String>,
+    checked_at: String,
+    error: Option<String>,
+}
+
+impl FileCheckRow {
+    fn into_file_check(self) -> Result<FileCheck> {
+        Ok(FileCheck {
+            document_id: DocumentId::new(self.document_id),
+            file_path: StoredPath::new(self.file_path),
+            status: self.status.parse()?,
+            size: self.size,
+            modified_at: self.modified_at.as_deref().map(parse_timestamp),
+            last_seen_at: self.last_seen_at.as_deref().map(parse_timestamp),
+            checked_at: parse_timestamp(&self.checked_at),
+            error: self.error,
+        })
+    }
+}
+
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn parse_timestamp(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// Record the result of a check, replacing the previous one.
+/// When the file is not present at the same path as before, the size,
+/// mtime and last-seen time of the previous sighting are kept.
+pub async fn record_file_check(pool: &SqlitePool, check: &FileCheck) -> Result<()> {
+    let file_path = check.file_path.as_str();
+    let status = check.status.as_str();
+    let modified_at = check.modified_at.map(format_timestamp);
+    let last_seen_at = check.last_seen_at.map(format_timestamp);
+    let checked_at = format_timestamp(check.checked_at);
+
+    sqlx::query!(
+        r#"
+        INSERT INTO file_checks (
+            document_id, file_path, status, size, modified_at, last_seen_at, checked_at, error
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(document_id) DO UPDATE SET
+            size = CASE
+                WHEN excluded.status = 'present' OR excluded.file_path <> file_checks.file_path
+                THEN excluded.size ELSE file_checks.size END,
+            modified_at = CASE
+                WHEN excluded.status = 'present' OR excluded.file_path <> file_checks.file_path
+                THEN excluded.modified_at ELSE file_checks.modified_at END,
+            last_seen_at = CASE
+                WHEN excluded.status = 'present' OR excluded.file_path <> file_checks.file_path
+                THEN excluded.last_seen_at ELSE file_checks.last_seen_at END,
+            file_path = excluded.file_path,
+            status = excluded.status,
+            checked_at = excluded.checked_at,
+            error = excluded.error
+        "#,
+        check.document_id.0,
+        file_path,
+        status,
+        check.size,
+        modified_at,
+        last_seen_at,
+        checked_at,
+        check.error
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Get the last check of a document's file
+pub async fn get_file_check(pool: &SqlitePool, id: &DocumentId) -> Result<Option<FileCheck>> {
+    let row = sqlx::query_as!(
+        FileCheckRow,
+        r#"
+        SELECT document_id, file_path, status, size, modified_at, last_seen_at, checked_at, error
+        FROM file_checks
+        WHERE document_id = ?
+        "#,
+        id.0
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(FileCheckRow::into_file_check).transpose()
+}
+
+/// List checks with one of the given statuses
+pub async fn list_file_checks(
+    pool: &SqlitePool,
+    statuses: &[FileStatus],
+) -> Result<Vec<FileCheck>> {
+    let rows = sqlx::query_as!(
+        FileCheckRow,
+        r#"
+        SELECT document_id, file_path, status, size, modified_at, last_seen_at, checked_at, error
+        FROM file_checks
+        ORDER BY checked_at DESC
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut checks = Vec::new();
+    for row in rows {
+        let check = row.into_file_check()?;
+        if statuses.contains(&check.status) {
+            checks.push(check);
+        }
+    }
+    Ok(checks)
+}
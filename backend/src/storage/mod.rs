@@ -6,7 +6,9 @@ pub mod db;
 pub mod department;
 pub mod document_path;
 pub mod document_type;
+pub mod file_check;
 pub mod query;
+pub mod reservation;
 pub mod section;
 pub mod user;
 
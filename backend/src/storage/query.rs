@@ -1,10 +1,11 @@
 //! Query operations for document paths
 
 use crate::error::Result;
-use crate::models::{DeptCode, DocumentId, DocumentPath, SectionCode, TaskId, TypeCode, UserId};
+use crate::models::{
+    DeptCode, DocumentId, DocumentPath, SectionCode, StoredPath, TaskId, TypeCode, UserId,
+};
 use chrono::{DateTime, Utc};
 use sqlx::SqlitePool;
-use std::path::PathBuf;
 
 /// Get all documents (respects deleted flag by default)
 pub async fn get_all_documents(
@@ -16,7 +17,8 @@ pub async fn get_all_documents(
     let rows = sqlx::query!(
         r#"
         SELECT id, document_number, document_type_code, department_code, section_code,
-               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted
+               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted,
+               generation_rule_id
         FROM documents
         WHERE (deleted = 0 OR ? = 1)
         ORDER BY created_at DESC
@@ -40,7 +42,7 @@ pub async fn get_all_documents(
                 section: SectionCode::new(section_char),
                 business_task: r.business_task_id.map(TaskId::new),
                 user: UserId::new(r.user_id),
-                file_path: PathBuf::from(r.file_path),
+                file_path: StoredPath::new(r.file_path),
                 created_at: DateTime::parse_from_rfc3339(&r.created_at)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
@@ -49,6 +51,7 @@ pub async fn get_all_documents(
                     .unwrap_or_else(|_| Utc::now()),
                 generated: r.generated != 0,
                 deleted: r.deleted != 0,
+                generation_rule_id: r.generation_rule_id,
             })
         })
         .collect();
@@ -67,7 +70,8 @@ pub async fn get_documents_by_type(
     let rows = sqlx::query!(
         r#"
         SELECT id, document_number, document_type_code, department_code, section_code,
-               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted
+               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted,
+               generation_rule_id
         FROM documents
         WHERE document_type_code = ? AND (deleted = 0 OR ? = 1)
         ORDER BY created_at DESC
@@ -92,7 +96,7 @@ pub async fn get_documents_by_type(
                 section: SectionCode::new(section_char),
                 business_task: r.business_task_id.map(TaskId::new),
                 user: UserId::new(r.user_id),
-                file_path: PathBuf::from(r.file_path),
+                file_path: StoredPath::new(r.file_path),
                 created_at: DateTime::parse_from_rfc3339(&r.created_at)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
@@ -101,6 +105,7 @@ pub async fn get_documents_by_type(
                     .unwrap_or_else(|_| Utc::now()),
                 generated: r.generated != 0,
                 deleted: r.deleted != 0,
+                generation_rule_id: r.generation_rule_id,
             })
         })
         .collect();
@@ -120,7 +125,8 @@ pub async fn get_documents_by_department(
     let rows = sqlx::query!(
         r#"
         SELECT id, document_number, document_type_code, department_code, section_code,
-               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted
+               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted,
+               generation_rule_id
         FROM documents
         WHERE department_code = ? AND (deleted = 0 OR ? = 1)
         ORDER BY created_at DESC
@@ -145,7 +151,7 @@ pub async fn get_documents_by_department(
                 section: SectionCode::new(section_char),
                 business_task: r.business_task_id.map(TaskId::new),
                 user: UserId::new(r.user_id),
-                file_path: PathBuf::from(r.file_path),
+                file_path: StoredPath::new(r.file_path),
                 created_at: DateTime::parse_from_rfc3339(&r.created_at)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
@@ -154,6 +160,7 @@ pub async fn get_documents_by_department(
                     .unwrap_or_else(|_| Utc::now()),
                 generated: r.generated != 0,
                 deleted: r.deleted != 0,
+                generation_rule_id: r.generation_rule_id,
             })
         })
         .collect();
@@ -173,7 +180,8 @@ pub async fn get_documents_by_section(
     let rows = sqlx::query!(
         r#"
         SELECT id, document_number, document_type_code, department_code, section_code,
-               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted
+               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted,
+               generation_rule_id
         FROM documents
         WHERE section_code = ? AND (deleted = 0 OR ? = 1)
         ORDER BY created_at DESC
@@ -198,7 +206,7 @@ pub async fn get_documents_by_section(
                 section: SectionCode::new(section_char),
                 business_task: r.business_task_id.map(TaskId::new),
                 user: UserId::new(r.user_id),
-                file_path: PathBuf::from(r.file_path),
+                file_path: StoredPath::new(r.file_path),
                 created_at: DateTime::parse_from_rfc3339(&r.created_at)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
@@ -207,6 +215,7 @@ pub async fn get_documents_by_section(
                     .unwrap_or_else(|_| Utc::now()),
                 generated: r.generated != 0,
                 deleted: r.deleted != 0,
+                generation_rule_id: r.generation_rule_id,
             })
         })
         .collect();
@@ -225,7 +234,8 @@ pub async fn get_documents_by_task(
     let rows = sqlx::query!(
         r#"
         SELECT id, document_number, document_type_code, department_code, section_code,
-               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted
+               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted,
+               generation_rule_id
         FROM documents
         WHERE business_task_id = ? AND (deleted = 0 OR ? = 1)
         ORDER BY created_at DESC
@@ -250,7 +260,7 @@ pub async fn get_documents_by_task(
                 section: SectionCode::new(section_char),
                 business_task: r.business_task_id.map(TaskId::new),
                 user: UserId::new(r.user_id),
-                file_path: PathBuf::from(r.file_path),
+                file_path: StoredPath::new(r.file_path),
                 created_at: DateTime::parse_from_rfc3339(&r.created_at)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
@@ -259,6 +269,7 @@ pub async fn get_documents_by_task(
                     .unwrap_or_else(|_| Utc::now()),
                 generated: r.generated != 0,
                 deleted: r.deleted != 0,
+                generation_rule_id: r.generation_rule_id,
             })
         })
         .collect();
@@ -278,7 +289,8 @@ pub async fn search_documents_by_text(
     let rows = sqlx::query!(
         r#"
         SELECT id, document_number, document_type_code, department_code, section_code,
-               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted
+               business_task_id, user_id, file_path, created_at, updated_at, generated, deleted,
+               generation_rule_id
         FROM documents
         WHERE (document_number LIKE ? OR file_path LIKE ?) 
               AND (deleted = 0 OR ? = 1)
@@ -305,7 +317,7 @@ pub async fn search_documents_by_text(
                 section: SectionCode::new(section_char),
                 business_task: r.business_task_id.map(TaskId::new),
                 user: UserId::new(r.user_id),
-                file_path: PathBuf::from(r.file_path),
+                file_path: StoredPath::new(r.file_path),
                 created_at: DateTime::parse_from_rfc3339(&r.created_at)
                     .map(|dt| dt.with_timezone(&Utc))
                     .unwrap_or_else(|_| Utc::now()),
@@ -314,6 +326,7 @@ pub async fn search_documents_by_text(
                     .unwrap_or_else(|_| Utc::now()),
                 generated: r.generated != 0,
                 deleted: r.deleted != 0,
+                generation_rule_id: r.generation_rule_id,
             })
         })
         .collect();
@@ -461,7 +474,7 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/docs/contracts/AGI-2509001.pdf"),
+            StoredPath::new("/docs/contracts/AGI-2509001.pdf"),
         );
 
         let doc2 = DocumentPath::new_auto(
@@ -470,7 +483,7 @@ mod tests {
             DeptCode::new('G'),
             SectionCode::new('I'),
             UserId::new("user001"),
-            PathBuf::from("/docs/ringi/りん議I-25009.pdf"),
+            StoredPath::new("/docs/ringi/りん議I-25009.pdf"),
         );
 
         document_path::create_document_path(pool, &doc1).await?;
@@ -0,0 +1,403 @@
+//! Number reservation storage operations
+
+use crate::error::{Error, Result};
+use crate::models::{
+    DeptCode, DocumentId, Reservation, ReservationId, ReservationStatus, SectionCode, TaskId,
+    TypeCode, UserId,
+};
+use crate::storage::db;
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::{SqliteExecutor, SqlitePool};
+
+/// Raw `reservations` row
+struct ReservationRow {
+    id: String,
+    document_number: String,
+    document_type_code: String,
+    department_code: String,
+    section_code: String,
+    user_id: String,
+    scope_key: String,
+    counter_value: i64,
+    status: String,
+    document_id: Option<String>,
+    reserved_at: String,
+    expires_at: String,
+    resolved_at: Option<String>,
+    generation_rule_id: Option<i64>,
+    business_task_id: Option<String>,
+}
+
+impl ReservationRow {
+    fn into_reservation(self) -> Result<Reservation> {
+        Ok(Reservation {
+            id: ReservationId::new(self.id),
+            document_number: self.document_number,
+            document_type: TypeCode::new(self.document_type_code),
+            department: DeptCode::new(self.department_code.chars().next().unwrap_or('?')),
+            section: SectionCode::new(self.section_code.chars().next().unwrap_or('?')),
+            user: UserId::new(self.user_id),
+            scope_key: self.scope_key,
+            counter_value: self.counter_value,
+            status: self.status.parse()?,
+            document_id: self.document_id.map(DocumentId::new),
+            reserved_at: parse_timestamp(&self.reserved_at),
+            expires_at: parse_timestamp(&self.expires_at),
+            resolved_at: self.resolved_at.as_deref().map(parse_timestamp),
+            generation_rule_id: self.generation_rule_id,
+            business_task: self.business_task_id.map(TaskId::new),
+        })
+    }
+}
+
+/// Timestamps are stored in a fixed-width UTC format so that the expiry
+/// sweep can compare them as strings
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn parse_timestamp(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// Create a new reservation.
+/// Accepts a pool or an open transaction (`&mut *tx`).
+pub async fn create_reservation<'e, E>(executor: E, reservation: &Reservation) -> Result<()>
+where
+    E: SqliteExecutor<'e>,
+{
+    let dept_code = reservation.department.0.to_string();
+    let section_code = reservation.section.0.to_string();
+    let status = reservation.status.as_str();
+    let document_id = reservation.document_id.as_ref().map(|d| d.0.clone());
+    let reserved_at = format_timestamp(reservation.reserved_at);
+    let expires_at = format_timestamp(reservation.expires_at);
+    let resolved_at = reservation.resolved_at.map(format_timestamp);
+    let business_task_id = reservation.business_task.as_ref().map(|t| t.0.clone());
+
+    sqlx::query!(
+        r#"
+        INSERT INTO reservations (
+            id, document_number, document_type_code, department_code, section_code,
+            user_id, scope_key, counter_value, status, document_id,
+            reserved_at, expires_at, resolved_at, generation_rule_id, business_task_id
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        reservation.id.0,
+        reservation.document_number,
+        reservation.document_type.0,
+        dept_code,
+        section_code,
+        reservation.user.0,
+        reservation.scope_key,
+        reservation.counter_value,
+        status,
+        document_id,
+        reserved_at,
+        expires_at,
+        resolved_at,
+        reservation.generation_rule_id,
+        business_task_id
+    )
+    .execute(executor)
+    .await
+    .map_err(db::on_unique_violation(|| {
+        Error::ReservedDocumentNumber(reservation.document_number.clone())
+    }))?;
+
+    Ok(())
+}
+
+/// Get a reservation by ID
+pub async fn get_reservation(pool: &SqlitePool, id: &ReservationId) -> Result<Option<Reservation>> {
+    let row = sqlx::query_as!(
+        ReservationRow,
+        r#"
+        SELECT id, document_number, document_type_code, department_code, section_code,
+               user_id, scope_key, counter_value, status, document_id,
+               reserved_at, expires_at, resolved_at, generation_rule_id, business_task_id
+        FROM reservations
+        WHERE id = ?
+        "#,
+        id.0
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(ReservationRow::into_reservation).transpose()
+}
+
+/// Get the reservation holding a document number, if any
+/// Accepts a pool or an open transaction (`&mut *tx`).
+pub async fn get_reservation_by_number<'e, E>(
+    executor: E,
+    number: &str,
+) -> Result<Option<Reservation>>
+where
+    E: SqliteExecutor<'e>,
+{
+    let row = sqlx::query_as!(
+        ReservationRow,
+        r#"
+        SELECT id, document_number, document_type_code, department_code, section_code,
+               user_id, scope_key, counter_value, status, document_id,
+               reserved_at, expires_at, resolved_at, generation_rule_id, business_task_id
+        FROM reservations
+        WHERE document_number = ?
+        "#,
+        number
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    row.map(ReservationRow::into_reservation).transpose()
+}
+
+/// List reservations, optionally filtered by status (newest first)
+pub async fn list_reservations(
+    pool: &SqlitePool,
+    status: Option<ReservationStatus>,
+) -> Result<Vec<Reservation>> {
+    let status = status.map(|s| s.as_str());
+
+    let rows = sqlx::query_as!(
+        ReservationRow,
+        r#"
+        SELECT id, document_number, document_type_code, department_code, section_code,
+               user_id, scope_key, counter_value, status, document_id,
+               reserved_at, expires_at, resolved_at, generation_rule_id, business_task_id
+        FROM reservations
+        WHERE (?1 IS NULL OR status = ?1)
+        ORDER BY reserved_at DESC
+        "#,
+        status
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(ReservationRow::into_reservation)
+        .collect()
+}
+
+/// List numbers that were reserved but never used (released or expired)
+pub async fn list_unused_reservations(pool: &SqlitePool) -> Result<Vec<Reservation>> {
+    let rows = sqlx::query_as!(
+        ReservationRow,
+        r#"
+        SELECT id, document_number, document_type_code, department_code, section_code,
+               user_id, scope_key, counter_value, status, document_id,
+               reserved_at, expires_at, resolved_at, generation_rule_id, business_task_id
+        FROM reservations
+        WHERE status IN ('released', 'expired')
+        ORDER BY scope_key, counter_value
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(ReservationRow::into_reservation)
+        .collect()
+}
+
+/// Move a reservation from `from` to `to`.
+/// Returns false when the reservation is no longer in state `from`
+/// (e.g., it was confirmed, released or expired concurrently).
+pub async fn transition_reservation<'e, E>(
+    executor: E,
+    id: &ReservationId,
+    from: ReservationStatus,
+    to: ReservationStatus,
+    resolved_at: Option<DateTime<Utc>>,
+) -> Result<bool>
+where
+    E: SqliteExecutor<'e>,
+{
+    let from = from.as_str();
+    let to = to.as_str();
+    let resolved_at = resolved_at.map(format_timestamp);
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE reservations
+        SET status = ?, resolved_at = ?
+        WHERE id = ? AND status = ?
+        "#,
+        to,
+        resolved_at,
+        id.0,
+        from
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record the document created from a confirmed reservation
+pub async fn set_reservation_document<'e, E>(
+    executor: E,
+    id: &ReservationId,
+    document_id: &DocumentId,
+) -> Result<()>
+where
+    E: SqliteExecutor<'e>,
+{
+    sqlx::query!(
+        r#"
+        UPDATE reservations
+        SET document_id = ?
+        WHERE id = ?
+        "#,
+        document_id.0,
+        id.0
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Mark every reservation whose expiry is at or before `now` as expired.
+/// Returns the number of reservations expired.
+pub async fn expire_reservations(pool: &SqlitePool, now: DateTime<Utc>) -> Result<u64> {
+    let now = format_timestamp(now);
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE reservations
+        SET status = 'expired', resolved_at = ?1
+        WHERE status = 'reserved' AND expires_at <= ?1
+        "#,
+        now
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Department, DocumentType, PathGenerationRule, Section, User};
+    use crate::storage::db::init_db_pool;
+    use crate::storage::{department, document_type, section, user};
+    use chrono::{Duration, TimeZone};
+
+    async fn setup_test_data(pool: &SqlitePool) -> Result<()> {
+        department::create_department(pool, &Department::new('G', "総務")).await?;
+        let sec = Section {
+            code: SectionCode::new('I'),
+            name: "インフラ".to_string(),
+            department: DeptCode::new('G'),
+        };
+        section::create_section(pool, &sec).await?;
+        user::create_user(pool, &User::new("user001", "田川太郎", 'G', 'I')).await?;
+        let doc_type = DocumentType::new(
+            "A",
+            "契約書",
+            "/docs/contracts/",
+            PathGenerationRule::example_agi(),
+        );
+        document_type::create_document_type(pool, &doc_type).await?;
+        Ok(())
+    }
+
+    fn reservation(id: &str, number: &str, reserved_at: DateTime<Utc>) -> Reservation {
+        Reservation {
+            id: ReservationId::new(id),
+            document_number: number.to_string(),
+            document_type: TypeCode::new("A"),
+            department: DeptCode::new('G'),
+            section: SectionCode::new('I'),
+            user: UserId::new("user001"),
+            scope_key: "A_G_I_2025_10".to_string(),
+            counter_value: 1,
+            status: ReservationStatus::Reserved,
+            document_id: None,
+            reserved_at,
+            expires_at: reserved_at + Duration::hours(1),
+            resolved_at: None,
+            generation_rule_id: None,
+            business_task: None,
+        }
+    }
+
+    fn at(h: u32) -> anyhow::Result<DateTime<Utc>> {
+        Utc.with_ymd_and_hms(2025, 10, 1, h, 0, 0)
+            .single()
+            .ok_or_else(|| anyhow::anyhow!("invalid datetime"))
+    }
+
+    #[tokio::test]
+    async fn test_create_and_get_reservation() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let r = reservation("r1", "AGI2510001", at(9)?);
+        create_reservation(&pool, &r).await?;
+
+        let loaded = get_reservation(&pool, &r.id).await?;
+        assert_eq!(loaded, Some(r.clone()));
+
+        let by_number = get_reservation_by_number(&pool, "AGI2510001").await?;
+        assert_eq!(by_number.map(|r| r.id), Some(r.id));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transition_requires_expected_state() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        let r = reservation("r1", "AGI2510001", at(9)?);
+        create_reservation(&pool, &r).await?;
+
+        let released = transition_reservation(
+            &pool,
+            &r.id,
+            ReservationStatus::Reserved,
+            ReservationStatus::Released,
+            Some(at(10)?),
+        )
+        .await?;
+        assert!(released);
+
+        // Already released: cannot be confirmed any more
+        let confirmed = transition_reservation(
+            &pool,
+            &r.id,
+            ReservationStatus::Reserved,
+            ReservationStatus::Confirmed,
+            Some(at(10)?),
+        )
+        .await?;
+        assert!(!confirmed);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expire_reservations() -> anyhow::Result<()> {
+        let pool = init_db_pool("sqlite::memory:").await?;
+        setup_test_data(&pool).await?;
+        create_reservation(&pool, &reservation("r1", "AGI2510001", at(8)?)).await?;
+        create_reservation(&pool, &reservation("r2", "AGI2510002", at(9)?)).await?;
+
+        // r1 expires at 09:00, r2 at 10:00
+        assert_eq!(expire_reservations(&pool, at(9)?).await?, 1);
+        assert_eq!(expire_reservations(&pool, at(9)?).await?, 0);
+
+        let unused = list_unused_reservations(&pool).await?;
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].document_number, "AGI2510001");
+        assert_eq!(unused[0].status, ReservationStatus::Expired);
+
+        let active = list_reservations(&pool, Some(ReservationStatus::Reserved)).await?;
+        assert_eq!(active.len(), 1);
+        assert_eq!(list_reservations(&pool, None).await?.len(), 2);
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 //! Section storage operations
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::models::{DeptCode, Section, SectionCode};
+use crate::storage::db;
 use sqlx::SqlitePool;
 
 /// Create a new section
@@ -19,7 +20,11 @@ pub async fn create_section(pool: &SqlitePool, section: &Section) -> Result<()>
         dept_code
     )
     .execute(pool)
-    .await?;
+    .await
+    .map_err(db::on_unique_violation(|| Error::AlreadyExists {
+        entity: "Section",
+        key: code.clone(),
+    }))?;
 
     Ok(())
 }
@@ -1,7 +1,8 @@
 //! User storage operations
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::models::{DeptCode, Permissions, SectionCode, User, UserId};
+use crate::storage::db;
 use sqlx::SqlitePool;
 
 /// Create a new user
@@ -28,7 +29,11 @@ pub async fn create_user(pool: &SqlitePool, user: &User) -> Result<()> {
         can_read
     )
     .execute(pool)
-    .await?;
+    .await
+    .map_err(db::on_unique_violation(|| Error::AlreadyExists {
+        entity: "User",
+        key: user.id.0.clone(),
+    }))?;
 
     Ok(())
 }
@@ -1,14 +1,12 @@
 //! Input validation utilities
 
 use crate::error::{Error, Result};
-use std::path::Path;
+use crate::models::StoredPath;
 
-/// Validate that a path is absolute
-pub fn validate_absolute_path(path: &Path) -> Result<()> {
-    if !path.is_absolute() {
-        return Err(Error::RelativePathNotAllowed);
-    }
-    Ok(())
+/// Validate that a path is absolute (POSIX, drive-letter or UNC), whatever
+/// OS the server runs on
+pub fn validate_absolute_path(path: &StoredPath) -> Result<()> {
+    path.validate()
 }
 
 /// Validate document type code format (1-3 characters, can include multi-byte)
@@ -41,8 +39,10 @@ mod tests {
 
     #[test]
     fn test_validate_absolute_path() {
-        assert!(validate_absolute_path(Path::new("/absolute/path")).is_ok());
-        assert!(validate_absolute_path(Path::new("relative/path")).is_err());
+        assert!(validate_absolute_path(&StoredPath::new("/absolute/path")).is_ok());
+        assert!(validate_absolute_path(&StoredPath::new("C:\\Users\\docs")).is_ok());
+        assert!(validate_absolute_path(&StoredPath::new("\\\\server\\share\\docs")).is_ok());
+        assert!(validate_absolute_path(&StoredPath::new("relative/path")).is_err());
     }
 
     #[test]
@@ -16,12 +16,17 @@ export interface DocumentPath {
   updated_at: string;
   generated: boolean;
   deleted: boolean;
+  generation_rule_id: number | null;
+  /** Warnings from the type's manual number policy (manual creation only) */
+  warnings?: string[];
 }
 
 export interface CreateDocumentRequest {
   type_code: string;
   user_id: string;
   task_id?: string;
+  /** File extension for the type's path template (e.g. "pdf") */
+  extension?: string;
 }
 
 export interface CreateDocumentManualRequest {
@@ -32,6 +37,12 @@ export interface CreateDocumentManualRequest {
   task_id?: string;
 }
 
+export interface DocumentPreview {
+  document_number: string;
+  file_path: string;
+  scope_key: string;
+}
+
 export interface UpdatePathRequest {
   new_path: string;
 }
@@ -50,7 +61,33 @@ export async function createDocumentAuto(
 
   if (!response.ok) {
     const error = await response.json();
-    throw new Error(error.message || 'Failed to create document');
+    throw new Error(error.error || 'Failed to create document');
+  }
+
+  return response.json();
+}
+
+/**
+ * Preview the next auto-generated number (does not consume the counter)
+ */
+export async function previewDocument(
+  typeCode: string,
+  userId: string,
+  taskId?: string,
+  extension?: string
+): Promise<DocumentPreview> {
+  const params = new URLSearchParams({ type_code: typeCode, user_id: userId });
+  if (taskId) {
+    params.set('task_id', taskId);
+  }
+  if (extension) {
+    params.set('extension', extension);
+  }
+  const response = await fetch(`/api/documents/preview?${params}`);
+
+  if (!response.ok) {
+    const error = await response.json();
+    throw new Error(error.error || 'Failed to preview document number');
   }
 
   return response.json();
@@ -70,7 +107,7 @@ export async function createDocumentManual(
 
   if (!response.ok) {
     const error = await response.json();
-    throw new Error(error.message || 'Failed to create document');
+    throw new Error(error.error || 'Failed to create document');
   }
 
   return response.json();
@@ -96,6 +133,10 @@ export async function getDocumentByNumber(number: string): Promise<DocumentPath>
   const response = await fetch(`/api/documents/number/${encodeURIComponent(number)}`);
 
   if (!response.ok) {
+    const error = await response.json().catch(() => ({}));
+    if (error.suggestions?.length) {
+      throw new Error(`${error.error} (did you mean ${error.suggestions.join(', ')}?)`);
+    }
     throw new Error('Document not found');
   }
 
@@ -117,7 +158,7 @@ export async function updateDocumentPath(
 
   if (!response.ok) {
     const error = await response.json();
-    throw new Error(error.message || 'Failed to update document path');
+    throw new Error(error.error || 'Failed to update document path');
   }
 
   return response.json();
@@ -133,7 +174,7 @@ export async function deleteDocument(id: string): Promise<DocumentPath> {
 
   if (!response.ok) {
     const error = await response.json();
-    throw new Error(error.message || 'Failed to delete document');
+    throw new Error(error.error || 'Failed to delete document');
   }
 
   return response.json();
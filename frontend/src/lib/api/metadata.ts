@@ -9,6 +9,14 @@ export interface DocumentType {
   description: string;
   root_directory: string | null;
   active: boolean;
+  /** File path template for auto-generated documents */
+  path_template?: string | null;
+  /** What happens when two live documents share a file path */
+  path_uniqueness?: 'allow' | 'warn' | 'reject';
+  /** Whether file paths must lie under root_directory */
+  path_containment?: 'off' | 'warn' | 'strict';
+  /** File name pattern of files the watcher registers automatically */
+  watch_pattern?: string | null;
 }
 
 export async function fetchDepartments(): Promise<Department[]> {